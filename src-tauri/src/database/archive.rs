@@ -0,0 +1,315 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Serialize;
+
+use super::models::EntityKind;
+use super::pagination::{query_page_by, Page, PageRequest, SortKey};
+
+/// Kinds that can be archived, priorities, milestones, checklist items
+/// and time logs are only deleted.
+const ARCHIVABLE_KINDS: [EntityKind; 5] = [
+    EntityKind::Group,
+    EntityKind::Board,
+    EntityKind::State,
+    EntityKind::Task,
+    EntityKind::View,
+];
+
+/// Resume of an archived entity.
+#[derive(Debug, Serialize)]
+pub struct ArchivedItem {
+    kind: EntityKind,
+    id: i64,
+    name: String,
+    parent_id: Option<i64>,
+    position: u32,
+    archived_at: String,
+}
+
+/// Fails for kinds that can't be archived, before touching any table.
+fn check_archivable(kind: EntityKind) -> Result<()> {
+    if !ARCHIVABLE_KINDS.contains(&kind) {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "cannot archive {:?}",
+            kind
+        )));
+    }
+
+    Ok(())
+}
+
+/// Hide an entity from normal loads, keeping it in database.
+pub fn archive(conn: &Connection, kind: EntityKind, id: i64) -> Result<bool> {
+    check_archivable(kind)?;
+    let query = format!(
+        "UPDATE {} SET archived_at = datetime('now') WHERE id = ?1 AND archived_at IS NULL;",
+        kind.table()
+    );
+    let count = conn.execute(&query, params![id])?;
+
+    Ok(count > 0)
+}
+
+/// List a page of archived entities of given kind, or of every kind when
+/// missing. They are sorted by name with [`SortKey::Name`], or else most
+/// recently archived first.
+pub fn list_archived(
+    conn: &Connection,
+    kind: Option<EntityKind>,
+    request: &PageRequest,
+) -> Result<Page<ArchivedItem>> {
+    let query = ARCHIVABLE_KINDS
+        .iter()
+        .enumerate()
+        .filter(|(_, archivable)| kind.is_none_or(|kind| kind == **archivable))
+        .map(|(index, kind)| {
+            format!(
+                r#"SELECT {} AS kind, id, name, {} AS parent_id, position, archived_at
+                FROM {} WHERE archived_at IS NOT NULL"#,
+                index,
+                kind.parent_column().unwrap_or("NULL"),
+                kind.table()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" UNION ALL ");
+    let columns: &[&str] = match request.sort {
+        SortKey::Name => &["name COLLATE NOCASE", "kind", "id"],
+        _ => &["-julianday(archived_at)", "kind", "id"],
+    };
+
+    query_page_by(conn, &query, vec![], columns, request, |row| {
+        Ok(ArchivedItem {
+            kind: ARCHIVABLE_KINDS[row.get::<_, usize>("kind")?],
+            id: row.get("id")?,
+            name: row.get("name")?,
+            parent_id: row.get("parent_id")?,
+            position: row.get("position")?,
+            archived_at: row.get("archived_at")?,
+        })
+    })
+}
+
+/// Bring back an archived entity to its original position, moving
+/// down siblings that took the place meanwhile.
+pub fn restore(conn: &Connection, kind: EntityKind, id: i64) -> Result<bool> {
+    check_archivable(kind)?;
+    let parent = kind.parent_column().unwrap_or("NULL");
+    let query = format!(
+        "SELECT position, {} FROM {} WHERE id = ?1 AND archived_at IS NOT NULL;",
+        parent,
+        kind.table()
+    );
+    let Some((position, parent_id)) = conn
+        .query_row(&query, params![id], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, Option<i64>>(1)?))
+        })
+        .optional()?
+    else {
+        return Ok(false);
+    };
+
+    let siblings = format!(
+        "{} IS ?1 AND archived_at IS NULL AND position >= ?2",
+        parent
+    );
+    let occupied: bool = conn.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE {} AND position = ?2);",
+            kind.table(),
+            siblings
+        ),
+        params![parent_id, position],
+        |row| row.get(0),
+    )?;
+    if occupied {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET position = position + 1 WHERE {};",
+                kind.table(),
+                siblings
+            ),
+            params![parent_id, position],
+        )?;
+    }
+
+    let count = conn.execute(
+        &format!(
+            "UPDATE {} SET archived_at = NULL WHERE id = ?1;",
+            kind.table()
+        ),
+        params![id],
+    )?;
+
+    Ok(count > 0)
+}
+
+/// Permanently remove an archived entity and everything inside it.
+/// Entities not archived are left untouched.
+pub fn purge(conn: &Connection, kind: EntityKind, id: i64) -> Result<bool> {
+    check_archivable(kind)?;
+    let archived: bool = conn.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1 AND archived_at IS NOT NULL);",
+            kind.table()
+        ),
+        params![id],
+        |row| row.get(0),
+    )?;
+    if !archived {
+        return Ok(false);
+    }
+
+    delete_tree(conn, kind, id)?;
+
+    Ok(true)
+}
+
+fn delete_tree(conn: &Connection, kind: EntityKind, id: i64) -> Result<()> {
+    match kind {
+        EntityKind::Group => {
+            let mut stmt = conn.prepare("SELECT id FROM boards WHERE group_id = ?1;")?;
+            let boards = stmt
+                .query_map(params![id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<i64>>>()?;
+            for board in boards {
+                delete_tree(conn, EntityKind::Board, board)?;
+            }
+        }
+        EntityKind::Board => {
+            conn.execute("DELETE FROM tasks WHERE board_id = ?1;", params![id])?;
+            conn.execute("DELETE FROM states WHERE board_id = ?1;", params![id])?;
+            conn.execute("DELETE FROM priorities WHERE board_id = ?1;", params![id])?;
+            conn.execute("DELETE FROM milestones WHERE board_id = ?1;", params![id])?;
+        }
+        EntityKind::State => {
+            conn.execute("DELETE FROM tasks WHERE state_id = ?1;", params![id])?;
+        }
+        EntityKind::Task
+        | EntityKind::View
+        | EntityKind::Priority
+        | EntityKind::ChecklistItem
+        | EntityKind::TimeLog
+        | EntityKind::Milestone => {}
+    }
+
+    conn.execute(
+        &format!("DELETE FROM {} WHERE id = ?1;", kind.table()),
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use rusqlite::Connection;
+
+    use super::{archive, list_archived, purge, restore};
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::{EntityKind, State, Task};
+    use crate::database::pagination::{PageRequest, SortKey};
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id)
+                VALUES (1, 'Todo', 0, 1), (2, 'Doing', 1, 1), (3, 'Done', 2, 1);
+            INSERT INTO tasks(id, name, duration, priority, state_id, board_id, position)
+                VALUES (1, 'login', 30, 3, 2, 1, 0), (2, 'logout', 10, 1, 1, 1, 0);"#,
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_archived_are_hidden_from_loads() {
+        let conn = setup();
+
+        assert!(archive(&conn, EntityKind::State, 2).unwrap());
+        assert!(!archive(&conn, EntityKind::State, 2).unwrap());
+        for kind in [EntityKind::Priority, EntityKind::Milestone] {
+            assert_eq!(
+                format!("cannot archive {:?}", kind),
+                match archive(&conn, kind, 1) {
+                    Err(rusqlite::Error::InvalidParameterName(message)) => message,
+                    other => panic!("unexpected {:?}", other),
+                }
+            );
+        }
+        assert_eq!(2, State::load_by_board(&conn, 1).unwrap().len());
+        assert_eq!(1, Task::load_by_board(&conn, 1).unwrap().len());
+
+        let archived = list_archived(&conn, None, &PageRequest::default()).unwrap();
+        assert_eq!(1, archived.items.len());
+        assert_eq!(EntityKind::State, archived.items[0].kind);
+    }
+
+    #[test]
+    fn test_archived_pages() {
+        let conn = setup();
+        conn.execute_batch(
+            r#"UPDATE tasks SET archived_at = '2024-01-02 00:00:00' WHERE id = 1;
+            UPDATE tasks SET archived_at = '2024-01-01 00:00:00' WHERE id = 2;
+            UPDATE states SET archived_at = '2024-01-02 00:00:00' WHERE id = 3;"#,
+        )
+        .unwrap();
+        let mut request = PageRequest {
+            limit: Some(2),
+            ..PageRequest::default()
+        };
+
+        let page = list_archived(&conn, None, &request).unwrap();
+        let names: Vec<&str> = page.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(vec!["Done", "login"], names);
+        request.cursor = page.next_cursor;
+        let page = list_archived(&conn, None, &request).unwrap();
+        assert_eq!("logout", page.items[0].name);
+        assert!(page.next_cursor.is_none());
+
+        let request = PageRequest {
+            sort: SortKey::Name,
+            ..PageRequest::default()
+        };
+        let page = list_archived(&conn, Some(EntityKind::Task), &request).unwrap();
+        let names: Vec<&str> = page.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(vec!["login", "logout"], names);
+    }
+
+    #[test]
+    fn test_restore_to_original_position() {
+        let conn = setup();
+        archive(&conn, EntityKind::State, 2).unwrap();
+        conn.execute("UPDATE states SET position = 1 WHERE id = 3;", [])
+            .unwrap();
+
+        assert!(restore(&conn, EntityKind::State, 2).unwrap());
+        let names: Vec<String> = State::load_by_board(&conn, 1)
+            .unwrap()
+            .iter()
+            .map(|state| state.get_name().clone())
+            .collect();
+        assert_eq!(vec!["Todo", "Doing", "Done"], names);
+    }
+
+    #[test]
+    fn test_purge_only_archived() {
+        let conn = setup();
+
+        assert!(!purge(&conn, EntityKind::Board, 1).unwrap());
+        assert!(matches!(
+            purge(&conn, EntityKind::Priority, 1),
+            Err(rusqlite::Error::InvalidParameterName(_))
+        ));
+        archive(&conn, EntityKind::Board, 1).unwrap();
+        assert!(purge(&conn, EntityKind::Board, 1).unwrap());
+
+        let tasks: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tasks;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(0, tasks);
+    }
+}
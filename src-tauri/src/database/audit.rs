@@ -0,0 +1,280 @@
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::history::{Action, RowSnapshot};
+use super::models::{EntityKind, Priority};
+
+/// Where a change came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Origin {
+    #[default]
+    Gui,
+    Cli,
+    Automation,
+}
+
+/// A single field change of an entity.
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    id: i64,
+    kind: EntityKind,
+    entity_id: i64,
+    action: Action,
+    field: String,
+    old_value: Value,
+    new_value: Value,
+    origin: Origin,
+    changed_at: String,
+}
+
+impl AuditEntry {
+    /// Get name of the changed field.
+    pub fn get_field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get field value before the change.
+    pub fn get_old_value(&self) -> &Value {
+        &self.old_value
+    }
+
+    /// Get field value after the change.
+    pub fn get_new_value(&self) -> &Value {
+        &self.new_value
+    }
+
+    /// Get when the change happened, in UTC.
+    pub fn get_changed_at(&self) -> &str {
+        &self.changed_at
+    }
+
+    fn from_row(row: &Row) -> Result<AuditEntry> {
+        Ok(AuditEntry {
+            id: row.get("id")?,
+            kind: from_name(row.get("entity")?)?,
+            entity_id: row.get("entity_id")?,
+            action: from_name(row.get("action")?)?,
+            field: row.get("field")?,
+            old_value: from_text(row.get("old_value")?)?,
+            new_value: from_text(row.get("new_value")?)?,
+            origin: from_name(row.get("origin")?)?,
+            changed_at: row.get("changed_at")?,
+        })
+    }
+}
+
+/// Store one audit entry per field that differs between `before` and `after`.
+pub fn log(
+    conn: &Connection,
+    origin: Origin,
+    action: Action,
+    target: (EntityKind, i64),
+    before: Option<&RowSnapshot>,
+    after: Option<&RowSnapshot>,
+) -> Result<()> {
+    let mut fields: Vec<&String> = before
+        .into_iter()
+        .chain(after)
+        .flat_map(|row| row.keys())
+        .collect();
+    fields.sort();
+    fields.dedup();
+
+    for field in fields {
+        // identifiers and modification times are not changes by themselves.
+        if field == "id" || field == "updated_at" {
+            continue;
+        }
+        let old_value = before
+            .and_then(|row| row.get(field))
+            .unwrap_or(&Value::Null);
+        let new_value = after.and_then(|row| row.get(field)).unwrap_or(&Value::Null);
+        if old_value == new_value {
+            continue;
+        }
+
+        conn.execute(
+            r#"INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);"#,
+            params![
+                to_name(&target.0),
+                target.1,
+                to_name(&action),
+                field,
+                old_value.to_string(),
+                new_value.to_string(),
+                to_name(&origin),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Get change history of an entity, oldest first, optionally of a single field.
+pub fn entity_history(
+    conn: &Connection,
+    kind: EntityKind,
+    id: i64,
+    field: Option<&str>,
+) -> Result<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT * FROM audit_log
+        WHERE entity = ?1 AND entity_id = ?2 AND (?3 IS NULL OR field = ?3)
+        ORDER BY changed_at, id;"#,
+    )?;
+    let entries = stmt
+        .query_map(params![to_name(&kind), id, field], AuditEntry::from_row)?
+        .collect::<Result<Vec<AuditEntry>>>()?;
+
+    Ok(entries)
+}
+
+/// Find last change that set `field` of an entity to `value`.
+/// Task priorities can be given by name of a level in the task scheme, e.g. `"Now"`,
+/// unknown names match no change.
+pub fn changed_to(
+    conn: &Connection,
+    kind: EntityKind,
+    id: i64,
+    field: &str,
+    value: Value,
+) -> Result<Option<AuditEntry>> {
+    let value = match (kind, field, &value) {
+        (EntityKind::Task, "priority", Value::String(name)) => {
+            let priority: Option<i64> = conn
+                .query_row(
+                    &format!(
+                        r#"SELECT p.id FROM priorities p, tasks t
+                        WHERE t.id = ?1 AND p.name = ?2 COLLATE NOCASE AND {};"#,
+                        Priority::scheme_condition("p", "t.board_id")
+                    ),
+                    params![id, name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(priority) = priority else {
+                return Ok(None);
+            };
+            Value::from(priority)
+        }
+        _ => value,
+    };
+
+    conn.query_row(
+        r#"SELECT * FROM audit_log
+        WHERE entity = ?1 AND entity_id = ?2 AND field = ?3 AND new_value = ?4
+        ORDER BY changed_at DESC, id DESC LIMIT 1;"#,
+        params![to_name(&kind), id, field, value.to_string()],
+        AuditEntry::from_row,
+    )
+    .optional()
+}
+
+/// Find entities of `kind` that ever had `field` set to or from `value`,
+/// deleted ones included.
+pub fn entities_with(
+    conn: &Connection,
+    kind: EntityKind,
+    field: &str,
+    value: Value,
+) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT DISTINCT entity_id FROM audit_log
+        WHERE entity = ?1 AND field = ?2 AND (old_value = ?3 OR new_value = ?3)
+        ORDER BY entity_id;"#,
+    )?;
+    let ids = stmt
+        .query_map(params![to_name(&kind), field, value.to_string()], |row| {
+            row.get(0)
+        })?
+        .collect::<Result<Vec<i64>>>()?;
+
+    Ok(ids)
+}
+
+fn to_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn from_name<T: DeserializeOwned>(name: String) -> Result<T> {
+    serde_json::from_value(Value::String(name)).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+fn from_text(text: Option<String>) -> Result<Value> {
+    match text {
+        None => Ok(Value::Null),
+        Some(text) => serde_json::from_str(&text).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use rusqlite::Connection;
+    use serde_json::Value;
+
+    use super::{changed_to, entity_history, Origin};
+    use crate::database::fixtures::create_tables;
+    use crate::database::history::{record, Action};
+    use crate::database::models::EntityKind;
+
+    fn set_priority(conn: &Connection, origin: Origin, priority: i32) {
+        record(
+            conn,
+            origin,
+            Action::Update,
+            (EntityKind::Task, Some(1)),
+            &[(EntityKind::Task, 1)],
+            |conn| {
+                conn.execute("UPDATE tasks SET priority = ?1 WHERE id = 1;", [priority])?;
+                Ok(((), vec![]))
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_field_changes_are_logged() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            r#"INSERT INTO tasks(id, name, duration, priority, state_id, board_id, position)
+            VALUES (1, 'login', 30, 1, 1, 1, 0);"#,
+            [],
+        )
+        .unwrap();
+
+        set_priority(&conn, Origin::Gui, 4);
+        set_priority(&conn, Origin::Automation, 2);
+
+        let entries = entity_history(&conn, EntityKind::Task, 1, None).unwrap();
+        assert_eq!(2, entries.len());
+        assert!(entries.iter().all(|entry| entry.field == "priority"));
+        assert_eq!(Value::from(1), entries[0].old_value);
+        assert_eq!(Origin::Automation, entries[1].origin);
+
+        let entry = changed_to(&conn, EntityKind::Task, 1, "priority", Value::from("Now"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Origin::Gui, entry.origin);
+        assert!(
+            changed_to(&conn, EntityKind::Task, 1, "priority", Value::from("High"))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            changed_to(&conn, EntityKind::Task, 1, "priority", Value::from("Nope"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
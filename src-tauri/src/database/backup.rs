@@ -0,0 +1,447 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use super::migrations;
+
+/// Prefix of backup file names, `my-board-YYYYMMDD-HHMMSS-reason.db`.
+const PREFIX: &str = "my-board-";
+
+/// Tables a database must have to be restored.
+const REQUIRED_TABLES: [&str; 4] = ["groups", "boards", "states", "tasks"];
+
+/// Where backups go, how often they are taken and how many are kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub directory: String,
+    /// hours between scheduled backups, none when 0.
+    pub interval_hours: u32,
+    /// days whose last backup is kept.
+    pub daily: usize,
+    /// weeks whose last backup is kept.
+    pub weekly: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig {
+            directory: String::from("./.my-board-backups"),
+            interval_hours: 24,
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+impl BackupConfig {
+    /// Load the config saved at `path`, the default one when missing.
+    pub fn load(path: &str) -> BackupConfig {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the config at `path`.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+
+        std::fs::write(path, contents)
+    }
+}
+
+/// Why a backup was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupReason {
+    Startup,
+    Migration,
+    Scheduled,
+    Manual,
+    BeforeRestore,
+}
+
+impl BackupReason {
+    const ALL: [BackupReason; 5] = [
+        BackupReason::Startup,
+        BackupReason::Migration,
+        BackupReason::Scheduled,
+        BackupReason::Manual,
+        BackupReason::BeforeRestore,
+    ];
+
+    /// Get name used in backup file names.
+    fn slug(&self) -> &'static str {
+        match self {
+            BackupReason::Startup => "startup",
+            BackupReason::Migration => "migration",
+            BackupReason::Scheduled => "scheduled",
+            BackupReason::Manual => "manual",
+            BackupReason::BeforeRestore => "before-restore",
+        }
+    }
+}
+
+/// A backup file found in the backup directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackupInfo {
+    file: String,
+    /// `YYYYMMDD-HHMMSS`, in UTC.
+    taken_at: String,
+    reason: BackupReason,
+    size: u64,
+}
+
+impl BackupInfo {
+    /// Read a backup file name, `None` when it is not one.
+    fn from_name(file: &str, size: u64) -> Option<BackupInfo> {
+        let rest = file.strip_prefix(PREFIX)?.strip_suffix(".db")?;
+        let (taken_at, slug) = (rest.get(..15)?, rest.get(16..)?);
+        let valid = taken_at.char_indices().all(|(index, char)| match index {
+            8 => char == '-',
+            _ => char.is_ascii_digit(),
+        });
+        let reason = BackupReason::ALL
+            .into_iter()
+            .find(|reason| reason.slug() == slug)?;
+
+        valid.then(|| BackupInfo {
+            file: String::from(file),
+            taken_at: String::from(taken_at),
+            reason,
+            size,
+        })
+    }
+}
+
+/// Why a backup could not be taken or restored.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum BackupError {
+    /// the backup is not a database this app can restore.
+    Invalid {
+        problems: Vec<String>,
+    },
+    Io {
+        message: String,
+    },
+    Database {
+        message: String,
+    },
+}
+
+impl From<rusqlite::Error> for BackupError {
+    fn from(_: rusqlite::Error) -> Self {
+        BackupError::Database {
+            message: String::from("Transaction error!"),
+        }
+    }
+}
+
+impl From<std::io::Error> for BackupError {
+    fn from(error: std::io::Error) -> Self {
+        BackupError::Io {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Tell if `conn` holds any table, so there is something to back up.
+pub fn has_data(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table');",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Copy the database of `conn` into the backup directory with the online
+/// backup API, which is safe while the app keeps writing, then drop the
+/// backups the retention rules no longer keep.
+pub fn create(
+    conn: &Connection,
+    config: &BackupConfig,
+    reason: BackupReason,
+) -> Result<BackupInfo, BackupError> {
+    std::fs::create_dir_all(&config.directory)?;
+    let taken_at: String =
+        conn.query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now');", [], |row| {
+            row.get(0)
+        })?;
+    let file = format!("{}{}-{}.db", PREFIX, taken_at, reason.slug());
+    let path = Path::new(&config.directory).join(&file);
+    conn.backup(DatabaseName::Main, &path, None)?;
+    prune(conn, config)?;
+
+    Ok(BackupInfo {
+        size: std::fs::metadata(&path)?.len(),
+        file,
+        taken_at,
+        reason,
+    })
+}
+
+/// List backups of the backup directory, newest first.
+pub fn list(config: &BackupConfig) -> std::io::Result<Vec<BackupInfo>> {
+    let entries = match std::fs::read_dir(&config.directory) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+    let mut backups = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let size = entry.metadata()?.len();
+        if let Some(backup) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| BackupInfo::from_name(name, size))
+        {
+            backups.push(backup);
+        }
+    }
+    backups.sort_by(|a, b| b.taken_at.cmp(&a.taken_at).then(b.file.cmp(&a.file)));
+
+    Ok(backups)
+}
+
+/// Backups the retention rules keep: the last one of each of the last
+/// `daily` days and `weekly` weeks with backups, weeks starting on
+/// Monday. The newest backup is always kept.
+fn retained<'a>(
+    conn: &Connection,
+    backups: &'a [BackupInfo],
+    config: &BackupConfig,
+) -> rusqlite::Result<HashSet<&'a str>> {
+    let mut kept: HashSet<&str> = backups
+        .first()
+        .map(|backup| backup.file.as_str())
+        .into_iter()
+        .collect();
+    let mut day_seen = HashSet::new();
+    let mut week_seen = HashSet::new();
+    let mut stmt = conn.prepare("SELECT date(?1), date(?1, 'weekday 0', '-6 days');")?;
+
+    for backup in backups {
+        let taken_at = &backup.taken_at;
+        let date = format!("{}-{}-{}", &taken_at[..4], &taken_at[4..6], &taken_at[6..8]);
+        let (day, monday): (String, String) =
+            stmt.query_row([date], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        if day_seen.len() < config.daily && day_seen.insert(day) {
+            kept.insert(&backup.file);
+        }
+        if week_seen.len() < config.weekly && week_seen.insert(monday) {
+            kept.insert(&backup.file);
+        }
+    }
+
+    Ok(kept)
+}
+
+/// Delete backups the retention rules no longer keep.
+pub fn prune(conn: &Connection, config: &BackupConfig) -> Result<Vec<BackupInfo>, BackupError> {
+    let backups = list(config)?;
+    let kept = retained(conn, &backups, config)?;
+    let mut removed = vec![];
+    for backup in &backups {
+        if !kept.contains(backup.file.as_str()) {
+            std::fs::remove_file(Path::new(&config.directory).join(&backup.file))?;
+            removed.push(backup.clone());
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Tell if a scheduled backup is due: none was taken, of any kind, in
+/// the last `interval_hours`.
+pub fn is_due(conn: &Connection, config: &BackupConfig) -> Result<bool, BackupError> {
+    if config.interval_hours == 0 {
+        return Ok(false);
+    }
+    let since: String = conn.query_row(
+        "SELECT strftime('%Y%m%d-%H%M%S', 'now', ?1);",
+        [format!("-{} hours", config.interval_hours)],
+        |row| row.get(0),
+    )?;
+
+    Ok(list(config)?
+        .first()
+        .is_none_or(|newest| newest.taken_at <= since))
+}
+
+/// Path of backup `file`, which must be one of the backup directory.
+pub fn path(config: &BackupConfig, file: &str) -> Result<PathBuf, BackupError> {
+    match list(config)?.iter().any(|backup| backup.file == file) {
+        true => Ok(Path::new(&config.directory).join(file)),
+        false => Err(BackupError::Invalid {
+            problems: vec![format!("backup \"{}\" not found", file)],
+        }),
+    }
+}
+
+/// Check the database at `path` can be restored: it must pass SQLite
+/// integrity check, have the tables of this app and a schema version
+/// this app can migrate from.
+pub fn validate(path: &Path) -> Vec<String> {
+    let conn = match Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(conn) => conn,
+        Err(error) => return vec![error.to_string()],
+    };
+    let mut problems = vec![];
+
+    let integrity = conn
+        .prepare("PRAGMA integrity_check;")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        });
+    match integrity {
+        Ok(messages) if messages == ["ok"] => {}
+        Ok(messages) => problems.extend(messages),
+        Err(error) => {
+            problems.push(error.to_string());
+            return problems;
+        }
+    }
+
+    for table in REQUIRED_TABLES {
+        let found: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1);",
+                [table],
+                |row| row.get(0),
+            )
+            .unwrap_or(false);
+        if !found {
+            problems.push(format!("table \"{}\" is missing", table));
+        }
+    }
+    match migrations::current_version(&conn) {
+        Ok(version) if version > migrations::latest_version() => problems.push(format!(
+            "schema version {} is newer than this app, {}",
+            version,
+            migrations::latest_version()
+        )),
+        Ok(_) => {}
+        Err(error) => problems.push(error.to_string()),
+    }
+
+    problems
+}
+
+/// Replace the database of `conn` with the backup at `path`, page by
+/// page through the backup API, once it is validated.
+pub fn restore(conn: &mut Connection, path: &Path) -> Result<(), BackupError> {
+    let problems = validate(path);
+    if !problems.is_empty() {
+        return Err(BackupError::Invalid { problems });
+    }
+    conn.restore(
+        DatabaseName::Main,
+        path,
+        None::<fn(rusqlite::backup::Progress)>,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod backup_tests {
+    use rusqlite::Connection;
+
+    use super::{
+        create, is_due, list, path, restore, retained, validate, BackupConfig, BackupInfo,
+        BackupReason,
+    };
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let directory =
+            std::env::temp_dir().join(format!("my-board-backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config = BackupConfig {
+            directory: directory.to_string_lossy().to_string(),
+            ..BackupConfig::default()
+        };
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);"#)
+            .unwrap();
+
+        assert!(is_due(&conn, &config).unwrap());
+        let backup = create(&conn, &config, BackupReason::Manual).unwrap();
+        assert!(!is_due(&conn, &config).unwrap());
+        assert_eq!(vec![backup.clone()], list(&config).unwrap());
+
+        conn.execute_batch("UPDATE groups SET name = 'home';")
+            .unwrap();
+        let file = path(&config, &backup.file).unwrap();
+        assert!(validate(&file).is_empty());
+        restore(&mut conn, &file).unwrap();
+        let name: String = conn
+            .query_row("SELECT name FROM groups WHERE id = 1;", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!("work", name);
+
+        let broken = directory.join("broken.db");
+        std::fs::write(&broken, "not a database").unwrap();
+        assert!(!validate(&broken).is_empty());
+        assert!(restore(&mut conn, &broken).is_err());
+        assert!(path(&config, "../broken.db").is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_retention_keeps_last_of_days_and_weeks() {
+        // 2024-05-13 is a Monday.
+        let backups: Vec<BackupInfo> = [
+            "20240515-180000-scheduled",
+            "20240515-090000-startup",
+            "20240514-090000-startup",
+            "20240513-090000-startup",
+            "20240512-090000-startup",
+            "20240510-090000-startup",
+            "20240503-090000-startup",
+            "20240426-090000-startup",
+        ]
+        .iter()
+        .filter_map(|name| BackupInfo::from_name(&format!("my-board-{}.db", name), 0))
+        .collect();
+        assert_eq!(8, backups.len());
+        assert!(BackupInfo::from_name("my-board-2024-manual.db", 0).is_none());
+
+        let config = BackupConfig {
+            daily: 2,
+            weekly: 3,
+            ..BackupConfig::default()
+        };
+        let conn = Connection::open_in_memory().unwrap();
+        let mut kept: Vec<&str> = retained(&conn, &backups, &config)
+            .unwrap()
+            .into_iter()
+            .collect();
+        kept.sort();
+        assert_eq!(
+            vec![
+                "my-board-20240503-090000-startup.db",
+                "my-board-20240512-090000-startup.db",
+                "my-board-20240514-090000-startup.db",
+                "my-board-20240515-180000-scheduled.db",
+            ],
+            kept
+        );
+
+        let none = BackupConfig {
+            daily: 0,
+            weekly: 0,
+            ..BackupConfig::default()
+        };
+        assert_eq!(1, retained(&conn, &backups, &none).unwrap().len());
+    }
+}
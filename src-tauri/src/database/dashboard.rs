@@ -0,0 +1,178 @@
+use rusqlite::{Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use super::models::{Priority, TaskSummary};
+
+/// Why a task is listed on the dashboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Reason {
+    /// task has the most urgent [`Priority`] of its board scheme.
+    Now,
+    Overdue,
+    DueToday,
+    /// task was started and not ended yet.
+    Running,
+    /// task is in a state being worked on.
+    Active,
+}
+
+/// Weights summed into the urgency score of each dashboard entry.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UrgencyWeights {
+    /// multiplied by priority rank, from 1 (low) to 4 (now) on default scheme.
+    pub priority: f64,
+    pub now: f64,
+    pub overdue: f64,
+    /// added for each day past due date, up to `max_overdue_days`.
+    pub overdue_per_day: f64,
+    pub max_overdue_days: u32,
+    pub due_today: f64,
+    pub running: f64,
+    pub active: f64,
+}
+
+impl Default for UrgencyWeights {
+    fn default() -> Self {
+        UrgencyWeights {
+            priority: 1.0,
+            now: 5.0,
+            overdue: 4.0,
+            overdue_per_day: 0.5,
+            max_overdue_days: 14,
+            due_today: 3.0,
+            running: 3.0,
+            active: 1.0,
+        }
+    }
+}
+
+/// A task that matters today, with its group and board.
+#[derive(Debug, Serialize)]
+pub struct DashboardEntry {
+    #[serde(flatten)]
+    task: TaskSummary,
+    score: f64,
+    reasons: Vec<Reason>,
+}
+
+/// List not finished tasks across every group that are urgent, due
+/// today or overdue, running or in active states, most urgent first.
+pub fn today(conn: &Connection, weights: &UrgencyWeights) -> Result<Vec<DashboardEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT * FROM (
+            SELECT summary.*
+                , CAST(julianday(date('now', 'localtime')) - julianday(date(summary.due_at)) AS INTEGER)
+                    AS days_overdue
+                , summary.started_at IS NOT NULL AND summary.ended_at IS NULL AS running
+                , summary.priority_rank = (
+                    SELECT MAX(p.rank) FROM priorities p WHERE {}
+                ) AS top_priority
+            FROM ({} WHERE {} AND s.done = 0) summary
+        )
+        WHERE top_priority
+            OR days_overdue >= 0
+            OR running
+            OR state_active;"#,
+        Priority::scheme_condition("p", "summary.board_id"),
+        TaskSummary::SELECT,
+        TaskSummary::NOT_ARCHIVED
+    ))?;
+    let mut entries = stmt
+        .query_map([], |row| {
+            let task = TaskSummary::from_row(row)?;
+            let days_overdue: Option<i64> = row.get("days_overdue")?;
+            let running: bool = row.get("running")?;
+            let top_priority: Option<bool> = row.get("top_priority")?;
+            let mut reasons = vec![];
+            let mut score = weights.priority * task.priority_rank.unwrap_or_default() as f64;
+
+            if top_priority.unwrap_or_default() {
+                reasons.push(Reason::Now);
+                score += weights.now;
+            }
+            match days_overdue {
+                Some(0) => {
+                    reasons.push(Reason::DueToday);
+                    score += weights.due_today;
+                }
+                Some(days) if days > 0 => {
+                    reasons.push(Reason::Overdue);
+                    score += weights.overdue
+                        + weights.overdue_per_day
+                            * days.min(weights.max_overdue_days as i64) as f64;
+                }
+                _ => {}
+            }
+            if running {
+                reasons.push(Reason::Running);
+                score += weights.running;
+            }
+            if task.state_active {
+                reasons.push(Reason::Active);
+                score += weights.active;
+            }
+
+            Ok(DashboardEntry {
+                task,
+                score,
+                reasons,
+            })
+        })?
+        .collect::<Result<Vec<DashboardEntry>>>()?;
+
+    entries.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.task.due_at.cmp(&b.task.due_at))
+            .then_with(|| a.task.id.cmp(&b.task.id))
+    });
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod dashboard_tests {
+    use rusqlite::Connection;
+
+    use super::{today, Reason, UrgencyWeights};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_today_across_groups() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0), (2, 'home', 1);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1), (2, 'house', 0, 2);
+            INSERT INTO states(id, name, position, board_id, active, done)
+                VALUES (1, 'Todo', 0, 1, 0, 0), (2, 'Doing', 1, 1, 1, 0), (3, 'Done', 2, 1, 0, 1)
+                    , (4, 'Todo', 0, 2, 0, 0);
+            INSERT INTO tasks(name, duration, priority, state_id, board_id, position, due_at, started_at)
+                VALUES ('urgent', 1, 4, 1, 1, 0, NULL, NULL)
+                    , ('late', 1, 2, 4, 2, 0, date('now', 'localtime', '-3 days'), NULL)
+                    , ('today', 1, 2, 4, 2, 1, date('now', 'localtime'), NULL)
+                    , ('timer', 1, 1, 1, 1, 1, NULL, datetime('now'))
+                    , ('doing', 1, 1, 2, 1, 0, NULL, NULL)
+                    , ('someday', 1, 3, 1, 1, 2, date('now', 'localtime', '+3 days'), NULL)
+                    , ('finished', 1, 4, 3, 1, 0, NULL, NULL);"#,
+        )
+        .unwrap();
+
+        let entries = today(&conn, &UrgencyWeights::default()).unwrap();
+        let names: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.task.name.as_str())
+            .collect();
+        assert_eq!(vec!["urgent", "late", "today", "timer", "doing"], names);
+        assert_eq!("home", entries[1].task.group_name);
+        assert_eq!(vec![Reason::Overdue], entries[1].reasons);
+        assert_eq!(2.0 + 4.0 + 1.5, entries[1].score);
+
+        let weights = UrgencyWeights {
+            running: 20.0,
+            ..UrgencyWeights::default()
+        };
+        assert_eq!("timer", today(&conn, &weights).unwrap()[0].task.name);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+/// options used when deep-copying a board or a group.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateOptions {
+    /// copy board tasks too, otherwise only states are copied.
+    pub include_tasks: bool,
+    /// clear tasks progress on the copy.
+    pub reset_progress: bool,
+    /// clear tasks started_at and ended_at on the copy. Due dates are
+    /// kept, they are part of the plan being copied.
+    pub reset_timestamps: bool,
+    /// [`Group`](super::models::Group) that receives the board copy.
+    /// when empty, the copy stays in the original group. Not used by
+    /// [`duplicate_group`], which always copies into a new group.
+    pub target_group_id: Option<i64>,
+    /// name of the copy, when empty the original name is kept.
+    pub name: Option<String>,
+}
+
+/// Copy board `board_id`, its states, its own priorities, milestones and
+/// (optionally) its tasks with their labels, due dates, estimates,
+/// subtasks and checklists. Time logs are not copied, nor archived
+/// states and tasks, tasks in archived states included.
+/// Returns the new board identifier.
+pub fn duplicate_board(
+    conn: &Connection,
+    board_id: i64,
+    options: &DuplicateOptions,
+) -> Result<i64> {
+    let (name, group_id): (String, i64) = conn.query_row(
+        r#"SELECT name, group_id FROM boards WHERE id = ?1;"#,
+        params![board_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let group_id = options.target_group_id.unwrap_or(group_id);
+    let name = options.name.clone().unwrap_or(name);
+
+    copy_board(conn, board_id, group_id, name, options)
+}
+
+/// Copy group `group_id` with all its not archived boards, each one like
+/// [`duplicate_board`] does, into a new group placed last.
+/// `options.target_group_id` is not used.
+/// Returns the new group identifier.
+pub fn duplicate_group(
+    conn: &Connection,
+    group_id: i64,
+    options: &DuplicateOptions,
+) -> Result<i64> {
+    let (name, icon): (String, Option<String>) = conn.query_row(
+        r#"SELECT name, icon FROM groups WHERE id = ?1;"#,
+        params![group_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let position = next_position(conn, r#"SELECT MAX(position) FROM groups;"#, params![])?;
+
+    conn.execute(
+        r#"INSERT INTO groups(name, icon, position) VALUES (?1, ?2, ?3);"#,
+        params![options.name.clone().unwrap_or(name), icon, position],
+    )?;
+    let new_group_id = conn.last_insert_rowid();
+
+    let mut stmt = conn.prepare(
+        r#"SELECT id, name FROM boards
+        WHERE group_id = ?1 AND archived_at IS NULL ORDER BY position;"#,
+    )?;
+    let boards = stmt
+        .query_map(params![group_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (board_id, board_name) in boards {
+        copy_board(conn, board_id, new_group_id, board_name, options)?;
+    }
+
+    Ok(new_group_id)
+}
+
+fn copy_board(
+    conn: &Connection,
+    board_id: i64,
+    group_id: i64,
+    name: String,
+    options: &DuplicateOptions,
+) -> Result<i64> {
+    let position = next_position(
+        conn,
+        r#"SELECT MAX(position) FROM boards WHERE group_id = ?1;"#,
+        params![group_id],
+    )?;
+    conn.execute(
+        r#"INSERT INTO boards(
+            name, position, group_id, hours_per_day, days_per_week, progress_strategy
+        ) SELECT ?1, ?2, ?3, hours_per_day, days_per_week, progress_strategy
+        FROM boards WHERE id = ?4;"#,
+        params![name, position, group_id, board_id],
+    )?;
+    let new_board_id = conn.last_insert_rowid();
+
+    let states = copy_states(conn, board_id, new_board_id)?;
+    let priorities = copy_priorities(conn, board_id, new_board_id)?;
+    let milestones = copy_milestones(conn, board_id, new_board_id)?;
+    if options.include_tasks {
+        let parents = Parents {
+            states: &states,
+            priorities: &priorities,
+            milestones: &milestones,
+        };
+        copy_tasks(conn, board_id, new_board_id, &parents, options)?;
+    }
+
+    Ok(new_board_id)
+}
+
+/// copy states and returns a map from old state ids to the new ones.
+fn copy_states(conn: &Connection, board_id: i64, new_board_id: i64) -> Result<HashMap<i64, i64>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, name, color, position, active, done FROM states
+        WHERE board_id = ?1 AND archived_at IS NULL ORDER BY position;"#,
+    )?;
+    let rows = stmt
+        .query_map(params![board_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, u32>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, bool>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut states = HashMap::new();
+    for (id, name, color, position, active, done) in rows {
+        conn.execute(
+            r#"INSERT INTO states(name, color, position, board_id, active, done)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6);"#,
+            params![name, color, position, new_board_id, active, done],
+        )?;
+        states.insert(id, conn.last_insert_rowid());
+    }
+
+    Ok(states)
+}
+
+/// copy board own priority scheme and returns a map from old priority
+/// ids to the new ones. Boards using workspace priorities get an empty map.
+fn copy_priorities(
+    conn: &Connection,
+    board_id: i64,
+    new_board_id: i64,
+) -> Result<HashMap<i64, i64>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, name, color, rank, icon FROM priorities WHERE board_id = ?1 ORDER BY id;"#,
+    )?;
+    let rows = stmt
+        .query_map(params![board_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut priorities = HashMap::new();
+    for (id, name, color, rank, icon) in rows {
+        conn.execute(
+            r#"INSERT INTO priorities(name, color, rank, icon, board_id)
+            VALUES (?1, ?2, ?3, ?4, ?5);"#,
+            params![name, color, rank, icon, new_board_id],
+        )?;
+        priorities.insert(id, conn.last_insert_rowid());
+    }
+
+    Ok(priorities)
+}
+
+/// copy board milestones and returns a map from old milestone ids to the new ones.
+fn copy_milestones(
+    conn: &Connection,
+    board_id: i64,
+    new_board_id: i64,
+) -> Result<HashMap<i64, i64>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, name, due_at, position FROM milestones WHERE board_id = ?1 ORDER BY position;"#,
+    )?;
+    let rows = stmt
+        .query_map(params![board_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, u32>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut milestones = HashMap::new();
+    for (id, name, due_at, position) in rows {
+        conn.execute(
+            r#"INSERT INTO milestones(name, due_at, position, board_id) VALUES (?1, ?2, ?3, ?4);"#,
+            params![name, due_at, position, new_board_id],
+        )?;
+        milestones.insert(id, conn.last_insert_rowid());
+    }
+
+    Ok(milestones)
+}
+
+/// maps from old ids to the new ones of entities referenced by tasks.
+struct Parents<'a> {
+    states: &'a HashMap<i64, i64>,
+    priorities: &'a HashMap<i64, i64>,
+    milestones: &'a HashMap<i64, i64>,
+}
+
+fn copy_tasks(
+    conn: &Connection,
+    board_id: i64,
+    new_board_id: i64,
+    parents: &Parents,
+    options: &DuplicateOptions,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"SELECT name, description, duration, progress, priority
+            , state_id, position, started_at, ended_at, due_at, labels, story_points
+            , id, parent_id, milestone_id
+        FROM tasks WHERE board_id = ?1 AND archived_at IS NULL
+        ORDER BY state_id, position;"#,
+    )?;
+    let mut rows = stmt.query(params![board_id])?;
+    let mut tasks = HashMap::new();
+    let mut subtasks = vec![];
+
+    while let Some(row) = rows.next()? {
+        let state_id: i64 = row.get(5)?;
+        // tasks in archived states, or pointing to states from other
+        // boards, are not copied.
+        let Some(new_state_id) = parents.states.get(&state_id) else {
+            continue;
+        };
+        let progress: Option<f32> = if options.reset_progress {
+            None
+        } else {
+            row.get(3)?
+        };
+        let (started_at, ended_at): (Option<String>, Option<String>) = if options.reset_timestamps
+        {
+            (None, None)
+        } else {
+            (row.get(7)?, row.get(8)?)
+        };
+        let priority: Option<i64> = row.get(4)?;
+        let priority = priority.map(|id| *parents.priorities.get(&id).unwrap_or(&id));
+        let milestone: Option<i64> = row.get(14)?;
+        let milestone = milestone.and_then(|id| parents.milestones.get(&id).copied());
+
+        conn.execute(
+            r#"INSERT INTO tasks(
+                name, description, duration, progress, priority
+                , state_id, board_id, position, started_at, ended_at
+                , due_at, labels, story_points, milestone_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14);"#,
+            params![
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<u32>>(2)?,
+                progress,
+                priority,
+                new_state_id,
+                new_board_id,
+                row.get::<_, u32>(6)?,
+                started_at,
+                ended_at,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, String>(10)?,
+                row.get::<_, Option<f32>>(11)?,
+                milestone,
+            ],
+        )?;
+        let new_task_id = conn.last_insert_rowid();
+        tasks.insert(row.get::<_, i64>(12)?, new_task_id);
+        if let Some(parent_id) = row.get::<_, Option<i64>>(13)? {
+            subtasks.push((new_task_id, parent_id));
+        }
+    }
+
+    for (new_task_id, parent_id) in subtasks {
+        conn.execute(
+            r#"UPDATE tasks SET parent_id = ?1 WHERE id = ?2;"#,
+            params![tasks.get(&parent_id), new_task_id],
+        )?;
+    }
+
+    copy_checklists(conn, &tasks, options)
+}
+
+/// copy checklist items of copied `tasks`, unchecked when progress is reset.
+fn copy_checklists(
+    conn: &Connection,
+    tasks: &HashMap<i64, i64>,
+    options: &DuplicateOptions,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"SELECT name, done, position FROM checklist_items WHERE task_id = ?1 ORDER BY position;"#,
+    )?;
+
+    for (task_id, new_task_id) in tasks {
+        let items = stmt
+            .query_map(params![task_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, u32>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        for (name, done, position) in items {
+            conn.execute(
+                r#"INSERT INTO checklist_items(name, done, position, task_id)
+                VALUES (?1, ?2, ?3, ?4);"#,
+                params![name, done && !options.reset_progress, position, new_task_id],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// next free position after the last one returned by `query`.
+fn next_position<P: rusqlite::Params>(conn: &Connection, query: &str, params: P) -> Result<u32> {
+    let last: Option<u32> = conn
+        .query_row(query, params, |row| row.get(0))
+        .optional()?
+        .flatten();
+
+    Ok(last.map_or(0, |position| position + 1))
+}
+
+#[cfg(test)]
+mod duplicate_tests {
+    use rusqlite::{params, Connection};
+
+    use super::{duplicate_board, duplicate_group, DuplicateOptions};
+    use crate::database::fixtures::create_tables;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0), (2, 'home', 1);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1), (2, 'Done', 1, 1);
+            INSERT INTO tasks(name, duration, progress, priority, state_id, board_id, position, started_at)
+                VALUES ('login', 30, 0.5, 3, 1, 1, 0, '2024-01-01 10:00:00')
+                    , ('logout', 10, 1.0, 1, 2, 1, 0, NULL);
+            UPDATE tasks SET parent_id = 1 WHERE name = 'logout';
+            INSERT INTO checklist_items(name, done, position, task_id) VALUES ('form', 1, 0, 1);"#,
+        )
+        .unwrap();
+
+        conn
+    }
+
+    fn count(conn: &Connection, query: &str, id: i64) -> i64 {
+        conn.query_row(query, params![id], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_board_without_tasks() {
+        let conn = setup();
+        let id = duplicate_board(&conn, 1, &DuplicateOptions::default()).unwrap();
+
+        assert_ne!(1, id);
+        assert_eq!(2, count(&conn, "SELECT COUNT(*) FROM states WHERE board_id = ?1", id));
+        assert_eq!(0, count(&conn, "SELECT COUNT(*) FROM tasks WHERE board_id = ?1", id));
+        assert_eq!(1, count(&conn, "SELECT position FROM boards WHERE id = ?1", id));
+    }
+
+    #[test]
+    fn test_duplicate_board_with_tasks_into_other_group() {
+        let conn = setup();
+        let options = DuplicateOptions {
+            include_tasks: true,
+            reset_progress: true,
+            reset_timestamps: true,
+            target_group_id: Some(2),
+            name: Some(String::from("app copy")),
+        };
+        let id = duplicate_board(&conn, 1, &options).unwrap();
+
+        assert_eq!(2, count(&conn, "SELECT group_id FROM boards WHERE id = ?1", id));
+        assert_eq!(0, count(&conn, "SELECT position FROM boards WHERE id = ?1", id));
+        assert_eq!(2, count(&conn, "SELECT COUNT(*) FROM tasks WHERE board_id = ?1", id));
+        assert_eq!(
+            0,
+            count(
+                &conn,
+                r#"SELECT COUNT(*) FROM tasks t JOIN states s ON s.id = t.state_id
+                WHERE t.board_id = ?1 AND (s.board_id <> t.board_id
+                    OR t.progress IS NOT NULL OR t.started_at IS NOT NULL)"#,
+                id
+            )
+        );
+        assert_eq!(
+            1,
+            count(
+                &conn,
+                r#"SELECT COUNT(*) FROM tasks t JOIN tasks parent ON parent.id = t.parent_id
+                WHERE t.board_id = ?1 AND parent.board_id = ?1"#,
+                id
+            )
+        );
+        assert_eq!(
+            1,
+            count(
+                &conn,
+                r#"SELECT COUNT(*) FROM checklist_items c JOIN tasks t ON t.id = c.task_id
+                WHERE t.board_id = ?1 AND c.done = 0"#,
+                id
+            )
+        );
+    }
+
+    #[test]
+    fn test_duplicate_group() {
+        let conn = setup();
+        let options = DuplicateOptions {
+            include_tasks: true,
+            ..Default::default()
+        };
+        let id = duplicate_group(&conn, 1, &options).unwrap();
+
+        assert_eq!(2, count(&conn, "SELECT position FROM groups WHERE id = ?1", id));
+        assert_eq!(1, count(&conn, "SELECT COUNT(*) FROM boards WHERE group_id = ?1", id));
+        assert_eq!(
+            2,
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM tasks t JOIN boards b ON b.id = t.board_id WHERE b.group_id = ?1",
+                id
+            )
+        );
+    }
+
+    #[test]
+    fn test_duplicate_board_leaves_out_archived_states() {
+        let conn = setup();
+        conn.execute("UPDATE states SET archived_at = datetime('now') WHERE id = 2;", [])
+            .unwrap();
+        let options = DuplicateOptions {
+            include_tasks: true,
+            ..Default::default()
+        };
+        let id = duplicate_board(&conn, 1, &options).unwrap();
+
+        assert_eq!(1, count(&conn, "SELECT COUNT(*) FROM states WHERE board_id = ?1", id));
+        assert_eq!(1, count(&conn, "SELECT COUNT(*) FROM tasks WHERE board_id = ?1", id));
+    }
+}
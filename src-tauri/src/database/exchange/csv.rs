@@ -0,0 +1,713 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::filter::{self, FilterError, Scope};
+use crate::database::history::Target;
+use crate::database::models::{
+    Board, DurationInput, EntityKind, ModelQueryBuilder, Priority, State, Task, TaskSummary,
+};
+
+/// Task fields that can be written to, or read from, a CSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    Id,
+    Name,
+    Description,
+    Group,
+    Board,
+    State,
+    Priority,
+    /// estimate in minutes, `1d 2h` like estimates are read too.
+    Duration,
+    StoryPoints,
+    /// from 0.0 to 1.0, percentages like `50%` are read too.
+    Progress,
+    /// separated by commas.
+    Labels,
+    DueAt,
+    StartedAt,
+    EndedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Columns exported when none is asked.
+pub const DEFAULT_COLUMNS: [Column; 8] = [
+    Column::Name,
+    Column::State,
+    Column::Priority,
+    Column::Duration,
+    Column::Progress,
+    Column::DueAt,
+    Column::StartedAt,
+    Column::EndedAt,
+];
+
+const ALL_COLUMNS: [Column; 16] = [
+    Column::Id,
+    Column::Name,
+    Column::Description,
+    Column::Group,
+    Column::Board,
+    Column::State,
+    Column::Priority,
+    Column::Duration,
+    Column::StoryPoints,
+    Column::Progress,
+    Column::Labels,
+    Column::DueAt,
+    Column::StartedAt,
+    Column::EndedAt,
+    Column::CreatedAt,
+    Column::UpdatedAt,
+];
+
+impl Column {
+    /// Header written on export.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Name => "name",
+            Column::Description => "description",
+            Column::Group => "group",
+            Column::Board => "board",
+            Column::State => "state",
+            Column::Priority => "priority",
+            Column::Duration => "duration",
+            Column::StoryPoints => "story_points",
+            Column::Progress => "progress",
+            Column::Labels => "labels",
+            Column::DueAt => "due_at",
+            Column::StartedAt => "started_at",
+            Column::EndedAt => "ended_at",
+            Column::CreatedAt => "created_at",
+            Column::UpdatedAt => "updated_at",
+        }
+    }
+
+    /// Column for a header of an imported file, by its exported name or
+    /// a common alias like `title` or `status`.
+    pub fn from_header(header: &str) -> Option<Column> {
+        let header = header.trim().to_lowercase().replace([' ', '-'], "_");
+        let column = match header.as_str() {
+            "title" | "summary" | "task" => Column::Name,
+            "status" | "column" => Column::State,
+            "estimate" => Column::Duration,
+            "points" => Column::StoryPoints,
+            "tags" => Column::Labels,
+            "due" | "due_date" => Column::DueAt,
+            _ => *ALL_COLUMNS
+                .iter()
+                .find(|column| column.header() == header)?,
+        };
+
+        Some(column)
+    }
+
+    fn value(&self, task: &TaskSummary) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self {
+            Column::Id => task.id.to_string(),
+            Column::Name => task.name.clone(),
+            Column::Description => text(&task.description),
+            Column::Group => task.group_name.clone(),
+            Column::Board => task.board_name.clone(),
+            Column::State => task.state_name.clone(),
+            Column::Priority => text(&task.priority_name),
+            Column::Duration => task
+                .duration
+                .map(|minutes| minutes.to_string())
+                .unwrap_or_default(),
+            Column::StoryPoints => task
+                .story_points
+                .map(|points| points.to_string())
+                .unwrap_or_default(),
+            Column::Progress => task
+                .progress
+                .map(|progress| progress.to_string())
+                .unwrap_or_default(),
+            Column::Labels => task.labels.join(", "),
+            Column::DueAt => text(&task.due_at),
+            Column::StartedAt => text(&task.started_at),
+            Column::EndedAt => text(&task.ended_at),
+            Column::CreatedAt => text(&task.created_at),
+            Column::UpdatedAt => text(&task.updated_at),
+        }
+    }
+}
+
+/// Tasks and columns of a CSV export.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CsvExport {
+    pub scope: Scope,
+    /// filter query, as typed in the filter bar.
+    pub filter: String,
+    /// [`DEFAULT_COLUMNS`] when empty.
+    pub columns: Vec<Column>,
+}
+
+/// How a CSV file is imported into a board.
+#[derive(Debug, Deserialize)]
+pub struct CsvImport {
+    pub board_id: i64,
+    /// field read from each file column, by position. Columns mapped to
+    /// `None` are skipped. When missing, columns are mapped by header.
+    #[serde(default)]
+    pub mapping: Option<Vec<Option<Column>>>,
+    /// check every row without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A record of a CSV file with the line it starts at.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// A problem found in a row of an imported file.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RowError {
+    line: usize,
+    column: Option<String>,
+    message: String,
+}
+
+impl RowError {
+    pub fn new(line: usize, column: Option<&str>, message: String) -> RowError {
+        RowError {
+            line,
+            column: column.map(String::from),
+            message,
+        }
+    }
+
+    /// Get line of the row.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// First rows of a CSV file, with the mapping suggested for its columns.
+#[derive(Debug, Serialize)]
+pub struct CsvPreview {
+    headers: Vec<String>,
+    mapping: Vec<Option<Column>>,
+    rows: Vec<Vec<String>>,
+}
+
+/// What a CSV import did, or would do on dry-run. Rows with errors are
+/// skipped, the others are imported.
+#[derive(Debug, Serialize)]
+pub struct CsvImportReport {
+    dry_run: bool,
+    imported: usize,
+    created_states: Vec<String>,
+    errors: Vec<RowError>,
+}
+
+/// Split CSV `text` in records. Fields may be quoted, with doubled quotes
+/// inside, and span several lines. Blank lines are skipped.
+pub fn parse_records(text: &str) -> std::result::Result<Vec<Record>, RowError> {
+    let mut records = vec![];
+    let mut fields: Vec<String> = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if in_quotes {
+            match char {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if char == '\n' {
+                        line += 1;
+                    }
+                    field.push(char);
+                }
+            }
+            continue;
+        }
+
+        match char {
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                in_quotes = true;
+            }
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if fields.len() > 1 || !fields[0].is_empty() || quoted {
+                    records.push(Record {
+                        line: start,
+                        fields: std::mem::take(&mut fields),
+                    });
+                }
+                fields.clear();
+                quoted = false;
+                line += 1;
+                start = line;
+            }
+            _ => field.push(char),
+        }
+    }
+    if in_quotes {
+        return Err(RowError::new(
+            start,
+            None,
+            String::from("quoted field is never closed"),
+        ));
+    }
+    if !fields.is_empty() || !field.is_empty() || quoted {
+        fields.push(field);
+        records.push(Record {
+            line: start,
+            fields,
+        });
+    }
+
+    Ok(records)
+}
+
+/// Write `fields` as a CSV line, quoting the ones that need it.
+pub fn write_record<S: AsRef<str>>(fields: &[S]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                String::from(field)
+            }
+        })
+        .collect();
+
+    format!("{}\n", fields.join(","))
+}
+
+/// Export not archived tasks matching `request` as CSV, ordered like
+/// boards show them.
+pub fn export(conn: &Connection, request: &CsvExport) -> std::result::Result<String, FilterError> {
+    let terms = filter::parse(&request.filter)?;
+    let (condition, values) = filter::to_sql(&terms, request.scope);
+    let columns = match request.columns.is_empty() {
+        true => DEFAULT_COLUMNS.to_vec(),
+        false => request.columns.clone(),
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        r#"{} WHERE {}
+        ORDER BY group_position, board_position, state_position, t.position, t.id;"#,
+        TaskSummary::SELECT,
+        condition
+    ))?;
+    let tasks = stmt
+        .query_map(params_from_iter(values.iter()), TaskSummary::from_row)?
+        .collect::<Result<Vec<TaskSummary>>>()?;
+
+    let headers: Vec<&str> = columns.iter().map(Column::header).collect();
+    let mut csv = write_record(&headers);
+    for task in &tasks {
+        let values: Vec<String> = columns.iter().map(|column| column.value(task)).collect();
+        csv.push_str(&write_record(&values));
+    }
+
+    Ok(csv)
+}
+
+/// Read the headers of CSV `contents` and suggest a column mapping.
+pub fn preview(contents: &str) -> std::result::Result<CsvPreview, RowError> {
+    let mut records = parse_records(contents)?.into_iter();
+    let headers = records
+        .next()
+        .map(|record| record.fields)
+        .unwrap_or_default();
+
+    Ok(CsvPreview {
+        mapping: headers
+            .iter()
+            .map(|header| Column::from_header(header))
+            .collect(),
+        rows: records.take(5).map(|record| record.fields).collect(),
+        headers,
+    })
+}
+
+/// Import rows of CSV `contents` as tasks at the end of their states,
+/// creating the states not found in the board. Returns the report and
+/// the rows written, which are kept even on dry-run: callers must roll
+/// them back.
+pub fn import(
+    conn: &Connection,
+    contents: &str,
+    request: &CsvImport,
+) -> Result<(CsvImportReport, Vec<Target>)> {
+    let mut report = CsvImportReport {
+        dry_run: request.dry_run,
+        imported: 0,
+        created_states: vec![],
+        errors: vec![],
+    };
+    let mut records = match parse_records(contents) {
+        Ok(records) => records.into_iter(),
+        Err(error) => {
+            report.errors.push(error);
+            return Ok((report, vec![]));
+        }
+    };
+    let headers = records
+        .next()
+        .map(|record| record.fields)
+        .unwrap_or_default();
+    let mapping = match &request.mapping {
+        Some(mapping) => mapping.clone(),
+        None => headers
+            .iter()
+            .map(|header| Column::from_header(header))
+            .collect(),
+    };
+    if !mapping.contains(&Some(Column::Name)) {
+        report.errors.push(RowError::new(
+            1,
+            None,
+            String::from("no column is mapped to the task name"),
+        ));
+        return Ok((report, vec![]));
+    }
+
+    let board = Rc::new(Board::load(conn, request.board_id)?);
+    let state_board = Rc::new(RefCell::new(Board::load(conn, request.board_id)?));
+    let mut states: Vec<Rc<State>> = State::load_by_board(conn, request.board_id)?
+        .into_iter()
+        .map(Rc::new)
+        .collect();
+    let mut written = vec![];
+
+    for record in records {
+        let cell = |column: Column| -> Option<&str> {
+            let index = mapping.iter().position(|mapped| *mapped == Some(column))?;
+            record
+                .fields
+                .get(index)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let mut errors = vec![];
+        let mut error = |column: Column, message: String| {
+            errors.push(RowError::new(record.line, Some(column.header()), message));
+        };
+
+        let name = cell(Column::Name).unwrap_or_default().to_string();
+        if name.is_empty() {
+            error(Column::Name, String::from("task name is empty"));
+        }
+        let priority = match cell(Column::Priority) {
+            None => None,
+            Some(priority) => {
+                let id = conn
+                    .query_row(
+                        &format!(
+                            "SELECT id FROM priorities p WHERE name = ?1 COLLATE NOCASE AND {};",
+                            Priority::scheme_condition("p", "?2")
+                        ),
+                        params![priority, request.board_id],
+                        |row| row.get::<_, i64>(0),
+                    )
+                    .optional()?;
+                if id.is_none() {
+                    error(
+                        Column::Priority,
+                        format!("unknown priority \"{}\"", priority),
+                    );
+                }
+                id
+            }
+        };
+        let duration = match cell(Column::Duration) {
+            None => None,
+            Some(duration) => match duration.parse::<u32>() {
+                Ok(minutes) => Some(DurationInput::Minutes(minutes)),
+                Err(_) => match DurationInput::parse(duration, &board.get_working_time()) {
+                    Ok(duration) => Some(duration),
+                    Err(parse_error) => {
+                        error(Column::Duration, parse_error.to_string());
+                        None
+                    }
+                },
+            },
+        };
+        let story_points = match cell(Column::StoryPoints).map(str::parse::<f32>) {
+            None => None,
+            Some(Ok(points)) if points >= 0.0 => Some(points),
+            Some(_) => {
+                error(Column::StoryPoints, String::from("invalid story points"));
+                None
+            }
+        };
+        let progress = match cell(Column::Progress) {
+            None => None,
+            Some(progress) => {
+                let value = match progress.strip_suffix('%') {
+                    Some(percent) => percent.trim().parse::<f32>().map(|percent| percent / 100.0),
+                    None => progress.parse::<f32>(),
+                };
+                match value {
+                    Ok(value) if (0.0..=1.0).contains(&value) => Some(value),
+                    _ => {
+                        error(
+                            Column::Progress,
+                            String::from("progress must be from 0 to 1"),
+                        );
+                        None
+                    }
+                }
+            }
+        };
+        let mut dates = vec![];
+        for column in [Column::DueAt, Column::StartedAt, Column::EndedAt] {
+            let date = cell(column).map(String::from);
+            if let Some(date) = &date {
+                let valid: bool =
+                    conn.query_row("SELECT julianday(?1) IS NOT NULL;", params![date], |row| {
+                        row.get(0)
+                    })?;
+                if !valid {
+                    error(column, format!("invalid date \"{}\"", date));
+                }
+            }
+            dates.push(date);
+        }
+        let labels: Vec<String> = cell(Column::Labels)
+            .unwrap_or_default()
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(String::from)
+            .collect();
+        let state_name = cell(Column::State).map(String::from);
+
+        if !errors.is_empty() {
+            report.errors.extend(errors);
+            continue;
+        }
+
+        conn.execute_batch("SAVEPOINT csv_row;")?;
+        let mut created = vec![];
+        let result = (|| -> Result<()> {
+            let state = match &state_name {
+                None => states.first().cloned(),
+                Some(state_name) => states
+                    .iter()
+                    .find(|state| state.get_name().to_lowercase() == state_name.to_lowercase())
+                    .cloned(),
+            };
+            let state = match (state, &state_name) {
+                (Some(state), _) => state,
+                (None, name) => {
+                    let position = states.iter().map(|state| state.get_position() + 1).max();
+                    let mut state = State::new(
+                        None,
+                        name.clone().unwrap_or_else(|| String::from("Todo")),
+                        None,
+                        Some(Rc::downgrade(&state_board)),
+                        position.unwrap_or(0),
+                    );
+                    state.insert(conn)?;
+                    created.push((EntityKind::State, state.get_id().unwrap_or_default()));
+                    let state = Rc::new(state);
+                    states.push(Rc::clone(&state));
+                    state
+                }
+            };
+            let position: u32 = conn.query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?1;",
+                params![state.get_id()],
+                |row| row.get(0),
+            )?;
+
+            let mut task = Task::new(
+                name.clone(),
+                cell(Column::Description).map(String::from),
+                None,
+                priority,
+                state,
+                Some(Rc::clone(&board)),
+                position,
+            );
+            // parsed durations always fit, see `DurationInput::parse`.
+            task.set_duration(duration)
+                .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?
+                .set_story_points(story_points)
+                .set_due_at(dates[0].clone())
+                .set_labels(labels.clone());
+            task.insert(conn)?;
+            if progress.is_some() || dates[1].is_some() || dates[2].is_some() {
+                task.set_progress(progress);
+                if let Some(started_at) = &dates[1] {
+                    task.set_started_ed(started_at.clone());
+                }
+                if let Some(ended_at) = &dates[2] {
+                    task.set_ended_ed(ended_at.clone());
+                }
+                task.update(conn)?;
+            }
+            created.push((EntityKind::Task, task.get_id().unwrap_or_default()));
+
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                conn.execute_batch("RELEASE csv_row;")?;
+                for (kind, id) in &created {
+                    if *kind == EntityKind::State {
+                        if let Some(state) = states.iter().find(|state| state.get_id() == Some(*id))
+                        {
+                            report.created_states.push(state.get_name().clone());
+                        }
+                    }
+                }
+                report.imported += 1;
+                written.extend(created);
+            }
+            Err(db_error) => {
+                conn.execute_batch("ROLLBACK TO csv_row; RELEASE csv_row;")?;
+                states.retain(|state| {
+                    !created.contains(&(EntityKind::State, state.get_id().unwrap_or_default()))
+                });
+                report
+                    .errors
+                    .push(RowError::new(record.line, None, db_error.to_string()));
+            }
+        }
+    }
+
+    Ok((report, written))
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use rusqlite::Connection;
+
+    use super::{export, import, parse_records, write_record, Column, CsvExport, CsvImport};
+    use crate::database::filter::Scope;
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_parse_and_write_records() {
+        let records =
+            parse_records("name,notes\r\n\"a, b\",\"say \"\"hi\"\"\nthen\"\n\nc,\n").unwrap();
+        let fields: Vec<(usize, Vec<&str>)> = records
+            .iter()
+            .map(|record| {
+                (
+                    record.line,
+                    record.fields.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (1, vec!["name", "notes"]),
+                (2, vec!["a, b", "say \"hi\"\nthen"]),
+                (5, vec!["c", ""])
+            ],
+            fields
+        );
+        assert_eq!(
+            "\"a, b\",\"say \"\"hi\"\"\",c\n",
+            write_record(&["a, b", "say \"hi\"", "c"])
+        );
+        assert!(parse_records("a,\"b\n").is_err());
+    }
+
+    #[test]
+    fn test_export_and_import_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1);
+            INSERT INTO tasks(id, name, duration, priority, state_id, board_id, position, labels)
+                VALUES (1, 'login, signup', 90, 3, 1, 1, 0, '["ui","bug"]');"#,
+        )
+        .unwrap();
+
+        let csv = export(
+            &conn,
+            &CsvExport {
+                scope: Scope::Board(1),
+                columns: vec![
+                    Column::Name,
+                    Column::Priority,
+                    Column::Duration,
+                    Column::Labels,
+                ],
+                ..CsvExport::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            "name,priority,duration,labels\n\"login, signup\",High,90,\"ui, bug\"\n",
+            csv
+        );
+
+        let contents = "Title,Status,Estimate,Progress,Due date\n\
+            logout,Doing,1h 30m,50%,2024-05-01\n\
+            ,Todo,,,\n\
+            profile,Todo,2x,,someday\n";
+        let request = CsvImport {
+            board_id: 1,
+            mapping: None,
+            dry_run: true,
+        };
+        let (report, written) = import(&conn, contents, &request).unwrap();
+        assert_eq!(1, report.imported);
+        assert_eq!(vec![String::from("Doing")], report.created_states);
+        let lines: Vec<(usize, Option<&str>)> = report
+            .errors
+            .iter()
+            .map(|error| (error.line, error.column.as_deref()))
+            .collect();
+        assert_eq!(
+            vec![
+                (3, Some("name")),
+                (4, Some("duration")),
+                (4, Some("due_at"))
+            ],
+            lines
+        );
+        assert_eq!(2, written.len());
+
+        let (duration, progress, due_at, state): (u32, f32, String, String) = conn
+            .query_row(
+                r#"SELECT t.duration, t.progress, t.due_at, s.name FROM tasks t
+                JOIN states s ON s.id = t.state_id WHERE t.name = 'logout';"#,
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (90, 0.5, "2024-05-01", "Doing"),
+            (duration, progress, due_at.as_str(), state.as_str())
+        );
+    }
+}
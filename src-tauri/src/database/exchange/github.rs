@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::exchange::workspace::{ImportError, Problem};
+use crate::database::history::Target;
+use crate::database::models::EntityKind;
+
+/// Value of `tasks.external_source` for imported issues.
+pub const SOURCE: &str = "GitHub";
+
+/// Issue of a `gh issue list --json` dump, only the fields that are imported:
+/// `number,title,body,state,url,labels,milestone,createdAt,closedAt`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubIssue {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    labels: Vec<GitHubLabel>,
+    #[serde(default)]
+    milestone: Option<GitHubMilestone>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    closed_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GitHubMilestone {
+    title: String,
+    #[serde(default)]
+    due_on: Option<String>,
+}
+
+impl GitHubIssue {
+    /// `owner/repo` of the issue, read from its url.
+    fn repository(&self) -> Option<&str> {
+        self.url
+            .trim_start_matches("https://github.com/")
+            .rsplitn(3, '/')
+            .nth(2)
+            .filter(|repository| !repository.is_empty() && !repository.contains(':'))
+    }
+
+    /// Id kept on the task, `owner/repo#number`. [`parse`] rejects issues
+    /// without a repository, so numbers of two repositories never mix.
+    fn key(&self) -> String {
+        format!("{}#{}", self.repository().unwrap_or_default(), self.number)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.eq_ignore_ascii_case("closed")
+    }
+}
+
+/// Where issues go.
+#[derive(Debug, Deserialize)]
+pub struct GitHubImport {
+    pub board_id: i64,
+    /// state of open issues, first not done state of the board by default.
+    #[serde(default)]
+    pub open_state_id: Option<i64>,
+    /// state of closed issues, first done state of the board by default.
+    #[serde(default)]
+    pub closed_state_id: Option<i64>,
+}
+
+/// Board states open and closed issues are mapped to.
+#[derive(Debug, Clone, Copy)]
+pub struct IssueStates {
+    open: i64,
+    closed: i64,
+}
+
+/// What a GitHub import changed.
+#[derive(Debug, Default, Serialize)]
+pub struct GitHubSummary {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    milestones: usize,
+}
+
+/// Parse a `gh issue list --json` dump. Every issue needs a title and
+/// its `url`, that tells which repository it is from.
+pub fn parse(contents: &str) -> std::result::Result<Vec<GitHubIssue>, ImportError> {
+    let issues: Vec<GitHubIssue> =
+        serde_json::from_str(contents).map_err(|error| ImportError::Invalid {
+            problems: vec![Problem::new("file", None, error.to_string())],
+        })?;
+    let mut problems = vec![];
+    for (row, issue) in issues.iter().enumerate() {
+        if issue.title.trim().is_empty() {
+            problems.push(Problem::new(
+                "issues",
+                Some(row),
+                format!("issue #{} has no title", issue.number),
+            ));
+        }
+        if issue.repository().is_none() {
+            problems.push(Problem::new(
+                "issues",
+                Some(row),
+                format!(
+                    "issue #{} has no url, dump it with `--json` including url",
+                    issue.number
+                ),
+            ));
+        }
+    }
+
+    match problems.is_empty() {
+        true => Ok(issues),
+        false => Err(ImportError::Invalid { problems }),
+    }
+}
+
+/// Resolve the states of `request`, checking they belong to its board.
+pub fn states(
+    conn: &Connection,
+    request: &GitHubImport,
+) -> std::result::Result<IssueStates, ImportError> {
+    let mut problems = vec![];
+    let mut resolve = |field: &str, id: Option<i64>, done: bool| -> Result<Option<i64>> {
+        let found: Option<i64> = match id {
+            Some(id) => conn
+                .query_row(
+                    "SELECT id FROM states WHERE id = ?1 AND board_id = ?2;",
+                    params![id, request.board_id],
+                    |row| row.get(0),
+                )
+                .optional()?,
+            None => conn
+                .query_row(
+                    r#"SELECT id FROM states WHERE board_id = ?1 AND archived_at IS NULL
+                    ORDER BY done = ?2 DESC, position, id LIMIT 1;"#,
+                    params![request.board_id, done],
+                    |row| row.get(0),
+                )
+                .optional()?,
+        };
+        if found.is_none() {
+            problems.push(Problem::new(
+                "request",
+                None,
+                match id {
+                    Some(id) => format!("{}: state {} is not in the board", field, id),
+                    None => format!("{}: board has no states", field),
+                },
+            ));
+        }
+
+        Ok(found)
+    };
+    let open = resolve("open_state_id", request.open_state_id, false)?;
+    let closed = resolve("closed_state_id", request.closed_state_id, true)?;
+
+    match (open, closed) {
+        (Some(open), Some(closed)) => Ok(IssueStates { open, closed }),
+        _ => Err(ImportError::Invalid { problems }),
+    }
+}
+
+/// Tasks already imported from `issues`, that a rerun updates.
+pub fn imported(conn: &Connection, issues: &[GitHubIssue]) -> Result<Vec<Target>> {
+    let mut tasks = vec![];
+    for issue in issues {
+        if let Some(id) = find(conn, &issue.key())? {
+            tasks.push((EntityKind::Task, id));
+        }
+    }
+
+    Ok(tasks)
+}
+
+fn find(conn: &Connection, key: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        r#"SELECT id FROM tasks WHERE external_source = ?1 AND external_id = ?2
+        ORDER BY id LIMIT 1;"#,
+        params![SOURCE, key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Turn `issues` into tasks of `board_id`, matched by repository and number
+/// so reruns update the tasks imported before instead of duplicating them.
+/// Milestones are matched by name, missing ones are created. New tasks go
+/// to the open or closed state; imported ones only move when their issue
+/// was opened or closed since, and keep their board when moved elsewhere.
+pub fn import(
+    conn: &Connection,
+    issues: &[GitHubIssue],
+    board_id: i64,
+    states: IssueStates,
+) -> Result<(GitHubSummary, Vec<Target>)> {
+    let mut summary = GitHubSummary::default();
+    let mut created = vec![];
+
+    let mut stmt = conn.prepare("SELECT name, id FROM milestones WHERE board_id = ?1;")?;
+    let mut milestones = stmt
+        .query_map(params![board_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<String, i64>>>()?;
+    let mut positions: HashMap<i64, u32> = HashMap::new();
+
+    for issue in issues {
+        let milestone_id = match &issue.milestone {
+            Some(milestone) => Some(match milestones.get(&milestone.title) {
+                Some(&id) => id,
+                None => {
+                    conn.execute(
+                        r#"INSERT INTO milestones(name, due_at, position, board_id)
+                        VALUES (?1, datetime(?2), (
+                            SELECT COALESCE(MAX(position) + 1, 0) FROM milestones WHERE board_id = ?3
+                        ), ?3);"#,
+                        params![milestone.title, milestone.due_on, board_id],
+                    )?;
+                    let id = conn.last_insert_rowid();
+                    milestones.insert(milestone.title.clone(), id);
+                    created.push((EntityKind::Milestone, id));
+                    summary.milestones += 1;
+                    id
+                }
+            }),
+            None => None,
+        };
+        let labels: Vec<&str> = issue
+            .labels
+            .iter()
+            .map(|label| label.name.as_str())
+            .collect();
+        let labels = serde_json::to_string(&labels).unwrap_or_else(|_| String::from("[]"));
+        let state_id = match issue.is_closed() {
+            true => states.closed,
+            false => states.open,
+        };
+        let key = issue.key();
+
+        let Some(task_id) = find(conn, &key)? else {
+            let position = match positions.get(&state_id) {
+                Some(&position) => position,
+                None => conn.query_row(
+                    "SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?1;",
+                    params![state_id],
+                    |row| row.get(0),
+                )?,
+            };
+            conn.execute(
+                r#"INSERT INTO tasks(
+                    name, description, state_id, board_id, position, labels, milestone_id
+                    , created_at, ended_at, external_source, external_id, external_url
+                ) VALUES (?1, NULLIF(?2, ''), ?3, ?4, ?5, ?6, ?7
+                    , COALESCE(datetime(?8), CURRENT_TIMESTAMP)
+                    , CASE WHEN ?9 THEN datetime(?10) END, ?11, ?12, NULLIF(?13, ''));"#,
+                params![
+                    issue.title,
+                    issue.body,
+                    state_id,
+                    board_id,
+                    position,
+                    labels,
+                    milestone_id,
+                    issue.created_at,
+                    issue.is_closed(),
+                    issue.closed_at,
+                    SOURCE,
+                    key,
+                    issue.url,
+                ],
+            )?;
+            positions.insert(state_id, position + 1);
+            created.push((EntityKind::Task, conn.last_insert_rowid()));
+            summary.created += 1;
+            continue;
+        };
+
+        let mut changed = conn.execute(
+            r#"UPDATE tasks SET name = ?2, description = NULLIF(?3, ''), labels = ?4
+                , external_url = NULLIF(?5, '')
+                , milestone_id = CASE WHEN board_id = ?6 THEN ?7 ELSE milestone_id END
+            WHERE id = ?1 AND (name IS NOT ?2 OR description IS NOT NULLIF(?3, '')
+                OR labels IS NOT ?4 OR external_url IS NOT NULLIF(?5, '')
+                OR (board_id = ?6 AND milestone_id IS NOT ?7));"#,
+            params![
+                task_id,
+                issue.title,
+                issue.body,
+                labels,
+                issue.url,
+                board_id,
+                milestone_id
+            ],
+        )?;
+        changed += conn.execute(
+            r#"UPDATE tasks SET state_id = ?2
+                , position = (SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?2)
+                , ended_at = CASE WHEN ?3 THEN datetime(?4) END
+            WHERE id = ?1 AND board_id = ?5 AND (
+                SELECT done FROM states WHERE id = tasks.state_id
+            ) IS NOT ?3;"#,
+            params![
+                task_id,
+                state_id,
+                issue.is_closed(),
+                issue.closed_at,
+                board_id
+            ],
+        )?;
+        positions.remove(&state_id);
+        match changed > 0 {
+            true => summary.updated += 1,
+            false => summary.unchanged += 1,
+        }
+    }
+
+    Ok((summary, created))
+}
+
+#[cfg(test)]
+mod github_tests {
+    use rusqlite::Connection;
+
+    use super::{import, imported, parse, states, GitHubImport};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_import_github_issues_twice() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Doing', 1, 1, 0), (3, 'Done', 2, 1, 1);
+            INSERT INTO milestones(id, name, position, board_id) VALUES (1, 'v1', 0, 1);"#,
+        )
+        .unwrap();
+        let request = GitHubImport {
+            board_id: 1,
+            open_state_id: None,
+            closed_state_id: None,
+        };
+        let issue_states = states(&conn, &request).unwrap();
+        let dump = r#"[
+            {"number": 1, "title": "login", "body": "oauth", "state": "OPEN",
+                "url": "https://github.com/acme/app/issues/1",
+                "labels": [{"name": "ui"}], "milestone": {"title": "v1"},
+                "createdAt": "2024-05-01T12:00:00Z", "closedAt": null},
+            {"number": 2, "title": "signup", "body": "", "state": "CLOSED",
+                "url": "https://github.com/acme/app/issues/2", "labels": [],
+                "milestone": {"title": "v2", "dueOn": "2024-06-01T00:00:00Z"},
+                "closedAt": "2024-05-03T08:00:00Z"}
+        ]"#;
+
+        let (summary, created) = import(&conn, &parse(dump).unwrap(), 1, issue_states).unwrap();
+        assert_eq!(
+            (2, 0, 1),
+            (summary.created, summary.updated, summary.milestones)
+        );
+        assert_eq!(3, created.len());
+
+        // the user moves the open issue along, then reopens the closed one
+        conn.execute("UPDATE tasks SET state_id = 2 WHERE name = 'login';", [])
+            .unwrap();
+        let dump = dump
+            .replace(r#""state": "CLOSED""#, r#""state": "OPEN""#)
+            .replace(r#""title": "login""#, r#""title": "login page""#);
+        let issues = parse(&dump).unwrap();
+        assert_eq!(2, imported(&conn, &issues).unwrap().len());
+        let (summary, created) = import(&conn, &issues, 1, issue_states).unwrap();
+        assert_eq!(
+            (0, 2, 0),
+            (summary.created, summary.updated, summary.milestones)
+        );
+        assert!(created.is_empty());
+        let (summary, _) = import(&conn, &issues, 1, issue_states).unwrap();
+        assert_eq!((0, 2), (summary.updated, summary.unchanged));
+
+        let mut stmt = conn
+            .prepare(
+                r#"SELECT t.name || ' ' || s.name || ' ' || t.labels || ' ' || m.name
+                    || ' ' || t.external_id || ' ' || COALESCE(t.ended_at, '-')
+                FROM tasks t JOIN states s ON s.id = t.state_id
+                    JOIN milestones m ON m.id = t.milestone_id ORDER BY t.id;"#,
+            )
+            .unwrap();
+        let tasks: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                r#"login page Doing ["ui"] v1 acme/app#1 -"#,
+                r#"signup Todo [] v2 acme/app#2 -"#,
+            ],
+            tasks
+        );
+
+        let request = GitHubImport {
+            board_id: 1,
+            open_state_id: Some(9),
+            closed_state_id: None,
+        };
+        assert!(states(&conn, &request).is_err());
+        assert!(parse(
+            r#"[{"number": 3, "title": " ", "url": "https://github.com/acme/app/issues/3"}]"#
+        )
+        .is_err());
+        // without a url the issue could match #4 of any other repository.
+        assert!(parse(r#"[{"number": 4, "title": "logout"}]"#).is_err());
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::filter::{self, Scope};
+use crate::database::models::{Priority, TaskSummary};
+
+/// Domain of every UID, so entries of this app never clash with others.
+const UID_DOMAIN: &str = "my-board-app";
+
+/// What goes into a calendar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IcsOptions {
+    pub scope: Scope,
+    /// a to-do per task with a due date.
+    pub due_dates: bool,
+    /// keep to-dos of tasks in done states, as completed.
+    pub done_tasks: bool,
+    /// an event per milestone, from the day after the previous milestone
+    /// of its board, the sprint it closes.
+    pub milestones: bool,
+    /// an event per time log, ending when it was logged.
+    pub time_logs: bool,
+}
+
+impl Default for IcsOptions {
+    fn default() -> Self {
+        IcsOptions {
+            scope: Scope::default(),
+            due_dates: true,
+            done_tasks: true,
+            milestones: true,
+            time_logs: true,
+        }
+    }
+}
+
+/// A calendar file rewritten after every change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarFeed {
+    pub path: String,
+    #[serde(default)]
+    pub options: IcsOptions,
+}
+
+impl CalendarFeed {
+    /// Load the feed saved at `path`, if any.
+    pub fn load(path: &str) -> Option<CalendarFeed> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+    }
+
+    /// Save `feed` at `path`, removing the file when there's no feed.
+    pub fn save(feed: Option<&CalendarFeed>, path: &str) -> std::io::Result<()> {
+        let Some(feed) = feed else {
+            return match std::fs::remove_file(path) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+                _ => Ok(()),
+            };
+        };
+        let contents = serde_json::to_string_pretty(feed).map_err(std::io::Error::other)?;
+
+        std::fs::write(path, contents)
+    }
+}
+
+/// Escape `text` for a TEXT property value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+/// Append content line `line` to `ics`, folded every 75 octets.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for char in line.chars() {
+        if octets + char.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(char);
+        octets += char.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// Render tasks, milestones and time logs of `options.scope` as an
+/// iCalendar file. Tasks become VTODOs due at their due date, milestones
+/// and time logs VEVENTs. UIDs are built from entity ids, so calendar
+/// apps update their entries on each import instead of duplicating them.
+/// Entries with dates SQLite can't read are left out.
+pub fn render(conn: &Connection, options: &IcsOptions) -> Result<String> {
+    let stamp: String = conn.query_row("SELECT strftime('%Y%m%dT%H%M%SZ', 'now');", [], |row| {
+        row.get(0)
+    })?;
+    let mut ics = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//my-board-app//Board calendar//EN",
+        "CALSCALE:GREGORIAN",
+        "X-WR-CALNAME:My Board",
+    ] {
+        push_line(&mut ics, line);
+    }
+
+    if options.due_dates {
+        render_tasks(conn, options, &stamp, &mut ics)?;
+    }
+    if options.milestones {
+        render_milestones(conn, options.scope, &stamp, &mut ics)?;
+    }
+    if options.time_logs {
+        render_time_logs(conn, options.scope, &stamp, &mut ics)?;
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok(ics)
+}
+
+fn render_tasks(
+    conn: &Connection,
+    options: &IcsOptions,
+    stamp: &str,
+    ics: &mut String,
+) -> Result<()> {
+    let (condition, values) = filter::to_sql(&[], options.scope);
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT * FROM ({}
+            WHERE {} AND t.due_at IS NOT NULL AND (? OR NOT s.done)
+        ) LEFT JOIN (
+            SELECT id AS ics_id
+                , CASE WHEN length(due_at) = 10 THEN strftime('%Y%m%d', due_at)
+                    ELSE strftime('%Y%m%dT%H%M%SZ', due_at) END AS ics_due
+                , strftime('%Y%m%dT%H%M%SZ', ended_at) AS ics_completed
+                , strftime('%Y%m%dT%H%M%SZ', updated_at) AS ics_modified
+            FROM tasks
+        ) ON ics_id = id
+        ORDER BY due_at, id;"#,
+        TaskSummary::SELECT,
+        condition
+    ))?;
+    let mut values = values;
+    values.push(Value::Integer(options.done_tasks.into()));
+    let mut rows = stmt.query(params_from_iter(values.iter()))?;
+
+    let mut schemes: HashMap<i64, Vec<Priority>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let Some(due) = row.get::<_, Option<String>>("ics_due")? else {
+            continue;
+        };
+        let task = TaskSummary::from_row(row)?;
+        let completed: Option<String> = row.get("ics_completed")?;
+        let modified: Option<String> = row.get("ics_modified")?;
+        let scheme = match schemes.entry(task.board_id) {
+            Entry::Occupied(scheme) => scheme.into_mut(),
+            Entry::Vacant(scheme) => {
+                scheme.insert(Priority::load_scheme(conn, Some(task.board_id))?)
+            }
+        };
+        let priority = scheme
+            .iter()
+            .position(|priority| priority.get_id() == task.priority_id)
+            .map(|index| (1 + 2 * index).min(9));
+
+        push_line(ics, "BEGIN:VTODO");
+        push_line(ics, &format!("UID:task-{}@{}", task.id, UID_DOMAIN));
+        push_line(ics, &format!("DTSTAMP:{}", stamp));
+        if let Some(modified) = modified {
+            push_line(ics, &format!("LAST-MODIFIED:{}", modified));
+        }
+        push_line(ics, &format!("SUMMARY:{}", escape(&task.name)));
+        if let Some(description) = task.description.as_deref().filter(|text| !text.is_empty()) {
+            push_line(ics, &format!("DESCRIPTION:{}", escape(description)));
+        }
+        match due.len() {
+            8 => push_line(ics, &format!("DUE;VALUE=DATE:{}", due)),
+            _ => push_line(ics, &format!("DUE:{}", due)),
+        }
+        let status = match (task.state_done, task.state_active) {
+            (true, _) => "COMPLETED",
+            (false, true) => "IN-PROCESS",
+            (false, false) => "NEEDS-ACTION",
+        };
+        push_line(ics, &format!("STATUS:{}", status));
+        if let Some(completed) = completed.filter(|_| task.state_done) {
+            push_line(ics, &format!("COMPLETED:{}", completed));
+        }
+        if let Some(progress) = task.progress {
+            push_line(
+                ics,
+                &format!("PERCENT-COMPLETE:{}", (progress * 100.0).round()),
+            );
+        }
+        if let Some(priority) = priority {
+            push_line(ics, &format!("PRIORITY:{}", priority));
+        }
+        if !task.labels.is_empty() {
+            let labels: Vec<String> = task.labels.iter().map(|label| escape(label)).collect();
+            push_line(ics, &format!("CATEGORIES:{}", labels.join(",")));
+        }
+        if let Some(url) = &task.external_url {
+            push_line(ics, &format!("URL:{}", url));
+        }
+        push_line(ics, "END:VTODO");
+    }
+
+    Ok(())
+}
+
+fn render_milestones(conn: &Connection, scope: Scope, stamp: &str, ics: &mut String) -> Result<()> {
+    let (condition, values) = filter::board_condition(scope);
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT id, name, board_name
+            , strftime('%Y%m%d', COALESCE(date(previous_due, '+1 day'), due_at)) AS start
+            , strftime('%Y%m%d', date(due_at, '+1 day')) AS end
+            , (SELECT COUNT(*) FROM tasks t WHERE t.milestone_id = m.id
+                AND t.archived_at IS NULL) AS tasks
+            , (SELECT COUNT(*) FROM tasks t JOIN states s ON s.id = t.state_id
+                WHERE t.milestone_id = m.id AND t.archived_at IS NULL AND s.done) AS done
+        FROM (
+            SELECT m.*, b.name AS board_name
+                , LAG(m.due_at) OVER (PARTITION BY m.board_id ORDER BY m.due_at, m.id)
+                    AS previous_due
+            FROM milestones m
+                JOIN boards b ON b.id = m.board_id
+                JOIN groups g ON g.id = b.group_id
+            WHERE m.due_at IS NOT NULL AND b.archived_at IS NULL AND g.archived_at IS NULL
+                AND {}
+        ) m
+        ORDER BY due_at, id;"#,
+        condition
+    ))?;
+    let mut rows = stmt.query(params_from_iter(values.iter()))?;
+
+    while let Some(row) = rows.next()? {
+        let (Some(start), Some(end)) = (
+            row.get::<_, Option<String>>("start")?,
+            row.get::<_, Option<String>>("end")?,
+        ) else {
+            continue;
+        };
+        let id: i64 = row.get("id")?;
+        let name: String = row.get("name")?;
+        let board: String = row.get("board_name")?;
+        let (tasks, done): (u32, u32) = (row.get("tasks")?, row.get("done")?);
+
+        push_line(ics, "BEGIN:VEVENT");
+        push_line(ics, &format!("UID:milestone-{}@{}", id, UID_DOMAIN));
+        push_line(ics, &format!("DTSTAMP:{}", stamp));
+        push_line(ics, &format!("DTSTART;VALUE=DATE:{}", start));
+        push_line(ics, &format!("DTEND;VALUE=DATE:{}", end));
+        push_line(
+            ics,
+            &format!("SUMMARY:{}", escape(&format!("{} ({})", name, board))),
+        );
+        push_line(
+            ics,
+            &format!("DESCRIPTION:{} of {} tasks done", done, tasks),
+        );
+        push_line(ics, "TRANSP:TRANSPARENT");
+        push_line(ics, "END:VEVENT");
+    }
+
+    Ok(())
+}
+
+fn render_time_logs(conn: &Connection, scope: Scope, stamp: &str, ics: &mut String) -> Result<()> {
+    let (condition, values) = filter::board_condition(scope);
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT l.id, l.note, t.name
+            , strftime('%Y%m%dT%H%M%SZ', l.logged_at, '-' || l.minutes || ' minutes') AS start
+            , strftime('%Y%m%dT%H%M%SZ', l.logged_at) AS end
+        FROM time_logs l
+            JOIN tasks t ON t.id = l.task_id
+            JOIN boards b ON b.id = t.board_id
+            JOIN groups g ON g.id = b.group_id
+        WHERE b.archived_at IS NULL AND g.archived_at IS NULL AND {}
+        ORDER BY l.logged_at, l.id;"#,
+        condition
+    ))?;
+    let mut rows = stmt.query(params_from_iter(values.iter()))?;
+
+    while let Some(row) = rows.next()? {
+        let (Some(start), Some(end)) = (
+            row.get::<_, Option<String>>("start")?,
+            row.get::<_, Option<String>>("end")?,
+        ) else {
+            continue;
+        };
+        let id: i64 = row.get("id")?;
+        let note: Option<String> = row.get("note")?;
+        let task: String = row.get("name")?;
+
+        push_line(ics, "BEGIN:VEVENT");
+        push_line(ics, &format!("UID:time-log-{}@{}", id, UID_DOMAIN));
+        push_line(ics, &format!("DTSTAMP:{}", stamp));
+        push_line(ics, &format!("DTSTART:{}", start));
+        push_line(ics, &format!("DTEND:{}", end));
+        push_line(ics, &format!("SUMMARY:{}", escape(&task)));
+        if let Some(note) = note.filter(|note| !note.is_empty()) {
+            push_line(ics, &format!("DESCRIPTION:{}", escape(&note)));
+        }
+        push_line(ics, "END:VEVENT");
+    }
+
+    Ok(())
+}
+
+/// Write the calendar of `feed` to its path. The file is replaced at
+/// once, so calendar apps never read it half written.
+pub fn write_feed(conn: &Connection, feed: &CalendarFeed) -> std::result::Result<(), String> {
+    let ics = render(conn, &feed.options).map_err(|_| String::from("Transaction error!"))?;
+    let partial = format!("{}.partial", feed.path);
+    std::fs::write(&partial, ics).map_err(|err| err.to_string())?;
+    std::fs::rename(&partial, &feed.path).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod ical_tests {
+    use rusqlite::Connection;
+
+    use super::{push_line, render, IcsOptions};
+    use crate::database::filter::Scope;
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_render_calendar() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1), (2, 'web', 1, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Done', 1, 1, 1), (3, 'Todo', 0, 2, 0);
+            INSERT INTO milestones(id, name, due_at, position, board_id)
+                VALUES (1, 'sprint 1', '2024-05-14', 0, 1), (2, 'sprint 2', '2024-05-28', 1, 1);
+            INSERT INTO tasks(id, name, description, priority, state_id, board_id, position
+                , labels, due_at, ended_at, milestone_id)
+                VALUES (1, 'login, oauth', 'line one
+line two', 4, 1, 1, 0, '["ui"]', '2024-05-10 12:00:00', NULL, 2)
+                , (2, 'signup', NULL, NULL, 2, 1, 0, '[]', '2024-05-12', '2024-05-11 18:00:00', 2)
+                , (3, 'home', NULL, NULL, 3, 2, 0, '[]', '2024-05-13', NULL, NULL);
+            INSERT INTO time_logs(id, minutes, note, logged_at, task_id)
+                VALUES (1, 90, 'pairing', '2024-05-09 16:00:00', 1);"#,
+        )
+        .unwrap();
+
+        let options = IcsOptions {
+            scope: Scope::Board(1),
+            ..IcsOptions::default()
+        };
+        let ics = render(&conn, &options).unwrap();
+        let lines: Vec<&str> = ics
+            .split("\r\n")
+            .filter(|line| !line.starts_with("DTSTAMP") && !line.starts_with("LAST-MODIFIED"))
+            .collect();
+        assert_eq!(
+            vec![
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//my-board-app//Board calendar//EN",
+                "CALSCALE:GREGORIAN",
+                "X-WR-CALNAME:My Board",
+                "BEGIN:VTODO",
+                "UID:task-1@my-board-app",
+                "SUMMARY:login\\, oauth",
+                "DESCRIPTION:line one\\nline two",
+                "DUE:20240510T120000Z",
+                "STATUS:NEEDS-ACTION",
+                "PRIORITY:1",
+                "CATEGORIES:ui",
+                "END:VTODO",
+                "BEGIN:VTODO",
+                "UID:task-2@my-board-app",
+                "SUMMARY:signup",
+                "DUE;VALUE=DATE:20240512",
+                "STATUS:COMPLETED",
+                "COMPLETED:20240511T180000Z",
+                "PERCENT-COMPLETE:100",
+                "END:VTODO",
+                "BEGIN:VEVENT",
+                "UID:milestone-1@my-board-app",
+                "DTSTART;VALUE=DATE:20240514",
+                "DTEND;VALUE=DATE:20240515",
+                "SUMMARY:sprint 1 (app)",
+                "DESCRIPTION:0 of 0 tasks done",
+                "TRANSP:TRANSPARENT",
+                "END:VEVENT",
+                "BEGIN:VEVENT",
+                "UID:milestone-2@my-board-app",
+                "DTSTART;VALUE=DATE:20240515",
+                "DTEND;VALUE=DATE:20240529",
+                "SUMMARY:sprint 2 (app)",
+                "DESCRIPTION:1 of 2 tasks done",
+                "TRANSP:TRANSPARENT",
+                "END:VEVENT",
+                "BEGIN:VEVENT",
+                "UID:time-log-1@my-board-app",
+                "DTSTART:20240509T143000Z",
+                "DTEND:20240509T160000Z",
+                "SUMMARY:login\\, oauth",
+                "DESCRIPTION:pairing",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ],
+            lines
+        );
+
+        let open = IcsOptions {
+            done_tasks: false,
+            milestones: false,
+            time_logs: false,
+            ..IcsOptions::default()
+        };
+        let ics = render(&conn, &open).unwrap();
+        assert_eq!(
+            vec!["task-1", "task-3"],
+            ics.lines()
+                .filter_map(|line| line.strip_prefix("UID:"))
+                .map(|uid| uid.trim_end_matches("@my-board-app"))
+                .collect::<Vec<&str>>()
+        );
+
+        let mut folded = String::new();
+        push_line(&mut folded, &format!("SUMMARY:{}", "é".repeat(40)));
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(
+            format!("SUMMARY:{}", "é".repeat(40)),
+            folded.trim_end().replace("\r\n ", "")
+        );
+    }
+
+    #[test]
+    fn test_unreadable_dates_are_left_out() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1);
+            INSERT INTO milestones(id, name, due_at, position, board_id)
+                VALUES (1, 'sprint 1', '2024-05-14', 0, 1), (2, 'sprint 2', 'soon', 1, 1);
+            INSERT INTO tasks(id, name, state_id, board_id, position, due_at)
+                VALUES (1, 'login', 1, 1, 0, 'next week'), (2, 'signup', 1, 1, 1, '2024-05-12');
+            INSERT INTO time_logs(id, minutes, logged_at, task_id)
+                VALUES (1, 30, 'yesterday', 1), (2, 30, '2024-05-09 16:00:00', 2);"#,
+        )
+        .unwrap();
+
+        let ics = render(&conn, &IcsOptions::default()).unwrap();
+        assert_eq!(
+            vec!["task-2", "milestone-1", "time-log-2"],
+            ics.lines()
+                .filter_map(|line| line.strip_prefix("UID:"))
+                .map(|uid| uid.trim_end_matches("@my-board-app"))
+                .collect::<Vec<&str>>()
+        );
+    }
+}
@@ -0,0 +1,604 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::exchange::csv::{parse_records, RowError};
+use crate::database::history::Target;
+use crate::database::models::{EntityKind, Priority};
+
+/// Value of `tasks.external_source` for imported issues.
+pub const SOURCE: &str = "Jira";
+
+/// Jira default priorities, least urgent first.
+const PRIORITIES: [&str; 5] = ["Lowest", "Low", "Medium", "High", "Highest"];
+
+/// Issue read from a row of a Jira CSV export.
+#[derive(Debug)]
+pub struct JiraIssue {
+    line: usize,
+    key: String,
+    id: Option<String>,
+    parent_id: Option<String>,
+    summary: String,
+    description: Option<String>,
+    status: String,
+    status_category: Option<String>,
+    priority: Option<String>,
+    labels: Vec<String>,
+    /// original estimate, in seconds.
+    estimate: Option<u32>,
+    /// time spent, in seconds.
+    spent: Option<u32>,
+    work_logs: Vec<WorkLog>,
+    created: Option<String>,
+    resolved: Option<String>,
+    due: Option<String>,
+}
+
+/// Entry of a `Log Work` column: `comment;date;author;seconds`.
+#[derive(Debug)]
+struct WorkLog {
+    note: Option<String>,
+    logged_at: Option<String>,
+    seconds: u32,
+}
+
+/// Jira statuses and priorities mapped by a user, by name.
+#[derive(Debug, Default, Deserialize)]
+pub struct JiraImport {
+    pub board_id: i64,
+    /// state of each status. Statuses left out go to the board state
+    /// with the same name, created when missing.
+    #[serde(default)]
+    pub statuses: BTreeMap<String, i64>,
+    /// priority of each Jira priority. Priorities left out go to the
+    /// suggested one.
+    #[serde(default)]
+    pub priorities: BTreeMap<String, i64>,
+}
+
+/// Statuses and priorities found in an export, with the mapping
+/// suggested for them, to edit before importing.
+#[derive(Debug, Serialize)]
+pub struct JiraMapping {
+    statuses: Vec<StatusMapping>,
+    priorities: Vec<PriorityMapping>,
+    errors: Vec<RowError>,
+}
+
+impl JiraMapping {
+    /// Add errors found reading the file.
+    pub fn add_errors(&mut self, errors: Vec<RowError>) {
+        self.errors.splice(0..0, errors);
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StatusMapping {
+    status: String,
+    issues: usize,
+    /// `None` when a state is created for the status.
+    state_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct PriorityMapping {
+    priority: String,
+    issues: usize,
+    /// `None` when issues get no priority.
+    priority_id: Option<i64>,
+}
+
+/// What a Jira import did. Rows with errors and issues imported before
+/// are skipped, the others are imported.
+#[derive(Debug, Default, Serialize)]
+pub struct JiraReport {
+    imported: usize,
+    time_logs: usize,
+    created_states: Vec<String>,
+    /// keys of issues imported before.
+    skipped: Vec<String>,
+    errors: Vec<RowError>,
+}
+
+impl JiraReport {
+    /// Add errors found reading the file.
+    pub fn add_errors(&mut self, errors: Vec<RowError>) {
+        self.errors.splice(0..0, errors);
+    }
+}
+
+/// Read the issues of a Jira CSV export. Columns are found by header,
+/// `Labels` and `Log Work` may be repeated.
+pub fn parse(contents: &str) -> (Vec<JiraIssue>, Vec<RowError>) {
+    let mut records = match parse_records(contents) {
+        Ok(records) => records.into_iter(),
+        Err(error) => return (vec![], vec![error]),
+    };
+    let headers: Vec<String> = records
+        .next()
+        .map(|record| record.fields)
+        .unwrap_or_default()
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let columns = |header: &str| -> Vec<usize> {
+        (0..headers.len())
+            .filter(|index| headers[*index] == header)
+            .collect()
+    };
+    let missing: Vec<RowError> = ["issue key", "summary", "status"]
+        .iter()
+        .filter(|header| columns(header).is_empty())
+        .map(|header| RowError::new(1, Some(header), String::from("column is missing")))
+        .collect();
+    if !missing.is_empty() {
+        return (vec![], missing);
+    }
+
+    let mut issues = vec![];
+    let mut errors = vec![];
+    for record in records {
+        let values = |header: &str| -> Vec<&str> {
+            columns(header)
+                .into_iter()
+                .filter_map(|index| record.fields.get(index))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .collect()
+        };
+        let cell = |header: &str| values(header).first().map(|value| value.to_string());
+        let mut seconds = |header: &str| match cell(header).map(|value| value.parse::<u32>()) {
+            None => None,
+            Some(Ok(seconds)) => Some(seconds),
+            Some(Err(_)) => {
+                errors.push(RowError::new(
+                    record.line,
+                    Some(header),
+                    String::from("expected a number of seconds"),
+                ));
+                None
+            }
+        };
+        let estimate = seconds("original estimate");
+        let spent = seconds("time spent");
+
+        let mut work_logs = vec![];
+        for entry in values("log work") {
+            let mut parts = entry.rsplitn(4, ';');
+            let seconds = parts.next().and_then(|seconds| seconds.trim().parse().ok());
+            let (_, logged_at, note) = (parts.next(), parts.next(), parts.next());
+            match seconds {
+                Some(seconds) => work_logs.push(WorkLog {
+                    note: note
+                        .map(str::trim)
+                        .filter(|note| !note.is_empty())
+                        .map(String::from),
+                    logged_at: logged_at.map(date),
+                    seconds,
+                }),
+                None => errors.push(RowError::new(
+                    record.line,
+                    Some("log work"),
+                    format!("invalid work log \"{}\"", entry),
+                )),
+            }
+        }
+
+        let (key, summary, status) = (cell("issue key"), cell("summary"), cell("status"));
+        for (header, value) in [
+            ("issue key", &key),
+            ("summary", &summary),
+            ("status", &status),
+        ] {
+            if value.is_none() {
+                errors.push(RowError::new(
+                    record.line,
+                    Some(header),
+                    String::from("value is empty"),
+                ));
+            }
+        }
+        if let (Some(key), Some(summary), Some(status)) = (key, summary, status) {
+            issues.push(JiraIssue {
+                line: record.line,
+                key,
+                id: cell("issue id"),
+                parent_id: cell("parent id").or_else(|| cell("parent")),
+                summary,
+                description: cell("description"),
+                status,
+                status_category: cell("status category"),
+                priority: cell("priority"),
+                labels: values("labels").into_iter().map(String::from).collect(),
+                estimate,
+                spent,
+                work_logs,
+                created: cell("created").as_deref().map(date),
+                resolved: cell("resolved").as_deref().map(date),
+                due: cell("due date").as_deref().map(date),
+            });
+        }
+    }
+    issues.retain(|issue| !errors.iter().any(|error| error.line() == issue.line));
+
+    (issues, errors)
+}
+
+/// Turn a Jira date like `12/Jan/24 3:05 PM` into an SQL one. Other
+/// values are kept, for SQLite to read or reject.
+fn date(value: &str) -> String {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let day: Vec<&str> = parts
+        .first()
+        .map(|day| day.split('/').collect())
+        .unwrap_or_default();
+    let month = day.get(1).and_then(|month| {
+        [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ]
+        .iter()
+        .position(|name| month.eq_ignore_ascii_case(name))
+    });
+    let (Some(month), [day, _, year]) = (month, day.as_slice()) else {
+        return String::from(value);
+    };
+    let (mut hour, minute) = parts
+        .get(1)
+        .and_then(|time| time.split_once(':'))
+        .and_then(|(hour, minute)| Some((hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?)))
+        .unwrap_or((0, 0));
+    match parts.get(2).map(|period| period.to_uppercase()).as_deref() {
+        Some("PM") if hour < 12 => hour += 12,
+        Some("AM") if hour == 12 => hour = 0,
+        _ => {}
+    }
+    let year = match year.len() {
+        2 => format!("20{}", year),
+        _ => year.to_string(),
+    };
+
+    format!(
+        "{}-{:02}-{:0>2} {:02}:{:02}:00",
+        year,
+        month + 1,
+        day,
+        hour,
+        minute
+    )
+}
+
+/// Suggest the states and priorities of `issues` in `request.board_id`,
+/// keeping the ones mapped in `request`. Statuses go to the state with
+/// the same name, priorities to the one with the same name or, for Jira
+/// default priorities, to the board priority of matching urgency.
+pub fn mapping(
+    conn: &Connection,
+    issues: &[JiraIssue],
+    request: &JiraImport,
+) -> Result<JiraMapping> {
+    let mut mapping = JiraMapping {
+        statuses: vec![],
+        priorities: vec![],
+        errors: vec![],
+    };
+
+    let mut statuses: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut priorities: BTreeMap<&str, usize> = BTreeMap::new();
+    for issue in issues {
+        *statuses.entry(&issue.status).or_insert(0) += 1;
+        if let Some(priority) = &issue.priority {
+            *priorities.entry(priority).or_insert(0) += 1;
+        }
+    }
+
+    for (status, count) in statuses {
+        let state_id = match request.statuses.get(status) {
+            Some(&id) => conn
+                .query_row(
+                    "SELECT id FROM states WHERE id = ?1 AND board_id = ?2;",
+                    params![id, request.board_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .or_else(|| {
+                    mapping.errors.push(RowError::new(
+                        1,
+                        Some("status"),
+                        format!("state {} of \"{}\" is not in the board", id, status),
+                    ));
+                    None
+                }),
+            None => conn
+                .query_row(
+                    r#"SELECT id FROM states WHERE board_id = ?1 AND name = ?2 COLLATE NOCASE
+                    AND archived_at IS NULL ORDER BY position LIMIT 1;"#,
+                    params![request.board_id, status],
+                    |row| row.get(0),
+                )
+                .optional()?,
+        };
+        mapping.statuses.push(StatusMapping {
+            status: String::from(status),
+            issues: count,
+            state_id,
+        });
+    }
+
+    let mut scheme = Priority::load_scheme(conn, Some(request.board_id))?;
+    scheme.reverse();
+    for (priority, count) in priorities {
+        let priority_id = match request.priorities.get(priority) {
+            Some(id) => match scheme.iter().any(|known| known.get_id() == Some(*id)) {
+                true => Some(*id),
+                false => {
+                    mapping.errors.push(RowError::new(
+                        1,
+                        Some("priority"),
+                        format!(
+                            "priority {} of \"{}\" is not used by the board",
+                            id, priority
+                        ),
+                    ));
+                    None
+                }
+            },
+            None => scheme
+                .iter()
+                .find(|known| known.get_name().eq_ignore_ascii_case(priority))
+                .or_else(|| {
+                    let level = PRIORITIES
+                        .iter()
+                        .position(|name| name.eq_ignore_ascii_case(priority))?;
+                    scheme.get(level * scheme.len().saturating_sub(1) / (PRIORITIES.len() - 1))
+                })
+                .and_then(Priority::get_id),
+        };
+        mapping.priorities.push(PriorityMapping {
+            priority: String::from(priority),
+            issues: count,
+            priority_id,
+        });
+    }
+
+    Ok(mapping)
+}
+
+fn find(conn: &Connection, key: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM tasks WHERE external_source = ?1 AND external_id = ?2 LIMIT 1;",
+        params![SOURCE, key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Import `issues` as tasks at the end of their states, following
+/// [`mapping`], and creating the states not mapped. Estimates become
+/// task durations, work logs, or the time spent when there are none,
+/// become time logs. Sub-tasks keep their parent when it is imported
+/// too. Nothing is written when the mapping has errors.
+pub fn import(
+    conn: &Connection,
+    issues: &[JiraIssue],
+    request: &JiraImport,
+) -> Result<(JiraReport, Vec<Target>)> {
+    let mut report = JiraReport::default();
+    let mapping = mapping(conn, issues, request)?;
+    if !mapping.errors.is_empty() {
+        report.errors = mapping.errors;
+        return Ok((report, vec![]));
+    }
+    let mut states: HashMap<String, Option<i64>> = mapping
+        .statuses
+        .into_iter()
+        .map(|status| (status.status, status.state_id))
+        .collect();
+    let priorities: HashMap<String, Option<i64>> = mapping
+        .priorities
+        .into_iter()
+        .map(|priority| (priority.priority, priority.priority_id))
+        .collect();
+
+    let mut written = vec![];
+    let mut tasks: HashMap<&str, i64> = HashMap::new();
+    for issue in issues {
+        if find(conn, &issue.key)?.is_some() {
+            report.skipped.push(issue.key.clone());
+            continue;
+        }
+
+        let state_id = match states.get(&issue.status).copied().flatten() {
+            Some(state_id) => state_id,
+            None => {
+                let done = match &issue.status_category {
+                    Some(category) => category.eq_ignore_ascii_case("done"),
+                    None => ["done", "closed", "resolved"]
+                        .iter()
+                        .any(|name| issue.status.eq_ignore_ascii_case(name)),
+                };
+                conn.execute(
+                    r#"INSERT INTO states(name, position, board_id, done) VALUES (?1, (
+                        SELECT COALESCE(MAX(position) + 1, 0) FROM states WHERE board_id = ?2
+                    ), ?2, ?3);"#,
+                    params![issue.status, request.board_id, done],
+                )?;
+                let state_id = conn.last_insert_rowid();
+                states.insert(issue.status.clone(), Some(state_id));
+                written.push((EntityKind::State, state_id));
+                report.created_states.push(issue.status.clone());
+                state_id
+            }
+        };
+        let priority = issue
+            .priority
+            .as_ref()
+            .and_then(|priority| priorities.get(priority).copied().flatten());
+
+        conn.execute(
+            r#"INSERT INTO tasks(
+                name, description, duration, priority, state_id, board_id, position, labels
+                , created_at, ended_at, due_at, external_source, external_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, (
+                SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?5
+            ), ?7, datetime(?8), datetime(?9), datetime(?10), ?11, ?12);"#,
+            params![
+                issue.summary,
+                issue.description,
+                issue.estimate.map(|seconds| seconds.div_ceil(60)),
+                priority,
+                state_id,
+                request.board_id,
+                serde_json::to_string(&issue.labels).unwrap_or_else(|_| String::from("[]")),
+                issue.created,
+                issue.resolved,
+                issue.due,
+                SOURCE,
+                issue.key,
+            ],
+        )?;
+        let task_id = conn.last_insert_rowid();
+        written.push((EntityKind::Task, task_id));
+        if let Some(id) = &issue.id {
+            tasks.insert(id, task_id);
+        }
+        report.imported += 1;
+
+        let logs: Vec<(u32, Option<&str>, Option<&str>)> = match issue.work_logs.is_empty() {
+            true => issue
+                .spent
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| {
+                    let logged_at = issue.resolved.as_deref().or(issue.created.as_deref());
+                    (seconds, Some("Time spent in Jira"), logged_at)
+                })
+                .into_iter()
+                .collect(),
+            false => issue
+                .work_logs
+                .iter()
+                .map(|log| (log.seconds, log.note.as_deref(), log.logged_at.as_deref()))
+                .collect(),
+        };
+        for (seconds, note, logged_at) in logs {
+            conn.execute(
+                r#"INSERT INTO time_logs(minutes, note, logged_at, task_id)
+                VALUES (?1, ?2, COALESCE(datetime(?3), CURRENT_TIMESTAMP), ?4);"#,
+                params![seconds.div_ceil(60), note, logged_at, task_id],
+            )?;
+            written.push((EntityKind::TimeLog, conn.last_insert_rowid()));
+            report.time_logs += 1;
+        }
+    }
+
+    for issue in issues {
+        let parent = issue.parent_id.as_deref().and_then(|id| tasks.get(id));
+        let task = issue.id.as_deref().and_then(|id| tasks.get(id));
+        if let (Some(parent), Some(task)) = (parent, task) {
+            conn.execute(
+                "UPDATE tasks SET parent_id = ?1 WHERE id = ?2;",
+                params![parent, task],
+            )?;
+        }
+    }
+
+    Ok((report, written))
+}
+
+#[cfg(test)]
+mod jira_tests {
+    use std::collections::BTreeMap;
+
+    use rusqlite::Connection;
+
+    use super::{date, import, mapping, parse, JiraImport};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_import_jira_csv() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'To Do', 0, 1, 0), (2, 'Doing', 1, 1, 0);"#,
+        )
+        .unwrap();
+        let contents = "Summary,Issue key,Issue id,Parent id,Status,Status Category,Priority,Labels,Labels,Original Estimate,Time Spent,Log Work,Created\n\
+            login,APP-1,10001,,In Progress,In Progress,Highest,ui,auth,7200,5400,\"pairing;03/Feb/24 2:30 PM;ana;3600\",01/Feb/24 9:00 AM\n\
+            form,APP-2,10002,10001,Closed,Done,Medium,,,,1800,,01/Feb/24 9:00 AM\n\
+            broken,APP-3,10003,,To Do,To Do,Low,,,soon,,,\n";
+        let (issues, errors) = parse(contents);
+        assert_eq!(2, issues.len());
+        assert_eq!(1, errors.len());
+
+        let mut request = JiraImport {
+            board_id: 1,
+            statuses: BTreeMap::from([(String::from("In Progress"), 2)]),
+            ..JiraImport::default()
+        };
+        let mut suggested = mapping(&conn, &issues, &request).unwrap();
+        suggested.add_errors(errors);
+        assert_eq!(1, suggested.errors.len());
+        assert_eq!(
+            vec![
+                (String::from("Closed"), None),
+                (String::from("In Progress"), Some(2))
+            ],
+            suggested
+                .statuses
+                .iter()
+                .map(|status| (status.status.clone(), status.state_id))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Some(4), Some(2)],
+            suggested
+                .priorities
+                .iter()
+                .map(|priority| priority.priority_id)
+                .collect::<Vec<_>>()
+        );
+
+        let (report, written) = import(&conn, &issues, &request).unwrap();
+        assert_eq!((2, 2), (report.imported, report.time_logs));
+        assert_eq!(vec!["Closed"], report.created_states);
+        assert_eq!(5, written.len());
+        let tasks: String = conn
+            .query_row(
+                r#"SELECT group_concat(t.name || ' ' || s.name || ' ' || s.done || ' '
+                    || COALESCE(t.duration, '-') || ' ' || t.priority || ' ' || t.labels
+                    || ' ' || t.external_id || ' ' || COALESCE(t.parent_id, '-'), ', ')
+                FROM tasks t JOIN states s ON s.id = t.state_id;"#,
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            r#"login Doing 0 120 4 ["ui","auth"] APP-1 -, form Closed 1 - 2 [] APP-2 1"#,
+            tasks
+        );
+        let logs: String = conn
+            .query_row(
+                "SELECT group_concat(minutes || ' ' || note || ' ' || logged_at, ', ') FROM time_logs;",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            "60 pairing 2024-02-03 14:30:00, 30 Time spent in Jira 2024-02-01 09:00:00",
+            logs
+        );
+
+        let (report, _) = import(&conn, &issues, &request).unwrap();
+        assert_eq!(
+            (0, vec![String::from("APP-1"), String::from("APP-2")]),
+            (report.imported, report.skipped)
+        );
+        request.statuses.insert(String::from("Closed"), 9);
+        assert_eq!(1, import(&conn, &issues, &request).unwrap().0.errors.len());
+        assert_eq!("2024-12-31 00:05:00", date("31/Dec/24 12:05 AM"));
+    }
+}
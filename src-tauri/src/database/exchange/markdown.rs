@@ -0,0 +1,199 @@
+use rusqlite::{params, Connection, Result};
+use serde::Deserialize;
+
+use crate::database::models::{Board, DurationInput, EntityKind, State, TaskSummary};
+
+/// What goes into a Markdown export.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// write task descriptions below their lines.
+    pub descriptions: bool,
+    /// leave out states with no tasks.
+    pub skip_empty_states: bool,
+}
+
+/// Render not archived board `id`, or every board of group `id`, as
+/// Markdown: a heading per state and a checklist line per task, checked
+/// when in a done state, with its priority, estimate and progress.
+pub fn render(
+    conn: &Connection,
+    kind: EntityKind,
+    id: i64,
+    options: &MarkdownOptions,
+) -> Result<String> {
+    match kind {
+        EntityKind::Board => render_board(conn, &Board::load(conn, id)?, 1, options),
+        EntityKind::Group => {
+            let name: String = conn.query_row(
+                "SELECT name FROM groups WHERE id = ?1 AND archived_at IS NULL;",
+                params![id],
+                |row| row.get(0),
+            )?;
+            let mut markdown = format!("# {}\n", inline(&name));
+            for board in Board::load_by_group(conn, id)? {
+                markdown.push('\n');
+                markdown.push_str(&render_board(conn, &board, 2, options)?);
+            }
+
+            Ok(markdown)
+        }
+        _ => Err(rusqlite::Error::InvalidParameterName(format!(
+            "cannot render {:?} as markdown",
+            kind
+        ))),
+    }
+}
+
+fn render_board(
+    conn: &Connection,
+    board: &Board,
+    level: usize,
+    options: &MarkdownOptions,
+) -> Result<String> {
+    let board_id = board.get_id().unwrap_or_default();
+    let time = board.get_working_time();
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE t.board_id = ?1 AND {} ORDER BY t.position, t.id;",
+        TaskSummary::SELECT,
+        TaskSummary::NOT_ARCHIVED
+    ))?;
+    let tasks = stmt
+        .query_map(params![board_id], TaskSummary::from_row)?
+        .collect::<Result<Vec<TaskSummary>>>()?;
+
+    let mut markdown = format!("{} {}\n", "#".repeat(level), inline(board.get_name()));
+    for state in State::load_by_board(conn, board_id)? {
+        let state_tasks: Vec<&TaskSummary> = tasks
+            .iter()
+            .filter(|task| Some(task.state_id) == state.get_id())
+            .collect();
+        if options.skip_empty_states && state_tasks.is_empty() {
+            continue;
+        }
+
+        markdown.push_str(&format!(
+            "\n{} {} ({})\n\n",
+            "#".repeat(level + 1),
+            inline(state.get_name()),
+            state_tasks.len()
+        ));
+        if state_tasks.is_empty() {
+            markdown.push_str("_No tasks._\n");
+        }
+        for task in state_tasks {
+            let mut details = vec![];
+            if let Some(priority) = &task.priority_name {
+                // backslashes don't escape inside code, a longer fence does.
+                details.push(match priority.contains('`') {
+                    true => format!("`` {} ``", priority),
+                    false => format!("`{}`", priority),
+                });
+            }
+            if let Some(duration) = task.duration {
+                details.push(DurationInput::split(duration, &time).format(&time));
+            }
+            if let Some(progress) = task.progress.filter(|_| !task.state_done) {
+                details.push(format!("{}%", (progress * 100.0).round()));
+            }
+            if let Some(due_at) = &task.due_at {
+                details.push(format!("due {}", due_at));
+            }
+
+            markdown.push_str(&format!(
+                "- [{}] {}",
+                if task.state_done { "x" } else { " " },
+                inline(&task.name)
+            ));
+            if !details.is_empty() {
+                markdown.push_str(&format!(" · {}", details.join(" · ")));
+            }
+            markdown.push('\n');
+
+            let description = task.description.as_deref().unwrap_or_default().trim();
+            if options.descriptions && !description.is_empty() {
+                for line in description.lines() {
+                    match line.trim().is_empty() {
+                        true => markdown.push('\n'),
+                        false => markdown.push_str(&format!("  {}\n", line)),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(markdown)
+}
+
+/// Keep `text` in a single Markdown line, escaping what Markdown would
+/// read as emphasis, links, headings, tables or code.
+fn inline(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mut escaped = String::new();
+    for c in line.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '#' | '|' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use rusqlite::Connection;
+
+    use super::{render, MarkdownOptions};
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::EntityKind;
+
+    #[test]
+    fn test_render_board_and_group() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Review', 1, 1, 0), (3, 'Done', 2, 1, 1);
+            INSERT INTO tasks(id, name, description, duration, progress, priority, state_id, board_id, position)
+                VALUES (1, 'login', 'use oauth
+and tokens', 570, 0.5, 3, 1, 1, 0)
+                    , (2, 'signup', NULL, NULL, NULL, NULL, 3, 1, 0);"#,
+        )
+        .unwrap();
+
+        let options = MarkdownOptions {
+            descriptions: true,
+            ..MarkdownOptions::default()
+        };
+        assert_eq!(
+            "# app\n\n## Todo (1)\n\n- [ ] login · `High` · 1d 1h 30m · 50%\n  use oauth\n  and tokens\n\n## Review (0)\n\n_No tasks._\n\n## Done (1)\n\n- [x] signup\n",
+            render(&conn, EntityKind::Board, 1, &options).unwrap()
+        );
+
+        let group = render(
+            &conn,
+            EntityKind::Group,
+            1,
+            &MarkdownOptions {
+                skip_empty_states: true,
+                ..MarkdownOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(group.starts_with("# work\n\n## app\n\n### Todo (1)\n"));
+        assert!(!group.contains("Review"));
+        assert!(render(&conn, EntityKind::Task, 1, &options).is_err());
+
+        conn.execute(
+            r#"UPDATE tasks SET name = '# fix *all* [links] | `code` \o/' WHERE id = 2;"#,
+            [],
+        )
+        .unwrap();
+        assert!(render(&conn, EntityKind::Board, 1, &options)
+            .unwrap()
+            .ends_with("- [x] \\# fix \\*all\\* \\[links\\] \\| \\`code\\` \\\\o/\n"));
+    }
+}
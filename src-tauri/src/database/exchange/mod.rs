@@ -0,0 +1,9 @@
+pub mod csv;
+pub mod github;
+pub mod ical;
+pub mod jira;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todo_txt;
+pub mod trello;
+pub mod workspace;
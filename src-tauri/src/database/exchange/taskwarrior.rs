@@ -0,0 +1,705 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::exchange::workspace::{ImportError, Problem};
+use crate::database::filter::{self, Scope};
+use crate::database::history::Target;
+use crate::database::models::{EntityKind, Priority};
+
+/// Taskwarrior priorities, least urgent first, with the names they match.
+const PRIORITIES: [(&str, &[&str]); 3] = [
+    ("L", &["low"]),
+    ("M", &["medium", "normal"]),
+    ("H", &["high"]),
+];
+
+/// Task of `task export`, or of `task import`, only the fields kept.
+/// Dates use Taskwarrior format, `20240501T090000Z`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskwarriorTask {
+    uuid: String,
+    description: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Annotation {
+    #[serde(default)]
+    entry: Option<String>,
+    description: String,
+}
+
+/// Where Taskwarrior tasks go.
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskwarriorImport {
+    /// group of the boards matched, or created, for projects.
+    pub group_id: i64,
+    /// board of each project. Projects left out go to the board of the
+    /// group with the same name, created when missing.
+    #[serde(default)]
+    pub projects: BTreeMap<String, i64>,
+    /// board of tasks without project, a board named `Inbox` by default.
+    #[serde(default)]
+    pub default_board_id: Option<i64>,
+}
+
+/// What goes into a Taskwarrior export.
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskwarriorExport {
+    #[serde(default)]
+    pub scope: Scope,
+    /// keep archived tasks, as deleted.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// What a Taskwarrior import changed.
+#[derive(Debug, Default, Serialize)]
+pub struct TaskwarriorSummary {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    created_boards: Vec<String>,
+    created_states: usize,
+    /// recurring templates, their instances are imported.
+    skipped: usize,
+}
+
+/// States of a board tasks go to by Taskwarrior status.
+#[derive(Debug, Clone, Copy)]
+struct BoardStates {
+    pending: i64,
+    started: i64,
+    done: i64,
+}
+
+/// Turn a Taskwarrior date into an SQL one. Other values are kept, for
+/// SQLite to read or reject.
+fn to_sql_date(value: &str) -> String {
+    let digits: Vec<char> = value.chars().collect();
+    let compact = digits.len() == 16
+        && digits[8] == 'T'
+        && digits[15] == 'Z'
+        && digits
+            .iter()
+            .enumerate()
+            .all(|(index, char)| [8, 15].contains(&index) || char.is_ascii_digit());
+    if !compact {
+        return String::from(value);
+    }
+
+    format!(
+        "{}-{}-{} {}:{}:{}",
+        &value[..4],
+        &value[4..6],
+        &value[6..8],
+        &value[9..11],
+        &value[11..13],
+        &value[13..15]
+    )
+}
+
+/// Parse a `task export` JSON array.
+pub fn parse(contents: &str) -> std::result::Result<Vec<TaskwarriorTask>, ImportError> {
+    let tasks: Vec<TaskwarriorTask> =
+        serde_json::from_str(contents).map_err(|error| ImportError::Invalid {
+            problems: vec![Problem::new("file", None, error.to_string())],
+        })?;
+    let mut problems = vec![];
+    for (row, task) in tasks.iter().enumerate() {
+        if task.uuid.trim().is_empty() {
+            problems.push(Problem::new(
+                "tasks",
+                Some(row),
+                String::from("uuid is empty"),
+            ));
+        }
+        if task.description.trim().is_empty() {
+            problems.push(Problem::new(
+                "tasks",
+                Some(row),
+                format!("task {} has no description", task.uuid),
+            ));
+        }
+    }
+
+    match problems.is_empty() {
+        true => Ok(tasks),
+        false => Err(ImportError::Invalid { problems }),
+    }
+}
+
+/// Check the group of `request` exists and its boards are in it.
+pub fn check(
+    conn: &Connection,
+    request: &TaskwarriorImport,
+) -> std::result::Result<(), ImportError> {
+    let group: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM groups WHERE id = ?1 AND archived_at IS NULL);",
+        params![request.group_id],
+        |row| row.get(0),
+    )?;
+    if !group {
+        return Err(ImportError::Invalid {
+            problems: vec![Problem::new(
+                "request",
+                None,
+                format!("group_id: group {} does not exist", request.group_id),
+            )],
+        });
+    }
+    let mut problems = vec![];
+    let boards = request
+        .projects
+        .iter()
+        .map(|(project, id)| (format!("project \"{}\"", project), *id))
+        .chain(
+            request
+                .default_board_id
+                .map(|id| (String::from("default_board_id"), id)),
+        );
+    for (field, id) in boards {
+        let found: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM boards WHERE id = ?1 AND group_id = ?2);",
+            params![id, request.group_id],
+            |row| row.get(0),
+        )?;
+        if !found {
+            problems.push(Problem::new(
+                "request",
+                None,
+                format!("{}: board {} is not in the group", field, id),
+            ));
+        }
+    }
+
+    match problems.is_empty() {
+        true => Ok(()),
+        false => Err(ImportError::Invalid { problems }),
+    }
+}
+
+/// Tasks already imported, or exported, with the uuids of `tasks`.
+pub fn imported(conn: &Connection, tasks: &[TaskwarriorTask]) -> Result<Vec<Target>> {
+    let mut found = vec![];
+    for task in tasks {
+        if let Some(id) = find(conn, &task.uuid)? {
+            found.push((EntityKind::Task, id));
+        }
+    }
+
+    Ok(found)
+}
+
+fn find(conn: &Connection, uuid: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM tasks WHERE uuid = ?1 ORDER BY id LIMIT 1;",
+        params![uuid.to_lowercase()],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Find or create the states of `board_id` for pending, started and
+/// completed tasks: the first not done state, the first active one,
+/// and the first done one.
+fn board_states(
+    conn: &Connection,
+    board_id: i64,
+    summary: &mut TaskwarriorSummary,
+    created: &mut Vec<Target>,
+) -> Result<BoardStates> {
+    let mut state = |condition: &str, name: &str, active: bool, done: bool| -> Result<i64> {
+        let found: Option<i64> = conn
+            .query_row(
+                &format!(
+                    r#"SELECT id FROM states WHERE board_id = ?1 AND archived_at IS NULL
+                    AND {} ORDER BY position, id LIMIT 1;"#,
+                    condition
+                ),
+                params![board_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = found {
+            return Ok(id);
+        }
+        conn.execute(
+            r#"INSERT INTO states(name, position, board_id, active, done) VALUES (?1, (
+                SELECT COALESCE(MAX(position) + 1, 0) FROM states WHERE board_id = ?2
+            ), ?2, ?3, ?4);"#,
+            params![name, board_id, active, done],
+        )?;
+        let id = conn.last_insert_rowid();
+        created.push((EntityKind::State, id));
+        summary.created_states += 1;
+
+        Ok(id)
+    };
+    let pending = state("NOT done", "Pending", false, false)?;
+    let started = state("NOT done AND active", "Started", true, false)?;
+    let done = state("done", "Completed", false, true)?;
+
+    Ok(BoardStates {
+        pending,
+        started,
+        done,
+    })
+}
+
+/// Import `tasks` into boards of `request.group_id`, one per project.
+/// Tasks are matched by uuid, so reruns, and files exported from here,
+/// update tasks instead of duplicating them. Tags become labels, the
+/// H, M and L priorities the board priorities of matching name or
+/// urgency, annotations the description, one line each. Pending,
+/// started and completed tasks go to states of matching kind, created
+/// when missing; deleted tasks are archived. Imported tasks only move
+/// when their status changed to or from completed.
+pub fn import(
+    conn: &Connection,
+    tasks: &[TaskwarriorTask],
+    request: &TaskwarriorImport,
+) -> Result<(TaskwarriorSummary, Vec<Target>)> {
+    let mut summary = TaskwarriorSummary::default();
+    let mut created = vec![];
+    let mut boards: HashMap<Option<String>, i64> = request
+        .projects
+        .iter()
+        .map(|(project, id)| (Some(project.clone()), *id))
+        .collect();
+    if let Some(id) = request.default_board_id {
+        boards.insert(None, id);
+    }
+    let mut states: HashMap<i64, BoardStates> = HashMap::new();
+    let mut schemes: HashMap<i64, Vec<Priority>> = HashMap::new();
+
+    for task in tasks {
+        if task.status == "recurring" {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let board_id = match boards.get(&task.project) {
+            Some(&id) => id,
+            None => {
+                let name = task.project.as_deref().unwrap_or("Inbox");
+                let found: Option<i64> = conn
+                    .query_row(
+                        r#"SELECT id FROM boards WHERE group_id = ?1 AND name = ?2 COLLATE NOCASE
+                        AND archived_at IS NULL ORDER BY position LIMIT 1;"#,
+                        params![request.group_id, name],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let id = match found {
+                    Some(id) => id,
+                    None => {
+                        conn.execute(
+                            r#"INSERT INTO boards(name, position, group_id) VALUES (?1, (
+                                SELECT COALESCE(MAX(position) + 1, 0) FROM boards WHERE group_id = ?2
+                            ), ?2);"#,
+                            params![name, request.group_id],
+                        )?;
+                        let id = conn.last_insert_rowid();
+                        created.push((EntityKind::Board, id));
+                        summary.created_boards.push(String::from(name));
+                        id
+                    }
+                };
+                boards.insert(task.project.clone(), id);
+                id
+            }
+        };
+        let board_states = match states.entry(board_id) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                *entry.insert(board_states(conn, board_id, &mut summary, &mut created)?)
+            }
+        };
+        let scheme = match schemes.entry(board_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut scheme = Priority::load_scheme(conn, Some(board_id))?;
+                scheme.reverse();
+                entry.insert(scheme)
+            }
+        };
+        let priority = task.priority.as_deref().and_then(|letter| {
+            let level = PRIORITIES
+                .iter()
+                .position(|(known, _)| known.eq_ignore_ascii_case(letter))?;
+            scheme
+                .iter()
+                .find(|priority| {
+                    PRIORITIES[level]
+                        .1
+                        .contains(&priority.get_name().to_lowercase().as_str())
+                })
+                .or_else(|| scheme.get(level * scheme.len().saturating_sub(1) / 2))
+                .and_then(Priority::get_id)
+        });
+
+        let done = task.status == "completed";
+        let state_id = match (done, task.start.is_some()) {
+            (true, _) => board_states.done,
+            (false, true) => board_states.started,
+            (false, false) => board_states.pending,
+        };
+        let labels = serde_json::to_string(&task.tags).unwrap_or_else(|_| String::from("[]"));
+        let description: Vec<String> = task
+            .annotations
+            .iter()
+            .map(|annotation| match &annotation.entry {
+                Some(entry) => format!("[{}] {}", to_sql_date(entry), annotation.description),
+                None => annotation.description.clone(),
+            })
+            .collect();
+        let description = description.join("\n");
+        let date = |value: &Option<String>| value.as_deref().map(to_sql_date);
+        let archived = task.status == "deleted";
+
+        let Some(task_id) = find(conn, &task.uuid)? else {
+            conn.execute(
+                r#"INSERT INTO tasks(
+                    name, description, priority, state_id, board_id, position, labels
+                    , due_at, started_at, ended_at, created_at, archived_at, uuid
+                ) VALUES (?1, NULLIF(?2, ''), ?3, ?4, ?5, (
+                    SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?4
+                ), ?6, datetime(?7), datetime(?8), datetime(?9), datetime(?10)
+                    , CASE WHEN ?11 THEN COALESCE(datetime(?9), datetime(?12), datetime('now')) END
+                    , ?13);"#,
+                params![
+                    task.description,
+                    description,
+                    priority,
+                    state_id,
+                    board_id,
+                    labels,
+                    date(&task.due),
+                    date(&task.start),
+                    date(&task.end),
+                    date(&task.entry),
+                    archived,
+                    date(&task.modified),
+                    task.uuid.to_lowercase(),
+                ],
+            )?;
+            created.push((EntityKind::Task, conn.last_insert_rowid()));
+            summary.created += 1;
+            continue;
+        };
+
+        let mut changed = conn.execute(
+            r#"UPDATE tasks SET name = ?2, description = NULLIF(?3, ''), labels = ?4
+                , due_at = CASE WHEN due_at IS datetime(?5) THEN due_at ELSE datetime(?5) END
+                , priority = CASE WHEN board_id = ?6 THEN ?7 ELSE priority END
+                , archived_at = CASE WHEN NOT ?8 THEN NULL
+                    ELSE COALESCE(archived_at, datetime('now')) END
+            WHERE id = ?1 AND (name IS NOT ?2 OR description IS NOT NULLIF(?3, '')
+                OR labels IS NOT ?4 OR due_at IS NOT datetime(?5)
+                OR (board_id = ?6 AND priority IS NOT ?7) OR (archived_at IS NOT NULL) IS NOT ?8);"#,
+            params![
+                task_id,
+                task.description,
+                description,
+                labels,
+                date(&task.due),
+                board_id,
+                priority,
+                archived,
+            ],
+        )?;
+        changed += conn.execute(
+            r#"UPDATE tasks SET state_id = ?2
+                , position = (SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?2)
+                , ended_at = CASE WHEN ?3 THEN datetime(?4) END
+            WHERE id = ?1 AND board_id = ?5 AND (
+                SELECT done FROM states WHERE id = tasks.state_id
+            ) IS NOT ?3;"#,
+            params![task_id, state_id, done, date(&task.end), board_id],
+        )?;
+        match changed > 0 {
+            true => summary.updated += 1,
+            false => summary.unchanged += 1,
+        }
+    }
+
+    Ok((summary, created))
+}
+
+/// Export tasks of `options.scope` in Taskwarrior import format,
+/// archived ones as deleted. Each task keeps its uuid, so importing the
+/// file back in Taskwarrior, or here, updates tasks instead of
+/// duplicating them. Boards become projects, labels tags, and
+/// description lines annotations.
+pub fn export(conn: &Connection, options: &TaskwarriorExport) -> Result<String> {
+    let (condition, mut values) = filter::board_condition(options.scope);
+    values.push(Value::from(options.archived));
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT t.uuid, t.name, t.description, t.labels, t.priority, t.board_id
+            , b.name AS board_name, s.done, t.archived_at IS NOT NULL AS archived
+            , strftime('%Y%m%dT%H%M%SZ', t.created_at) AS entry
+            , strftime('%Y%m%dT%H%M%SZ', t.updated_at) AS modified
+            , strftime('%Y%m%dT%H%M%SZ', t.started_at) AS start
+            , strftime('%Y%m%dT%H%M%SZ', t.ended_at) AS end
+            , strftime('%Y%m%dT%H%M%SZ', t.due_at) AS due
+        FROM tasks t
+            JOIN states s ON s.id = t.state_id
+            JOIN boards b ON b.id = t.board_id
+            JOIN groups g ON g.id = b.group_id
+        WHERE s.archived_at IS NULL AND b.archived_at IS NULL AND g.archived_at IS NULL
+            AND {} AND (? OR t.archived_at IS NULL)
+        ORDER BY g.position, b.position, s.position, t.position, t.id;"#,
+        condition
+    ))?;
+    let mut rows = stmt.query(params_from_iter(values.iter()))?;
+
+    let mut schemes: HashMap<i64, Vec<Priority>> = HashMap::new();
+    let mut tasks = vec![];
+    while let Some(row) = rows.next()? {
+        let board_id: i64 = row.get("board_id")?;
+        let priority: Option<i64> = row.get("priority")?;
+        let scheme = match schemes.entry(board_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut scheme = Priority::load_scheme(conn, Some(board_id))?;
+                scheme.reverse();
+                entry.insert(scheme)
+            }
+        };
+        let priority = scheme
+            .iter()
+            .position(|known| known.get_id() == priority)
+            .map(|index| {
+                let name = scheme[index].get_name().to_lowercase();
+                PRIORITIES
+                    .iter()
+                    .find(|(_, names)| names.contains(&name.as_str()))
+                    .map_or_else(
+                        || {
+                            let level = (index * 2 + scheme.len().saturating_sub(1) / 2)
+                                / scheme.len().saturating_sub(1).max(1);
+                            PRIORITIES[level.min(2)].0
+                        },
+                        |(letter, _)| *letter,
+                    )
+            });
+
+        let entry: Option<String> = row.get("entry")?;
+        let description: Option<String> = row.get("description")?;
+        let annotations = description
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let dated = line
+                    .strip_prefix('[')
+                    .and_then(|line| line.split_once("] "))
+                    .filter(|(date, _)| date.len() == 19);
+                match dated {
+                    Some((date, text)) => Annotation {
+                        entry: Some(date.replace(['-', ':'], "").replace(' ', "T") + "Z"),
+                        description: String::from(text),
+                    },
+                    None => Annotation {
+                        entry: entry.clone(),
+                        description: String::from(line),
+                    },
+                }
+            })
+            .collect();
+        let labels: String = row.get("labels")?;
+        let tags: Vec<String> = serde_json::from_str::<Vec<String>>(&labels)
+            .unwrap_or_default()
+            .iter()
+            .map(|label| label.split_whitespace().collect::<Vec<&str>>().join("_"))
+            .collect();
+        let done: bool = row.get("done")?;
+        let archived: bool = row.get("archived")?;
+
+        tasks.push(TaskwarriorTask {
+            uuid: row.get::<_, Option<String>>("uuid")?.unwrap_or_default(),
+            description: row.get("name")?,
+            status: String::from(match (archived, done) {
+                (true, _) => "deleted",
+                (false, true) => "completed",
+                (false, false) => "pending",
+            }),
+            project: Some(row.get("board_name")?),
+            tags,
+            priority: priority.map(String::from),
+            entry,
+            modified: row.get("modified")?,
+            start: row.get("start")?,
+            end: match done || archived {
+                true => row.get("end")?,
+                false => None,
+            },
+            due: row.get("due")?,
+            annotations,
+        });
+    }
+
+    Ok(serde_json::to_string_pretty(&tasks).unwrap_or_else(|_| String::from("[]")))
+}
+
+#[cfg(test)]
+mod taskwarrior_tests {
+    use std::collections::BTreeMap;
+
+    use rusqlite::Connection;
+
+    use super::{check, export, import, imported, parse, TaskwarriorExport, TaskwarriorImport};
+    use crate::database::filter::Scope;
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_import_and_export_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Done', 1, 1, 1);"#,
+        )
+        .unwrap();
+        let dump = r#"[
+            {"id": 1, "uuid": "8F1D3A52-0B6E-4C55-9E0C-2D1B0F6A7C01", "description": "login",
+                "status": "pending", "project": "app", "tags": ["ui"], "priority": "H",
+                "entry": "20240501T090000Z", "due": "20240510T120000Z", "start": "20240502T080000Z",
+                "annotations": [{"entry": "20240502T100000Z", "description": "use oauth"}],
+                "urgency": 9.2},
+            {"id": 0, "uuid": "0c2e5b7a-1d43-4f8e-a6b9-3e7d2c1f0a92", "description": "fence",
+                "status": "completed", "project": "home", "priority": "L",
+                "entry": "20240401T090000Z", "end": "20240403T180000Z"},
+            {"id": 0, "uuid": "5b1c9e20-7a3d-4e6f-8b2a-9c0d1e2f3a4b", "description": "old",
+                "status": "deleted", "modified": "20240405T100000Z"},
+            {"id": 0, "uuid": "9e8d7c6b-5a49-4382-a1b0-c9d8e7f6a5b4", "description": "standup",
+                "status": "recurring"}
+        ]"#;
+        let tasks = parse(dump).unwrap();
+        let mut request = TaskwarriorImport {
+            group_id: 9,
+            projects: BTreeMap::new(),
+            default_board_id: None,
+        };
+        // boards for projects would be created in a missing group.
+        assert!(check(&conn, &request).is_err());
+        request.group_id = 1;
+        assert!(check(&conn, &request).is_ok());
+
+        let (summary, created) = import(&conn, &tasks, &request).unwrap();
+        assert_eq!((3, 1), (summary.created, summary.skipped));
+        assert_eq!(vec!["home", "Inbox"], summary.created_boards);
+        // app gets a started state, home and Inbox the three of them.
+        assert_eq!(7, summary.created_states);
+        assert_eq!(12, created.len());
+
+        let rows: String = conn
+            .query_row(
+                r#"SELECT group_concat(t.name || ' ' || b.name || ' ' || s.name || ' '
+                    || COALESCE(p.name, '-') || ' ' || t.labels || ' '
+                    || COALESCE(t.archived_at, '-') || ' ' || t.uuid, ', ')
+                FROM tasks t JOIN states s ON s.id = t.state_id JOIN boards b ON b.id = t.board_id
+                    LEFT JOIN priorities p ON p.id = t.priority;"#,
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            r#"login app Started High ["ui"] - 8f1d3a52-0b6e-4c55-9e0c-2d1b0f6a7c01, fence home Completed Low [] - 0c2e5b7a-1d43-4f8e-a6b9-3e7d2c1f0a92, old Inbox Pending - [] 2024-04-05 10:00:00 5b1c9e20-7a3d-4e6f-8b2a-9c0d1e2f3a4b"#,
+            rows
+        );
+
+        let exported = export(
+            &conn,
+            &TaskwarriorExport {
+                scope: Scope::Board(1),
+                archived: false,
+            },
+        )
+        .unwrap();
+        let round_trip = parse(&exported).unwrap();
+        assert_eq!(1, round_trip.len());
+        let task = &round_trip[0];
+        assert_eq!(
+            (
+                "8f1d3a52-0b6e-4c55-9e0c-2d1b0f6a7c01",
+                "pending",
+                Some("H"),
+                Some("20240510T120000Z"),
+                Some("20240502T100000Z"),
+                "use oauth",
+            ),
+            (
+                task.uuid.as_str(),
+                task.status.as_str(),
+                task.priority.as_deref(),
+                task.due.as_deref(),
+                task.annotations[0].entry.as_deref(),
+                task.annotations[0].description.as_str(),
+            )
+        );
+        assert_eq!(1, imported(&conn, &round_trip).unwrap().len());
+        let (summary, created) = import(&conn, &round_trip, &request).unwrap();
+        assert_eq!(
+            (0, 0, 1),
+            (summary.created, summary.updated, summary.unchanged)
+        );
+        assert!(created.is_empty());
+
+        let completed = dump.replace(
+            r#""description": "login",
+                "status": "pending""#,
+            r#""description": "login page",
+                "status": "completed""#,
+        );
+        let (summary, _) = import(&conn, &parse(&completed).unwrap(), &request).unwrap();
+        assert_eq!(
+            (0, 1, 2),
+            (summary.created, summary.updated, summary.unchanged)
+        );
+        let state: String = conn
+            .query_row(
+                "SELECT s.name FROM tasks t JOIN states s ON s.id = t.state_id WHERE t.name = 'login page';",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!("Done", state);
+
+        let tasks: String = conn
+            .query_row(
+                "SELECT COUNT(*) || ' ' || COUNT(DISTINCT uuid) FROM tasks;",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!("3 3", tasks);
+    }
+}
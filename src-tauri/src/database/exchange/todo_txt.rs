@@ -0,0 +1,607 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::exchange::csv::RowError;
+use crate::database::history::Target;
+use crate::database::models::{Board, EntityKind, Priority, State, TaskSummary};
+
+/// Task read from a todo.txt line.
+#[derive(Debug, PartialEq)]
+pub struct TodoItem {
+    line: usize,
+    done: bool,
+    priority: Option<char>,
+    completed: Option<String>,
+    created: Option<String>,
+    text: String,
+    projects: Vec<String>,
+    contexts: Vec<String>,
+    due: Option<String>,
+    /// `id:` tag, the task the line was exported from.
+    id: Option<i64>,
+    /// `state:` tag, with spaces written as `_`.
+    state: Option<String>,
+}
+
+/// How a todo.txt file is synced into a board.
+#[derive(Debug, Default, Deserialize)]
+pub struct TodoTxtImport {
+    pub board_id: i64,
+    /// priority of each letter. Letters left out follow the board
+    /// priorities, `A` being the most urgent.
+    #[serde(default)]
+    pub priorities: BTreeMap<char, i64>,
+    /// state of open tasks without a known `state:` tag, first not done
+    /// state of the board by default.
+    #[serde(default)]
+    pub open_state_id: Option<i64>,
+    /// state of completed tasks without a known `state:` tag, first done
+    /// state of the board by default.
+    #[serde(default)]
+    pub done_state_id: Option<i64>,
+    /// only import lines with this `+project`, all lines when missing.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// keep `+projects` as labels, next to `@contexts`.
+    #[serde(default)]
+    pub projects_as_labels: bool,
+}
+
+/// What a todo.txt import changed. Lines with errors are skipped.
+#[derive(Debug, Default, Serialize)]
+pub struct TodoTxtReport {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    /// lines left out by the project filter.
+    ignored: usize,
+    errors: Vec<RowError>,
+}
+
+impl TodoTxtReport {
+    /// Add errors found reading the file.
+    pub fn add_errors(&mut self, errors: Vec<RowError>) {
+        self.errors.splice(0..0, errors);
+    }
+}
+
+/// Write `text` as a single todo.txt word.
+fn word(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join("_")
+}
+
+/// Tags read from todo.txt lines, any other `key:value` is text.
+const TAGS: [&str; 4] = ["due", "pri", "state", "id"];
+
+/// Write task `name` as todo.txt text, escaping with a `\` the words
+/// [`parse`] would read otherwise: projects, contexts, tags, and a first
+/// word that looks like a completion mark, a priority or a date.
+fn text(name: &str) -> String {
+    name.split_whitespace()
+        .enumerate()
+        .map(|(index, word)| {
+            let marker = index == 0 && (word == "x" || is_priority(word) || is_date(word));
+            let tag = word
+                .split_once(':')
+                .is_some_and(|(key, _)| TAGS.contains(&key));
+            let escaped = marker
+                || tag
+                || word.starts_with('\\')
+                || (word.len() > 1 && (word.starts_with('+') || word.starts_with('@')));
+            match escaped {
+                true => format!("\\{}", word),
+                false => String::from(word),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn is_priority(word: &str) -> bool {
+    word.len() == 3
+        && word.starts_with('(')
+        && word.ends_with(')')
+        && word.as_bytes()[1].is_ascii_uppercase()
+}
+
+fn is_date(value: &str) -> bool {
+    value.len() == 10
+        && value.char_indices().all(|(index, char)| match index {
+            4 | 7 => char == '-',
+            _ => char.is_ascii_digit(),
+        })
+}
+
+/// Day of an SQL date or datetime, `None` when it doesn't start with one.
+fn day(value: &str) -> Option<&str> {
+    value.get(..10).filter(|day| is_date(day))
+}
+
+/// Export not archived tasks of `board_id` as todo.txt lines, in board
+/// order. Priorities become letters, `A` the most urgent of the board,
+/// the board a `+project` and labels `@contexts`. Due dates, states and
+/// task ids are kept as `due:`, `state:` and `id:` tags, so the file can
+/// be imported back.
+pub fn export(conn: &Connection, board_id: i64) -> Result<String> {
+    let board = Board::load(conn, board_id)?;
+    let scheme = Priority::load_scheme(conn, Some(board_id))?;
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE t.board_id = ?1 AND {} ORDER BY state_position, t.position, t.id;",
+        TaskSummary::SELECT,
+        TaskSummary::NOT_ARCHIVED
+    ))?;
+    let tasks = stmt
+        .query_map(params![board_id], TaskSummary::from_row)?
+        .collect::<Result<Vec<TaskSummary>>>()?;
+
+    let mut todo = String::new();
+    for task in tasks {
+        let letter = scheme
+            .iter()
+            .position(|priority| priority.get_id() == task.priority_id)
+            .map(|index| char::from(b'A' + index.min(25) as u8));
+        let created = task.created_at.as_deref().and_then(day);
+
+        let mut words = vec![];
+        match task.state_done {
+            true => {
+                words.push(String::from("x"));
+                let ended = task.ended_at.as_deref().or(task.updated_at.as_deref());
+                if let Some(ended) = ended.and_then(day) {
+                    words.push(ended.to_string());
+                    words.extend(created.map(String::from));
+                }
+            }
+            false => {
+                words.extend(letter.map(|letter| format!("({})", letter)));
+                words.extend(created.map(String::from));
+            }
+        }
+        words.push(text(&task.name));
+        words.push(format!("+{}", word(board.get_name())));
+        words.extend(task.labels.iter().map(|label| format!("@{}", word(label))));
+        if let Some(due) = task.due_at.as_deref().and_then(day) {
+            words.push(format!("due:{}", due));
+        }
+        if let Some(letter) = letter.filter(|_| task.state_done) {
+            words.push(format!("pri:{}", letter));
+        }
+        words.push(format!("state:{}", word(&task.state_name)));
+        words.push(format!("id:{}", task.id));
+
+        todo.push_str(&words.join(" "));
+        todo.push('\n');
+    }
+
+    Ok(todo)
+}
+
+/// Read the tasks of a todo.txt file, skipping blank lines. Tags other
+/// than `due:`, `pri:`, `state:` and `id:` are kept in the text, and
+/// words escaped with a `\` are kept in it as written after the `\`.
+pub fn parse(contents: &str) -> (Vec<TodoItem>, Vec<RowError>) {
+    let mut items = vec![];
+    let mut errors = vec![];
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+        let mut item = TodoItem {
+            line: line_number,
+            done: false,
+            priority: None,
+            completed: None,
+            created: None,
+            text: String::new(),
+            projects: vec![],
+            contexts: vec![],
+            due: None,
+            id: None,
+            state: None,
+        };
+
+        if words.peek() == Some(&"x") {
+            words.next();
+            item.done = true;
+            if let Some(completed) = words.next_if(|word| is_date(word)) {
+                item.completed = Some(completed.to_string());
+            }
+        } else if let Some(priority) = words.next_if(|word| is_priority(word)) {
+            item.priority = priority.chars().nth(1);
+        }
+        if let Some(created) = words.next_if(|word| is_date(word)) {
+            item.created = Some(created.to_string());
+        }
+
+        let mut text = vec![];
+        let mut error = |column: &str, message: String| {
+            errors.push(RowError::new(line_number, Some(column), message));
+        };
+        for word in words {
+            if let Some(escaped) = word.strip_prefix('\\') {
+                text.push(escaped);
+                continue;
+            }
+            match word.split_once(':') {
+                Some(("due", due)) if is_date(due) => item.due = Some(due.to_string()),
+                Some(("due", due)) => error("due", format!("invalid date \"{}\"", due)),
+                Some(("pri", letter))
+                    if letter.len() == 1 && letter.as_bytes()[0].is_ascii_uppercase() =>
+                {
+                    item.priority = letter.chars().next()
+                }
+                Some(("state", state)) if !state.is_empty() => item.state = Some(state.to_string()),
+                Some(("id", id)) => match id.parse() {
+                    Ok(id) => item.id = Some(id),
+                    Err(_) => error("id", format!("invalid task id \"{}\"", id)),
+                },
+                _ => match (word.strip_prefix('+'), word.strip_prefix('@')) {
+                    (Some(project), _) if !project.is_empty() => {
+                        item.projects.push(project.to_string())
+                    }
+                    (_, Some(context)) if !context.is_empty() => {
+                        item.contexts.push(context.to_string())
+                    }
+                    _ => text.push(word),
+                },
+            }
+        }
+        item.text = text.join(" ");
+        if item.text.is_empty() {
+            error("text", String::from("task has no text"));
+        }
+
+        if errors
+            .last()
+            .is_none_or(|error| error.line() != line_number)
+        {
+            items.push(item);
+        }
+    }
+
+    (items, errors)
+}
+
+/// Tasks of `board_id` that `items` were exported from, that an import updates.
+pub fn synced(conn: &Connection, items: &[TodoItem], board_id: i64) -> Result<Vec<Target>> {
+    let mut tasks = vec![];
+    for id in items.iter().filter_map(|item| item.id) {
+        let found: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM tasks WHERE id = ?1 AND board_id = ?2;",
+                params![id, board_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        tasks.extend(found.map(|id| (EntityKind::Task, id)));
+    }
+
+    Ok(tasks)
+}
+
+/// Sync `items` into `request.board_id`: lines with the `id:` of a task
+/// of the board update it, the others create tasks at the end of their
+/// states. Contexts are matched to the board labels they were exported
+/// from. Tasks go to the state of their `state:` tag when it is in the
+/// board and agrees with the line being completed, to the open or done
+/// state of `request` otherwise. Nothing is written when `request` maps
+/// to priorities or states the board does not use.
+pub fn import(
+    conn: &Connection,
+    items: &[TodoItem],
+    request: &TodoTxtImport,
+) -> Result<(TodoTxtReport, Vec<Target>)> {
+    let mut report = TodoTxtReport::default();
+    let scheme = Priority::load_scheme(conn, Some(request.board_id))?;
+    let states = State::load_by_board(conn, request.board_id)?;
+    for (letter, id) in &request.priorities {
+        if !scheme.iter().any(|priority| priority.get_id() == Some(*id)) {
+            report.errors.push(RowError::new(
+                1,
+                Some("priority"),
+                format!("priority {} of ({}) is not used by the board", id, letter),
+            ));
+        }
+    }
+    let mut default_state = |id: Option<i64>, done: bool, field: &str| {
+        let state = match id {
+            Some(id) => states.iter().find(|state| state.get_id() == Some(id)),
+            None => states
+                .iter()
+                .find(|state| state.is_done() == done)
+                .or(states.first()),
+        };
+        if state.is_none() {
+            report.errors.push(RowError::new(
+                1,
+                Some(field),
+                String::from("state is not in the board"),
+            ));
+        }
+        state
+    };
+    let open_state = default_state(request.open_state_id, false, "open_state_id");
+    let done_state = default_state(request.done_state_id, true, "done_state_id");
+    let (Some(open_state), Some(done_state)) = (open_state, done_state) else {
+        return Ok((report, vec![]));
+    };
+    if !report.errors.is_empty() {
+        return Ok((report, vec![]));
+    }
+
+    let mut stmt = conn.prepare(
+        r#"SELECT DISTINCT l.value FROM tasks t, json_each(t.labels) l
+        WHERE t.board_id = ?1 ORDER BY l.value;"#,
+    )?;
+    let known_labels = stmt
+        .query_map(params![request.board_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    let mut created = vec![];
+    for item in items {
+        if let Some(project) = &request.project {
+            if !item
+                .projects
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(project))
+            {
+                report.ignored += 1;
+                continue;
+            }
+        }
+
+        let priority = item
+            .priority
+            .and_then(|letter| match request.priorities.get(&letter) {
+                Some(id) => Some(*id),
+                None => scheme
+                    .get((letter as usize - 'A' as usize).min(scheme.len().saturating_sub(1)))
+                    .and_then(Priority::get_id),
+            });
+        let mut labels: Vec<&str> = item.contexts.iter().map(String::as_str).collect();
+        if request.projects_as_labels {
+            labels.extend(item.projects.iter().map(String::as_str));
+        }
+        let labels: Vec<&str> = labels
+            .into_iter()
+            .map(|label| {
+                known_labels
+                    .iter()
+                    .find(|known| word(known) == label)
+                    .map_or(label, String::as_str)
+            })
+            .collect();
+        let labels = serde_json::to_string(&labels).unwrap_or_else(|_| String::from("[]"));
+        let state = item
+            .state
+            .as_ref()
+            .and_then(|name| {
+                states
+                    .iter()
+                    .find(|state| word(state.get_name()).eq_ignore_ascii_case(name))
+            })
+            .filter(|state| state.is_done() == item.done)
+            .unwrap_or(match item.done {
+                true => done_state,
+                false => open_state,
+            });
+        let state_id = state.get_id();
+        let task_id = match item.id {
+            Some(id) => conn
+                .query_row(
+                    "SELECT id FROM tasks WHERE id = ?1 AND board_id = ?2;",
+                    params![id, request.board_id],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?,
+            None => None,
+        };
+
+        let Some(task_id) = task_id else {
+            conn.execute(
+                r#"INSERT INTO tasks(
+                    name, priority, state_id, board_id, position, labels, due_at
+                    , created_at, ended_at
+                ) VALUES (?1, ?2, ?3, ?4, (
+                    SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?3
+                ), ?5, datetime(?6), datetime(?7), datetime(?8));"#,
+                params![
+                    item.text,
+                    priority,
+                    state_id,
+                    request.board_id,
+                    labels,
+                    item.due,
+                    item.created,
+                    item.completed,
+                ],
+            )?;
+            created.push((EntityKind::Task, conn.last_insert_rowid()));
+            report.created += 1;
+            continue;
+        };
+
+        let mut changed = conn.execute(
+            r#"UPDATE tasks SET name = ?2, priority = ?3, labels = ?4
+                , due_at = CASE WHEN date(due_at) IS ?5 THEN due_at ELSE datetime(?5) END
+            WHERE id = ?1 AND (name IS NOT ?2 OR priority IS NOT ?3 OR labels IS NOT ?4
+                OR date(due_at) IS NOT ?5);"#,
+            params![task_id, item.text, priority, labels, item.due],
+        )?;
+        changed += conn.execute(
+            r#"UPDATE tasks SET state_id = ?2
+                , position = (SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE state_id = ?2)
+                , ended_at = CASE WHEN ?3 THEN COALESCE(datetime(?4), ended_at) END
+            WHERE id = ?1 AND state_id IS NOT ?2 AND (?5 OR (
+                SELECT done FROM states WHERE id = tasks.state_id
+            ) IS NOT ?3);"#,
+            params![
+                task_id,
+                state_id,
+                item.done,
+                item.completed,
+                item.state.is_some()
+            ],
+        )?;
+        match changed > 0 {
+            true => report.updated += 1,
+            false => report.unchanged += 1,
+        }
+    }
+
+    Ok((report, created))
+}
+
+#[cfg(test)]
+mod todo_txt_tests {
+    use rusqlite::Connection;
+
+    use super::{export, import, parse, synced, TodoTxtImport};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_export_and_sync_todo_txt() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'my app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'To Do', 0, 1, 0), (2, 'Doing', 1, 1, 0), (3, 'Done', 2, 1, 1);
+            INSERT INTO tasks(id, name, priority, state_id, board_id, position, labels, due_at
+                , created_at, ended_at)
+                VALUES (1, 'login', 4, 2, 1, 0, '["ui","phone call"]', '2024-05-10 12:00:00'
+                    , '2024-05-01 09:00:00', NULL)
+                , (2, 'signup', 3, 3, 1, 0, '[]', NULL, '2024-05-01 09:00:00', '2024-05-03 18:00:00');"#,
+        )
+        .unwrap();
+
+        let todo = export(&conn, 1).unwrap();
+        assert_eq!(
+            "(A) 2024-05-01 login +my_app @ui @phone_call due:2024-05-10 state:Doing id:1\n\
+            x 2024-05-03 2024-05-01 signup +my_app pri:B state:Done id:2\n",
+            todo
+        );
+        let (items, errors) = parse(&todo);
+        assert!(errors.is_empty());
+        let request = TodoTxtImport {
+            board_id: 1,
+            ..TodoTxtImport::default()
+        };
+        let (report, created) = import(&conn, &items, &request).unwrap();
+        assert_eq!(
+            (0, 0, 2),
+            (report.created, report.updated, report.unchanged)
+        );
+        assert!(created.is_empty());
+
+        let (items, errors) = parse(
+            "(B) login page +my_app @ui due:2024-05-10 state:Doing id:1\n\
+            \n\
+            signup +my_app id:2\n\
+            (C) 2024-06-01 call bank +home @phone\n\
+            x 2024-06-02 pay rent +my_app\n\
+            broken due:tomorrow\n",
+        );
+        assert_eq!(
+            vec![6],
+            errors.iter().map(|error| error.line()).collect::<Vec<_>>()
+        );
+        assert_eq!(2, synced(&conn, &items, 1).unwrap().len());
+        let request = TodoTxtImport {
+            project: Some(String::from("my_app")),
+            ..request
+        };
+        let (report, created) = import(&conn, &items, &request).unwrap();
+        assert_eq!((1, 2, 1), (report.created, report.updated, report.ignored));
+        assert_eq!(1, created.len());
+
+        let tasks: String = conn
+            .query_row(
+                r#"SELECT group_concat(t.name || ' ' || s.name || ' ' || COALESCE(t.priority, '-')
+                    || ' ' || t.labels || ' ' || COALESCE(t.ended_at, '-'), ', ')
+                FROM tasks t JOIN states s ON s.id = t.state_id ORDER BY t.id;"#,
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            r#"login page Doing 3 ["ui"] -, signup To Do - [] -, pay rent Done - [] 2024-06-02 00:00:00"#,
+            tasks
+        );
+    }
+
+    #[test]
+    fn test_names_looking_like_markers_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'To Do', 0, 1, 0), (2, 'Done', 1, 1, 1);
+            INSERT INTO tasks(id, name, state_id, board_id, position, created_at)
+                VALUES (1, 'x marks the spot', 1, 1, 0, NULL)
+                , (2, '(A) plan', 1, 1, 1, NULL)
+                , (3, '2024-05-01 review', 2, 1, 0, NULL)
+                , (4, '+1 for @home due:x id:9 a+b C:\dir', 1, 1, 2, '2024-05-01 09:00:00')
+                , (5, '\escaped', 1, 1, 3, '2024-05-01 09:00:00');
+            UPDATE tasks SET created_at = NULL, ended_at = NULL WHERE id < 4;"#,
+        )
+        .unwrap();
+        let names = |conn: &Connection| -> Vec<String> {
+            let mut stmt = conn.prepare("SELECT name FROM tasks ORDER BY id;").unwrap();
+            let names = stmt
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<Vec<String>>>()
+                .unwrap();
+            names
+        };
+        let before = names(&conn);
+
+        let todo = export(&conn, 1).unwrap();
+        let (items, errors) = parse(&todo);
+        assert!(errors.is_empty());
+        assert!(items.iter().all(|item| item.projects == ["app"]
+            && item.contexts.is_empty()
+            && item.due.is_none()));
+        let request = TodoTxtImport {
+            board_id: 1,
+            ..TodoTxtImport::default()
+        };
+        let (report, _) = import(&conn, &items, &request).unwrap();
+        assert_eq!(
+            (0, 0, 5),
+            (report.created, report.updated, report.unchanged)
+        );
+        assert_eq!(before, names(&conn));
+    }
+
+    #[test]
+    fn test_unreadable_dates_are_left_out() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'To Do', 0, 1, 0), (2, 'Done', 1, 1, 1);
+            INSERT INTO tasks(id, name, state_id, board_id, position, due_at, created_at, ended_at)
+                VALUES (1, 'plan', 1, 1, 0, 'aéééééé', 'é', NULL)
+                , (2, 'review', 2, 1, 0, 'next week', '2024-05-01 09:00:00', 'soonéééééé');"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "plan +app state:To_Do id:1\n\
+            x review +app state:Done id:2\n",
+            export(&conn, 1).unwrap()
+        );
+    }
+}
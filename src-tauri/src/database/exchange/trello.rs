@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::exchange::workspace::{ImportError, Problem};
+
+/// Board of a Trello JSON export, only the parts that are imported.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloBoard {
+    name: String,
+    #[serde(default)]
+    lists: Vec<TrelloList>,
+    #[serde(default)]
+    cards: Vec<TrelloCard>,
+    #[serde(default)]
+    checklists: Vec<TrelloChecklist>,
+    #[serde(default)]
+    actions: Vec<serde_json::Value>,
+    #[serde(default)]
+    members: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    pos: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloCard {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    closed: bool,
+    id_list: String,
+    #[serde(default)]
+    pos: f64,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    start: Option<String>,
+    #[serde(default)]
+    date_last_activity: Option<String>,
+    #[serde(default)]
+    labels: Vec<TrelloLabel>,
+    #[serde(default)]
+    attachments: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrelloLabel {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloChecklist {
+    name: String,
+    id_card: String,
+    #[serde(default)]
+    pos: f64,
+    #[serde(default)]
+    check_items: Vec<TrelloCheckItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrelloCheckItem {
+    name: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    pos: f64,
+}
+
+/// What a Trello import created, and what it left out.
+#[derive(Debug, Serialize)]
+pub struct TrelloSummary {
+    board_id: i64,
+    states: usize,
+    tasks: usize,
+    archived_tasks: usize,
+    checklist_items: usize,
+    skipped: Vec<String>,
+}
+
+impl TrelloSummary {
+    /// Get id of the board created.
+    pub fn board_id(&self) -> i64 {
+        self.board_id
+    }
+}
+
+/// Parse a Trello board JSON export.
+pub fn parse(contents: &str) -> std::result::Result<TrelloBoard, ImportError> {
+    serde_json::from_str(contents).map_err(|error| ImportError::Invalid {
+        problems: vec![Problem::new("file", None, error.to_string())],
+    })
+}
+
+/// Create `board` as a new board at the end of group `group_id`: lists
+/// become states and cards become tasks, both kept in Trello order.
+/// Closed lists and cards are archived. Card labels, due and start dates,
+/// and checklists are kept, checklists being merged when a card has
+/// more than one. Comments, members and attachments are skipped.
+pub fn import(conn: &Connection, board: &TrelloBoard, group_id: i64) -> Result<TrelloSummary> {
+    let position: Option<u32> = conn
+        .query_row(
+            "SELECT MAX(position) FROM boards WHERE group_id = ?1;",
+            params![group_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    conn.execute(
+        "INSERT INTO boards(name, position, group_id) VALUES (?1, ?2, ?3);",
+        params![
+            board.name,
+            position.map_or(0, |position| position + 1),
+            group_id
+        ],
+    )?;
+    let board_id = conn.last_insert_rowid();
+    let mut summary = TrelloSummary {
+        board_id,
+        states: 0,
+        tasks: 0,
+        archived_tasks: 0,
+        checklist_items: 0,
+        skipped: vec![],
+    };
+
+    let mut lists: Vec<&TrelloList> = board.lists.iter().collect();
+    lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    let mut states: HashMap<&str, i64> = HashMap::new();
+    for (position, list) in lists.iter().enumerate() {
+        conn.execute(
+            r#"INSERT INTO states(name, position, board_id, archived_at)
+            VALUES (?1, ?2, ?3, CASE WHEN ?4 THEN datetime('now') END);"#,
+            params![list.name, position, board_id, list.closed],
+        )?;
+        states.insert(list.id.as_str(), conn.last_insert_rowid());
+        summary.states += 1;
+    }
+
+    let mut cards: Vec<&TrelloCard> = board.cards.iter().collect();
+    cards.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    let mut positions: HashMap<i64, u32> = HashMap::new();
+    let mut tasks: HashMap<&str, i64> = HashMap::new();
+    let mut attachments = 0;
+    for card in cards {
+        let Some(&state_id) = states.get(card.id_list.as_str()) else {
+            summary
+                .skipped
+                .push(format!("card \"{}\": list not found", card.name));
+            continue;
+        };
+        let position = positions.entry(state_id).or_insert(0);
+        let labels: Vec<&str> = card
+            .labels
+            .iter()
+            .filter_map(|label| match label.name.trim() {
+                "" => label.color.as_deref(),
+                name => Some(name),
+            })
+            .collect();
+
+        conn.execute(
+            r#"INSERT INTO tasks(
+                name, description, state_id, board_id, position, due_at, started_at
+                , labels, archived_at
+            ) VALUES (?1, NULLIF(?2, ''), ?3, ?4, ?5, datetime(?6), datetime(?7), ?8
+                , CASE WHEN ?9 THEN COALESCE(datetime(?10), datetime('now')) END);"#,
+            params![
+                card.name,
+                card.desc,
+                state_id,
+                board_id,
+                *position,
+                card.due,
+                card.start,
+                serde_json::to_string(&labels).unwrap_or_else(|_| String::from("[]")),
+                card.closed,
+                card.date_last_activity,
+            ],
+        )?;
+        tasks.insert(card.id.as_str(), conn.last_insert_rowid());
+        *position += 1;
+        summary.tasks += 1;
+        if card.closed {
+            summary.archived_tasks += 1;
+        }
+        attachments += card.attachments.len();
+    }
+
+    let mut checklists: Vec<&TrelloChecklist> = board.checklists.iter().collect();
+    checklists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    let mut per_card: HashMap<&str, usize> = HashMap::new();
+    for checklist in &checklists {
+        *per_card.entry(checklist.id_card.as_str()).or_insert(0) += 1;
+    }
+    let mut item_positions: HashMap<i64, u32> = HashMap::new();
+    for checklist in checklists {
+        let Some(&task_id) = tasks.get(checklist.id_card.as_str()) else {
+            summary
+                .skipped
+                .push(format!("checklist \"{}\": card not found", checklist.name));
+            continue;
+        };
+        let mut items: Vec<&TrelloCheckItem> = checklist.check_items.iter().collect();
+        items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+        for item in items {
+            let name = match per_card[checklist.id_card.as_str()] > 1 {
+                true => format!("{}: {}", checklist.name, item.name),
+                false => item.name.clone(),
+            };
+            let position = item_positions.entry(task_id).or_insert(0);
+            conn.execute(
+                r#"INSERT INTO checklist_items(name, done, position, task_id)
+                VALUES (?1, ?2, ?3, ?4);"#,
+                params![name, item.state == "complete", *position, task_id],
+            )?;
+            *position += 1;
+            summary.checklist_items += 1;
+        }
+    }
+
+    let comments = board
+        .actions
+        .iter()
+        .filter(|action| action.get("type").and_then(|kind| kind.as_str()) == Some("commentCard"))
+        .count();
+    for (count, what) in [
+        (comments, "comments"),
+        (board.members.len(), "members"),
+        (attachments, "attachments"),
+    ] {
+        if count > 0 {
+            summary.skipped.push(format!("{} {}", count, what));
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod trello_tests {
+    use rusqlite::Connection;
+
+    use super::{import, parse};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_import_trello_board() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);"#)
+            .unwrap();
+        let board = parse(
+            r#"{
+                "id": "b1", "name": "Roadmap",
+                "lists": [
+                    {"id": "l2", "name": "Done", "closed": false, "pos": 32768},
+                    {"id": "l1", "name": "To Do", "closed": false, "pos": 16384}
+                ],
+                "cards": [
+                    {"id": "c2", "name": "signup", "desc": "", "closed": false, "idList": "l1", "pos": 2,
+                        "labels": [{"name": "", "color": "red"}], "due": null},
+                    {"id": "c1", "name": "login", "desc": "oauth", "closed": false, "idList": "l1", "pos": 1,
+                        "labels": [{"name": "ui", "color": "green"}], "due": "2024-05-01T12:00:00.000Z"},
+                    {"id": "c3", "name": "old", "closed": true, "idList": "l2", "pos": 1,
+                        "dateLastActivity": "2024-01-02T10:00:00.000Z", "attachments": [{}]},
+                    {"id": "c4", "name": "lost", "idList": "l9", "pos": 1}
+                ],
+                "checklists": [
+                    {"id": "k1", "name": "Steps", "idCard": "c1", "pos": 1,
+                        "checkItems": [{"name": "form", "state": "complete", "pos": 2},
+                            {"name": "api", "state": "incomplete", "pos": 1}]}
+                ],
+                "actions": [{"type": "commentCard"}, {"type": "updateCard"}],
+                "members": [{"id": "m1"}]
+            }"#,
+        )
+        .unwrap();
+
+        let summary = import(&conn, &board, 1).unwrap();
+        assert_eq!(
+            (2, 3, 1, 2),
+            (
+                summary.states,
+                summary.tasks,
+                summary.archived_tasks,
+                summary.checklist_items
+            )
+        );
+        assert_eq!(
+            vec![
+                "card \"lost\": list not found",
+                "1 comments",
+                "1 members",
+                "1 attachments"
+            ],
+            summary.skipped
+        );
+
+        let mut stmt = conn
+            .prepare(
+                r#"SELECT t.name || ' ' || s.name || ' ' || t.position || ' ' || t.labels
+                    || ' ' || COALESCE(t.due_at, '-') || ' ' || COALESCE(t.archived_at, '-')
+                FROM tasks t JOIN states s ON s.id = t.state_id ORDER BY s.position, t.position;"#,
+            )
+            .unwrap();
+        let tasks: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                r#"login To Do 0 ["ui"] 2024-05-01 12:00:00 -"#,
+                r#"signup To Do 1 ["red"] - -"#,
+                r#"old Done 0 [] - 2024-01-02 10:00:00"#,
+            ],
+            tasks
+        );
+        let items: String = conn
+            .query_row(
+                "SELECT group_concat(name || ':' || done, ',') FROM (SELECT * FROM checklist_items ORDER BY position);",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!("api:0,form:1", items);
+        assert!(parse("[]").is_err());
+    }
+}
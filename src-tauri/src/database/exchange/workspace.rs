@@ -0,0 +1,804 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::{params, params_from_iter, Connection, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::filter::Scope;
+use crate::database::history::{self, RowSnapshot, Target};
+use crate::database::models::EntityKind;
+
+/// Name written in [`Workspace::format`].
+pub const FORMAT: &str = "my-board-workspace";
+
+/// Current version of the workspace file. Rows hold raw table columns,
+/// so it goes up whenever a migration renames a column of an exported
+/// table or adds one that must have a value. Files of other versions
+/// are refused, there's no upgrade of older files yet.
+pub const VERSION: u32 = 1;
+
+/// Sections of the file, in import order, with the columns of each row
+/// that reference rows of other sections.
+const SECTIONS: &[(EntityKind, &[(&str, EntityKind)])] = &[
+    (EntityKind::Group, &[]),
+    (EntityKind::Board, &[("group_id", EntityKind::Group)]),
+    (EntityKind::Priority, &[("board_id", EntityKind::Board)]),
+    (EntityKind::State, &[("board_id", EntityKind::Board)]),
+    (EntityKind::Milestone, &[("board_id", EntityKind::Board)]),
+    (
+        EntityKind::Task,
+        &[
+            ("board_id", EntityKind::Board),
+            ("state_id", EntityKind::State),
+            ("priority", EntityKind::Priority),
+            ("milestone_id", EntityKind::Milestone),
+            ("parent_id", EntityKind::Task),
+        ],
+    ),
+    (EntityKind::ChecklistItem, &[("task_id", EntityKind::Task)]),
+    (EntityKind::TimeLog, &[("task_id", EntityKind::Task)]),
+    (EntityKind::View, &[]),
+];
+
+/// A whole workspace as a JSON file.
+///
+/// Every section holds the rows of the table with the same name, one
+/// object per row with all its columns, `id` included. Ids are only used
+/// to link rows inside the file and are replaced by new ones on import:
+///
+/// - `boards.group_id` references `groups`.
+/// - `priorities.board_id`, `states.board_id` and `milestones.board_id`
+///   reference `boards`, priorities without board are workspace levels.
+/// - `tasks.board_id`, `tasks.state_id`, `tasks.priority`,
+///   `tasks.milestone_id` and `tasks.parent_id` reference `boards`,
+///   `states`, `priorities`, `milestones` and `tasks`.
+/// - `checklist_items.task_id` and `time_logs.task_id` reference `tasks`.
+/// - `saved_views.scope` references a group or a board by its `id`.
+///
+/// Undo history and audit log are not exported.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Workspace {
+    /// always [`FORMAT`].
+    pub format: String,
+    /// [`VERSION`] of the file format.
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub boards: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub priorities: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub states: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub milestones: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub tasks: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub checklist_items: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub time_logs: Vec<RowSnapshot>,
+    #[serde(default)]
+    pub saved_views: Vec<RowSnapshot>,
+}
+
+impl Workspace {
+    /// Rows of the section storing `kind`.
+    fn rows(&self, kind: EntityKind) -> &Vec<RowSnapshot> {
+        match kind {
+            EntityKind::Group => &self.groups,
+            EntityKind::Board => &self.boards,
+            EntityKind::Priority => &self.priorities,
+            EntityKind::State => &self.states,
+            EntityKind::Milestone => &self.milestones,
+            EntityKind::Task => &self.tasks,
+            EntityKind::ChecklistItem => &self.checklist_items,
+            EntityKind::TimeLog => &self.time_logs,
+            EntityKind::View => &self.saved_views,
+        }
+    }
+
+    fn rows_mut(&mut self, kind: EntityKind) -> &mut Vec<RowSnapshot> {
+        match kind {
+            EntityKind::Group => &mut self.groups,
+            EntityKind::Board => &mut self.boards,
+            EntityKind::Priority => &mut self.priorities,
+            EntityKind::State => &mut self.states,
+            EntityKind::Milestone => &mut self.milestones,
+            EntityKind::Task => &mut self.tasks,
+            EntityKind::ChecklistItem => &mut self.checklist_items,
+            EntityKind::TimeLog => &mut self.time_logs,
+            EntityKind::View => &mut self.saved_views,
+        }
+    }
+}
+
+/// What happens to current data when importing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportMode {
+    /// imported groups and views are added after the current ones.
+    #[default]
+    Merge,
+    /// current data is removed once the file is written. Undo history
+    /// and audit log are kept, and the import itself can be undone.
+    /// Workspace priority levels are only replaced by a file that has
+    /// some, boards use them when they have no levels of their own.
+    Replace,
+}
+
+/// Something wrong in a workspace file.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Problem {
+    section: String,
+    /// index of the row in its section.
+    row: Option<usize>,
+    message: String,
+}
+
+impl Problem {
+    pub fn new(section: &str, row: Option<usize>, message: String) -> Problem {
+        Problem {
+            section: String::from(section),
+            row,
+            message,
+        }
+    }
+}
+
+/// Errors returned to views by workspace import.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum ImportError {
+    /// file was refused, nothing was written.
+    Invalid {
+        problems: Vec<Problem>,
+    },
+    Database {
+        message: String,
+    },
+}
+
+impl From<rusqlite::Error> for ImportError {
+    fn from(_: rusqlite::Error) -> Self {
+        ImportError::Database {
+            message: String::from("Transaction error!"),
+        }
+    }
+}
+
+/// Rows imported by section.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    imported: BTreeMap<&'static str, usize>,
+}
+
+/// Export every row of the workspace.
+pub fn export(conn: &Connection) -> Result<Workspace> {
+    let mut workspace = Workspace {
+        format: String::from(FORMAT),
+        version: VERSION,
+        exported_at: Some(conn.query_row("SELECT datetime('now');", [], |row| row.get(0))?),
+        groups: vec![],
+        boards: vec![],
+        priorities: vec![],
+        states: vec![],
+        milestones: vec![],
+        tasks: vec![],
+        checklist_items: vec![],
+        time_logs: vec![],
+        saved_views: vec![],
+    };
+    for (kind, _) in SECTIONS {
+        *workspace.rows_mut(*kind) = history::snapshot_all(conn, *kind)?;
+    }
+
+    Ok(workspace)
+}
+
+/// Parse a workspace file, refusing it when it is not valid JSON.
+pub fn parse(contents: &str) -> std::result::Result<Workspace, ImportError> {
+    serde_json::from_str(contents).map_err(|error| ImportError::Invalid {
+        problems: vec![Problem::new("file", None, error.to_string())],
+    })
+}
+
+/// Check `workspace` against the current schema: format and version,
+/// unique ids, known columns, required values and references between
+/// rows. Nothing is written.
+pub fn validate(conn: &Connection, workspace: &Workspace) -> Result<Vec<Problem>> {
+    let mut problems = vec![];
+    if workspace.format != FORMAT {
+        problems.push(Problem::new(
+            "format",
+            None,
+            format!("expected \"{}\"", FORMAT),
+        ));
+    }
+    if workspace.version != VERSION {
+        problems.push(Problem::new(
+            "version",
+            None,
+            format!("unsupported version {}", workspace.version),
+        ));
+    }
+    if !problems.is_empty() {
+        return Ok(problems);
+    }
+
+    let mut ids: HashMap<EntityKind, HashMap<i64, &RowSnapshot>> = HashMap::new();
+    for (kind, _) in SECTIONS {
+        let section = kind.table();
+        let (known, required) = columns(conn, *kind)?;
+        let section_ids = ids.entry(*kind).or_default();
+        for (index, row) in workspace.rows(*kind).iter().enumerate() {
+            match row.get("id").and_then(Value::as_i64) {
+                Some(id) if section_ids.insert(id, row).is_some() => problems.push(Problem::new(
+                    section,
+                    Some(index),
+                    format!("duplicated id {}", id),
+                )),
+                Some(_) => {}
+                None => problems.push(Problem::new(
+                    section,
+                    Some(index),
+                    String::from("missing id"),
+                )),
+            }
+            for (column, value) in row {
+                let message = match known.get(column) {
+                    None => format!("unknown column \"{}\"", column),
+                    Some(_) if value.is_array() || value.is_object() => {
+                        format!("\"{}\" must be a single value", column)
+                    }
+                    Some(affinity) => match affinity.check(value) {
+                        Some(expected) => format!("\"{}\" must be {}", column, expected),
+                        None => continue,
+                    },
+                };
+                problems.push(Problem::new(section, Some(index), message));
+            }
+            for column in &required {
+                if row.get(column).is_none_or(Value::is_null) {
+                    problems.push(Problem::new(
+                        section,
+                        Some(index),
+                        format!("missing \"{}\"", column),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (kind, references) in SECTIONS {
+        let section = kind.table();
+        for (index, row) in workspace.rows(*kind).iter().enumerate() {
+            for (column, target) in references.iter() {
+                let Some(value) = row.get(*column).filter(|value| !value.is_null()) else {
+                    continue;
+                };
+                let found = value.as_i64().and_then(|id| ids[target].get(&id));
+                if found.is_none() {
+                    problems.push(Problem::new(
+                        section,
+                        Some(index),
+                        format!("\"{}\" {} not found in {}", column, value, target.table()),
+                    ));
+                }
+            }
+            if *kind == EntityKind::View {
+                if let Err(message) =
+                    view_scope(row, |kind, id| ids[&kind].contains_key(&id).then_some(id))
+                {
+                    problems.push(Problem::new(section, Some(index), message));
+                }
+            }
+        }
+    }
+
+    // tasks must use states, priorities and milestones of their own board.
+    for (index, task) in workspace.tasks.iter().enumerate() {
+        let board = task.get("board_id");
+        for (column, target, nullable_board) in [
+            ("state_id", EntityKind::State, false),
+            ("milestone_id", EntityKind::Milestone, false),
+            ("priority", EntityKind::Priority, true),
+        ] {
+            let Some(row) = task
+                .get(column)
+                .and_then(Value::as_i64)
+                .and_then(|id| ids[&target].get(&id))
+            else {
+                continue;
+            };
+            let owner = row.get("board_id").unwrap_or(&Value::Null);
+            if Some(owner) != board && !(nullable_board && owner.is_null()) {
+                problems.push(Problem::new(
+                    "tasks",
+                    Some(index),
+                    format!("\"{}\" belongs to another board", column),
+                ));
+            }
+            // boards with levels of their own don't use workspace ones.
+            let board_scheme = board.is_some_and(|board| {
+                workspace
+                    .priorities
+                    .iter()
+                    .any(|priority| priority.get("board_id") == Some(board))
+            });
+            if nullable_board && owner.is_null() && board_scheme {
+                problems.push(Problem::new(
+                    "tasks",
+                    Some(index),
+                    format!("\"{}\" is not in the board scheme", column),
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Every row of the workspace, children first, the rows removed by an
+/// [`ImportMode::Replace`] import of `workspace`.
+pub fn current(conn: &Connection, workspace: &Workspace) -> Result<Vec<Target>> {
+    let workspace_levels = workspace
+        .priorities
+        .iter()
+        .any(|row| row.get("board_id").unwrap_or(&Value::Null).is_null());
+    let mut rows = vec![];
+    for (kind, _) in SECTIONS.iter().rev() {
+        let kept = match kind {
+            EntityKind::Priority if !workspace_levels => "WHERE board_id IS NOT NULL",
+            _ => "",
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM {} {} ORDER BY id;",
+            kind.table(),
+            kept
+        ))?;
+        for id in stmt.query_map([], |row| row.get::<_, i64>(0))? {
+            rows.push((*kind, id?));
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Write `workspace` rows with new ids, returning the rows created. The
+/// workspace must be validated before, and this should run inside a
+/// transaction since a failure may leave rows written.
+///
+/// Replaced rows are removed last, so new rows never take their ids and
+/// history and audit log entries keep pointing to the old rows.
+pub fn import(
+    conn: &Connection,
+    workspace: &Workspace,
+    mode: ImportMode,
+) -> Result<(ImportSummary, Vec<Target>)> {
+    let replaced = match mode {
+        ImportMode::Merge => vec![],
+        ImportMode::Replace => current(conn, workspace)?,
+    };
+
+    let mut created = vec![];
+    let mut new_ids: HashMap<EntityKind, HashMap<i64, i64>> = HashMap::new();
+    let mut summary = ImportSummary::default();
+    for (kind, references) in SECTIONS {
+        let mut imported = 0;
+        // top level rows are placed after the current ones.
+        let offset: i64 = match kind {
+            EntityKind::Group | EntityKind::View if mode == ImportMode::Merge => conn.query_row(
+                &format!(
+                    "SELECT COALESCE(MAX(position) + 1, 0) FROM {};",
+                    kind.table()
+                ),
+                [],
+                |row| row.get(0),
+            )?,
+            _ => 0,
+        };
+
+        for row in workspace.rows(*kind) {
+            let old_id = row.get("id").and_then(Value::as_i64).unwrap_or_default();
+            if *kind == EntityKind::Priority && mode == ImportMode::Merge {
+                if let Some(id) = same_workspace_priority(conn, row)? {
+                    new_ids.entry(*kind).or_default().insert(old_id, id);
+                    continue;
+                }
+            }
+
+            let mut values = row.clone();
+            values.remove("id");
+            for (column, target) in references.iter() {
+                let Some(id) = values.get(*column).and_then(Value::as_i64) else {
+                    continue;
+                };
+                // parents are linked once every task exists.
+                let id = match *column {
+                    "parent_id" => None,
+                    _ => new_id(&new_ids, *target, id),
+                };
+                values.insert(String::from(*column), Value::from(id));
+            }
+            if offset > 0 {
+                let position = values.get("position").and_then(Value::as_i64).unwrap_or(0);
+                values.insert(String::from("position"), Value::from(position + offset));
+            }
+            if *kind == EntityKind::Task && mode == ImportMode::Merge {
+                // copies of tasks already here get their own uuid.
+                let uuid = values.get("uuid").and_then(Value::as_str);
+                let taken: bool = conn.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM tasks WHERE uuid = ?1);",
+                    params![uuid],
+                    |row| row.get(0),
+                )?;
+                if taken {
+                    values.remove("uuid");
+                }
+            }
+            if *kind == EntityKind::View {
+                let scope = view_scope(row, |kind, id| new_id(&new_ids, kind, id))
+                    .map_err(rusqlite::Error::InvalidParameterName)?;
+                values.insert(String::from("scope"), Value::from(scope));
+            }
+
+            let id = insert(conn, *kind, &values)?;
+            new_ids.entry(*kind).or_default().insert(old_id, id);
+            created.push((*kind, id));
+            imported += 1;
+        }
+        summary.imported.insert(kind.table(), imported);
+    }
+
+    for task in &workspace.tasks {
+        let task_id = |column: &str| {
+            let id = task.get(column).and_then(Value::as_i64)?;
+            new_id(&new_ids, EntityKind::Task, id)
+        };
+        if let (Some(parent), Some(id)) = (task_id("parent_id"), task_id("id")) {
+            conn.execute(
+                "UPDATE tasks SET parent_id = ?1 WHERE id = ?2;",
+                params![parent, id],
+            )?;
+        }
+    }
+
+    for (kind, id) in replaced {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE id = ?1;", kind.table()),
+            params![id],
+        )?;
+    }
+
+    Ok((summary, created))
+}
+
+/// Id given on import to the row of `kind` with `id` in the file.
+fn new_id(
+    new_ids: &HashMap<EntityKind, HashMap<i64, i64>>,
+    kind: EntityKind,
+    id: i64,
+) -> Option<i64> {
+    new_ids.get(&kind)?.get(&id).copied()
+}
+
+/// Values a column takes, read from its declared type the way SQLite
+/// does.
+#[derive(Debug, Clone, Copy)]
+enum Affinity {
+    Numeric,
+    Text,
+    Any,
+}
+
+impl Affinity {
+    fn from_declared(declared: &str) -> Affinity {
+        let declared = declared.to_uppercase();
+        if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") {
+            Affinity::Text
+        } else if declared.is_empty() || declared.contains("BLOB") {
+            Affinity::Any
+        } else {
+            Affinity::Numeric
+        }
+    }
+
+    /// What `value` should have been, when it doesn't fit. Nulls always
+    /// fit, required columns are checked apart.
+    fn check(self, value: &Value) -> Option<&'static str> {
+        match self {
+            _ if value.is_null() => None,
+            Affinity::Numeric if !value.is_number() && !value.is_boolean() => Some("a number"),
+            Affinity::Text if !value.is_string() => Some("text"),
+            _ => None,
+        }
+    }
+}
+
+/// Known columns of the table of `kind` with their affinity, and the
+/// ones that must have a value, `id` left out.
+fn columns(
+    conn: &Connection,
+    kind: EntityKind,
+) -> Result<(HashMap<String, Affinity>, Vec<String>)> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({});", kind.table()))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>("name")?,
+                row.get::<_, String>("type")?,
+                row.get::<_, bool>("notnull")?,
+                row.get::<_, Option<String>>("dflt_value")?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let known = rows
+        .iter()
+        .map(|(name, declared, _, _)| (name.clone(), Affinity::from_declared(declared)))
+        .collect();
+    let required = rows
+        .into_iter()
+        .filter(|(name, _, not_null, default)| *not_null && default.is_none() && name != "id")
+        .map(|(name, _, _, _)| name)
+        .collect();
+
+    Ok((known, required))
+}
+
+/// Scope of a saved view row, as stored, with its group or board id
+/// replaced by `remap`. Fails when the scope cannot be read or `remap`
+/// finds no id.
+fn view_scope<F>(row: &RowSnapshot, remap: F) -> std::result::Result<String, String>
+where
+    F: Fn(EntityKind, i64) -> Option<i64>,
+{
+    let scope = match row.get("scope") {
+        None | Some(Value::Null) => Scope::default(),
+        Some(Value::String(scope)) => {
+            serde_json::from_str(scope).map_err(|error| format!("invalid \"scope\": {}", error))?
+        }
+        Some(_) => return Err(String::from("invalid \"scope\"")),
+    };
+    let scope = match scope {
+        Scope::Workspace => Some(Scope::Workspace),
+        Scope::Group(id) => remap(EntityKind::Group, id).map(Scope::Group),
+        Scope::Board(id) => remap(EntityKind::Board, id).map(Scope::Board),
+    }
+    .ok_or_else(|| String::from("\"scope\" not found"))?;
+
+    serde_json::to_string(&scope).map_err(|error| error.to_string())
+}
+
+/// Workspace priority level with the same name as `row`, used to merge
+/// files instead of repeating the levels every workspace has.
+fn same_workspace_priority(conn: &Connection, row: &RowSnapshot) -> Result<Option<i64>> {
+    if !row.get("board_id").is_none_or(Value::is_null) {
+        return Ok(None);
+    }
+    let name = row.get("name").and_then(Value::as_str).unwrap_or_default();
+    let mut stmt = conn.prepare(
+        r#"SELECT id FROM priorities
+        WHERE board_id IS NULL AND name = ?1 COLLATE NOCASE ORDER BY id LIMIT 1;"#,
+    )?;
+    let mut rows = stmt.query_map(params![name], |row| row.get(0))?;
+
+    rows.next().transpose()
+}
+
+fn insert(conn: &Connection, kind: EntityKind, values: &RowSnapshot) -> Result<i64> {
+    let columns: Vec<&str> = values.keys().map(String::as_str).collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+    conn.execute(
+        &format!(
+            "INSERT INTO {}({}) VALUES ({});",
+            kind.table(),
+            columns.join(", "),
+            placeholders.join(", ")
+        ),
+        params_from_iter(values.values().map(history::from_json)),
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+#[cfg(test)]
+mod workspace_tests {
+    use rusqlite::Connection;
+
+    use super::{current, export, import, parse, validate, ImportMode, Problem};
+    use crate::database::audit::Origin;
+    use crate::database::fixtures::create_tables;
+    use crate::database::history::{self, Action};
+    use crate::database::models::EntityKind;
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {};", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (7, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (5, 'app', 0, 7);
+            INSERT INTO priorities(id, name, rank, board_id) VALUES (9, 'Urgent', 5, 5);
+            INSERT INTO states(id, name, position, board_id) VALUES (3, 'Todo', 0, 5);
+            INSERT INTO milestones(id, name, position, board_id) VALUES (2, 'v1', 0, 5);
+            INSERT INTO tasks(id, name, priority, state_id, board_id, position, parent_id, milestone_id, created_at)
+                VALUES (11, 'child', 9, 3, 5, 1, 12, 2, '2024-01-01 10:00:00')
+                    , (12, 'parent', 9, 3, 5, 0, NULL, NULL, '2024-01-01 10:00:00');
+            INSERT INTO checklist_items(name, position, task_id) VALUES ('check', 0, 11);
+            INSERT INTO time_logs(minutes, task_id) VALUES (30, 12);
+            INSERT INTO saved_views(name, scope) VALUES ('app', '{"type":"Board","id":5}');"#,
+        )
+        .unwrap();
+        let contents = serde_json::to_string(&export(&conn).unwrap()).unwrap();
+
+        let other = Connection::open_in_memory().unwrap();
+        create_tables(&other).unwrap();
+        let workspace = parse(&contents).unwrap();
+        assert_eq!(Vec::<Problem>::new(), validate(&other, &workspace).unwrap());
+        let (summary, _) = import(&other, &workspace, ImportMode::Replace).unwrap();
+        assert_eq!(Some(&2), summary.imported.get("tasks"));
+        assert_eq!(5, count(&other, "priorities"));
+        let (parent, milestone, created_at): (String, String, String) = other
+            .query_row(
+                r#"SELECT p.name, m.name, t.created_at FROM tasks t
+                JOIN tasks p ON p.id = t.parent_id JOIN milestones m ON m.id = t.milestone_id
+                WHERE t.name = 'child';"#,
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            ("parent", "v1", "2024-01-01 10:00:00"),
+            (parent.as_str(), milestone.as_str(), created_at.as_str())
+        );
+        let same_scope: bool = other
+            .query_row(
+                "SELECT v.scope = json_object('type', 'Board', 'id', b.id) FROM saved_views v, boards b;",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(same_scope);
+
+        // merging keeps current data and reuses workspace priority levels.
+        import(&other, &workspace, ImportMode::Merge).unwrap();
+        assert_eq!(4, count(&other, "tasks"));
+        assert_eq!(6, count(&other, "priorities"));
+        assert_eq!(
+            1,
+            other
+                .query_row("SELECT MAX(position) FROM groups;", [], |row| row
+                    .get::<_, i64>(0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_replace_keeps_history_and_undoes() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let create_group = |name: &str| {
+            history::record(
+                &conn,
+                Origin::Gui,
+                Action::Create,
+                (EntityKind::Group, None),
+                &[],
+                |conn| {
+                    conn.execute("INSERT INTO groups(name, position) VALUES (?1, 0);", [name])?;
+                    Ok(((), vec![(EntityKind::Group, conn.last_insert_rowid())]))
+                },
+            )
+            .unwrap();
+        };
+        create_group("work");
+        let workspace = export(&conn).unwrap();
+        conn.execute("UPDATE groups SET name = 'home';", [])
+            .unwrap();
+
+        let touched = current(&conn, &workspace).unwrap();
+        history::record(
+            &conn,
+            Origin::Gui,
+            Action::Update,
+            (EntityKind::Group, None),
+            &touched,
+            |conn| import(conn, &workspace, ImportMode::Replace),
+        )
+        .unwrap();
+        let group: (i64, String) = conn
+            .query_row("SELECT id, name FROM groups;", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((2, String::from("work")), group);
+        assert_eq!(2, count(&conn, "history"));
+        let creation_logged: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM audit_log WHERE action = 'Create' AND entity_id = 1);",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(creation_logged);
+
+        history::undo(&conn, Origin::Gui).unwrap().unwrap();
+        let group: (i64, String) = conn
+            .query_row("SELECT id, name FROM groups;", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((1, String::from("home")), group);
+    }
+
+    #[test]
+    fn test_import_without_tasks_or_states() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let workspace = parse(
+            r#"{
+                "format": "my-board-workspace",
+                "version": 1,
+                "groups": [{"id": 1, "name": "work", "position": 0}],
+                "saved_views": [{"id": 1, "name": "work", "scope": "{\"type\":\"Group\",\"id\":1}"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(Vec::<Problem>::new(), validate(&conn, &workspace).unwrap());
+
+        for mode in [ImportMode::Merge, ImportMode::Replace] {
+            let (summary, _) = import(&conn, &workspace, mode).unwrap();
+            assert_eq!(Some(&0), summary.imported.get("tasks"));
+        }
+        assert_eq!(1, count(&conn, "groups"));
+        assert_eq!(1, count(&conn, "saved_views"));
+        // the file defines no priority levels, the workspace ones are kept.
+        assert_eq!(4, count(&conn, "priorities"));
+    }
+
+    #[test]
+    fn test_validate_refuses_broken_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let workspace = parse(
+            r#"{
+                "format": "my-board-workspace",
+                "version": 1,
+                "groups": [{"id": 1, "name": "work", "position": 0}],
+                "boards": [{"id": 1, "name": "app", "position": 0, "group_id": 2, "color": "red"}],
+                "states": [{"id": 1, "position": 0, "board_id": 1}],
+                "tasks": [{"id": 1, "name": 7, "position": "last", "state_id": 1, "board_id": 1}]
+            }"#,
+        )
+        .unwrap();
+
+        let messages: Vec<String> = validate(&conn, &workspace)
+            .unwrap()
+            .into_iter()
+            .map(|problem| format!("{}: {}", problem.section, problem.message))
+            .collect();
+        assert_eq!(
+            vec![
+                "boards: unknown column \"color\"",
+                "states: missing \"name\"",
+                "tasks: \"name\" must be text",
+                "tasks: \"position\" must be a number",
+                "boards: \"group_id\" 2 not found in groups",
+            ],
+            messages
+        );
+        assert!(parse("{").is_err());
+        let newer = parse(r#"{"format": "my-board-workspace", "version": 2}"#).unwrap();
+        assert_eq!(1, validate(&conn, &newer).unwrap().len());
+    }
+}
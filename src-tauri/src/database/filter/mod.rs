@@ -0,0 +1,262 @@
+mod parser;
+
+pub use parser::{parse, Comparison, Condition, DueValue, ParseError, Term, UpdatedValue};
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use super::models::{Priority, TaskSummary};
+use super::pagination::{query_page, Page, PageRequest, DEFAULT_LIMIT};
+use super::search::to_fts_query;
+
+/// Part of workspace where tasks are looked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id")]
+pub enum Scope {
+    #[default]
+    Workspace,
+    Group(i64),
+    Board(i64),
+}
+
+/// A page of filtered tasks with the total of tasks matching.
+#[derive(Debug, Serialize)]
+pub struct FilterPage {
+    #[serde(flatten)]
+    pub tasks: Page<TaskSummary>,
+    pub total: i64,
+}
+
+/// Errors returned to views by filter commands.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum FilterError {
+    Syntax(ParseError),
+    Database { message: String },
+}
+
+impl From<ParseError> for FilterError {
+    fn from(error: ParseError) -> Self {
+        FilterError::Syntax(error)
+    }
+}
+
+impl From<rusqlite::Error> for FilterError {
+    fn from(_: rusqlite::Error) -> Self {
+        FilterError::Database {
+            message: String::from("Transaction error!"),
+        }
+    }
+}
+
+/// Condition on boards aliased as `b` for `scope`, with its values.
+pub fn board_condition(scope: Scope) -> (String, Vec<Value>) {
+    match scope {
+        Scope::Workspace => (String::from("1"), vec![]),
+        Scope::Group(id) => (String::from("b.group_id = ?"), vec![Value::Integer(id)]),
+        Scope::Board(id) => (String::from("b.id = ?"), vec![Value::Integer(id)]),
+    }
+}
+
+/// Build `WHERE` condition for `terms` inside `scope`, using the aliases
+/// of [`TaskSummary::SELECT`]. Archived items are always left out.
+pub fn to_sql(terms: &[Term], scope: Scope) -> (String, Vec<Value>) {
+    let (board, mut values) = board_condition(scope);
+    let mut conditions = vec![String::from(TaskSummary::NOT_ARCHIVED), board];
+
+    for term in terms {
+        let condition = match &term.condition {
+            Condition::Text(text) => {
+                let query = to_fts_query(text);
+                if query.is_empty() {
+                    continue;
+                }
+                values.push(Value::Text(query));
+                String::from("t.id IN (SELECT rowid FROM tasks_search WHERE tasks_search MATCH ?)")
+            }
+            Condition::Priority(comparison, name) => {
+                values.push(Value::Text(name.clone()));
+                format!(
+                    r#"COALESCE(p.rank, 0) {} (SELECT pp.rank FROM priorities pp
+                    WHERE pp.name = ? COLLATE NOCASE AND {})"#,
+                    comparison.operator(),
+                    Priority::scheme_condition("pp", "t.board_id")
+                )
+            }
+            Condition::State(name) => {
+                values.push(Value::Text(name.clone()));
+                String::from("s.name = ? COLLATE NOCASE")
+            }
+            Condition::Board(name) => {
+                values.push(Value::Text(name.clone()));
+                String::from("b.name = ? COLLATE NOCASE")
+            }
+            Condition::Group(name) => {
+                values.push(Value::Text(name.clone()));
+                String::from("g.name = ? COLLATE NOCASE")
+            }
+            Condition::Label(name) => {
+                values.push(Value::Text(name.clone()));
+                String::from(
+                    "EXISTS (SELECT 1 FROM json_each(t.labels) WHERE value = ? COLLATE NOCASE)",
+                )
+            }
+            Condition::Due(_, DueValue::None) => String::from("t.due_at IS NULL"),
+            Condition::Due(comparison, DueValue::Date(date)) => {
+                values.push(Value::Text(date.clone()));
+                format!("date(t.due_at) {} ?", comparison.operator())
+            }
+            Condition::Due(comparison, DueValue::Days(days)) => {
+                values.push(Value::Text(format!("{:+} days", days)));
+                format!(
+                    "date(t.due_at) {} date('now', 'localtime', ?)",
+                    comparison.operator()
+                )
+            }
+            // updates are stored in UTC, unlike due dates picked by users.
+            Condition::Updated(comparison, UpdatedValue::Date(date)) => {
+                values.push(Value::Text(date.clone()));
+                format!("date(t.updated_at) {} ?", comparison.operator())
+            }
+            Condition::Updated(comparison, UpdatedValue::Age(days)) => {
+                values.push(Value::Integer(*days));
+                format!(
+                    "CAST(julianday('now') - julianday(t.updated_at) AS INTEGER) {} ?",
+                    comparison.operator()
+                )
+            }
+        };
+
+        conditions.push(match term.negated {
+            true => format!("NOT COALESCE(({}), 0)", condition),
+            false => format!("({})", condition),
+        });
+    }
+
+    (conditions.join(" AND "), values)
+}
+
+/// Get a page of tasks matching `terms` inside `scope`, at most
+/// [`DEFAULT_LIMIT`] tasks when request has no limit.
+pub fn filter_tasks(
+    conn: &Connection,
+    terms: &[Term],
+    scope: Scope,
+    request: &PageRequest,
+) -> Result<FilterPage> {
+    let (condition, values) = to_sql(terms, scope);
+    let query = format!("{} WHERE {}", TaskSummary::SELECT, condition);
+
+    let total = conn.query_row(
+        &format!("SELECT COUNT(*) FROM ({});", query),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    )?;
+    let request = PageRequest {
+        limit: Some(request.limit.unwrap_or(DEFAULT_LIMIT)),
+        ..request.clone()
+    };
+    let tasks = query_page(conn, &query, values, &request, TaskSummary::from_row)?;
+
+    Ok(FilterPage { tasks, total })
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use rusqlite::Connection;
+
+    use super::{filter_tasks, parse, Scope};
+    use crate::database::fixtures::create_tables;
+    use crate::database::pagination::{PageRequest, SortKey};
+
+    fn names(conn: &Connection, filter: &str, scope: Scope, sort: SortKey) -> Vec<String> {
+        let request = PageRequest {
+            sort,
+            ..PageRequest::default()
+        };
+        filter_tasks(conn, &parse(filter).unwrap(), scope, &request)
+            .unwrap()
+            .tasks
+            .items
+            .into_iter()
+            .map(|task| task.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1), (2, 'site', 1, 1);
+            INSERT INTO states(id, name, position, board_id)
+                VALUES (1, 'Todo', 0, 1), (2, 'Doing', 1, 1), (3, 'Doing', 0, 2);
+            INSERT INTO tasks(name, description, duration, priority, state_id, board_id, position, due_at, labels)
+                VALUES ('login', 'login form', 30, 3, 2, 1, 0, date('now', 'localtime', '+2 days'), '["bug"]')
+                    , ('signup', NULL, 30, 3, 1, 1, 0, date('now', 'localtime', '+20 days'), '["bug", "ui"]')
+                    , ('landing', 'login link', 10, 4, 3, 2, 0, date('now', 'localtime', '-1 days'), '[]')
+                    , ('footer', NULL, 10, 1, 3, 2, 1, NULL, '["ui"]');"#,
+        )
+        .unwrap();
+        let all = Scope::Workspace;
+
+        assert_eq!(
+            vec!["login"],
+            names(
+                &conn,
+                r#"priority:high state:Doing label:bug due:<7d "login""#,
+                all,
+                SortKey::Position
+            )
+        );
+        assert_eq!(
+            vec!["landing", "login", "signup"],
+            names(&conn, "p:>=high", all, SortKey::Priority)
+        );
+        assert_eq!(
+            vec!["landing"],
+            names(&conn, "due:overdue", all, SortKey::Due)
+        );
+        assert_eq!(vec!["footer"], names(&conn, "due:none", all, SortKey::Due));
+        assert_eq!(
+            vec!["login", "landing"],
+            names(&conn, "login", all, SortKey::Position)
+        );
+        assert_eq!(
+            vec!["login"],
+            names(&conn, "login", Scope::Board(1), SortKey::Position)
+        );
+        assert_eq!(
+            vec!["landing", "login"],
+            names(&conn, "-label:ui", all, SortKey::Name)
+        );
+        conn.execute(
+            "UPDATE tasks SET updated_at = datetime('now', '-20 days') WHERE name = 'footer';",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["footer"],
+            names(&conn, "updated:>2w", all, SortKey::Name)
+        );
+        assert_eq!(
+            vec!["landing", "login", "signup"],
+            names(&conn, "updated:<1d", all, SortKey::Name)
+        );
+
+        let mut request = PageRequest {
+            sort: SortKey::Name,
+            limit: Some(3),
+            ..PageRequest::default()
+        };
+        let page = filter_tasks(&conn, &[], Scope::Group(1), &request).unwrap();
+        assert_eq!(4, page.total);
+        assert_eq!(3, page.tasks.items.len());
+        request.cursor = page.tasks.next_cursor;
+        let page = filter_tasks(&conn, &[], Scope::Group(1), &request).unwrap();
+        assert_eq!("signup", page.tasks.items[0].name);
+        assert!(page.tasks.next_cursor.is_none());
+    }
+}
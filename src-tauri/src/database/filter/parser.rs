@@ -0,0 +1,404 @@
+use serde::Serialize;
+
+/// Comparison used by ordered filters, like priority and due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn operator(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// Due date value of a `due:` filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DueValue {
+    /// task without due date.
+    None,
+    /// `YYYY-MM-DD` date.
+    Date(String),
+    /// days from today, negative values are in the past.
+    Days(i64),
+}
+
+/// Last update value of an `updated:` filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdatedValue {
+    /// `YYYY-MM-DD` date.
+    Date(String),
+    /// whole days since the last update, so `updated:>14d` finds stale tasks.
+    Age(i64),
+}
+
+/// Single condition of a filter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// word or phrase searched in task name and description.
+    Text(String),
+    /// priority name, compared by rank inside the task board scheme.
+    Priority(Comparison, String),
+    State(String),
+    Board(String),
+    Group(String),
+    Label(String),
+    Due(Comparison, DueValue),
+    Updated(Comparison, UpdatedValue),
+}
+
+/// Condition that may be negated with a leading `-`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+/// Syntax error, `position` and `length` are counted in characters.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ParseError {
+    pub position: usize,
+    pub length: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, length: usize, message: String) -> ParseError {
+        ParseError {
+            position,
+            length: length.max(1),
+            message,
+        }
+    }
+}
+
+/// Parse a filter like `priority:high state:Doing label:bug due:<7d "login"`.
+///
+/// Supported keys are `priority`, `state`, `board`, `group`, `label`, `due`
+/// and `updated`. Values with spaces must be quoted and any term can be negated
+/// with a leading `-`. Words outside keys are searched in task texts.
+pub fn parse(input: &str) -> Result<Vec<Term>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut terms = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        if chars[index].is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        let negated =
+            chars[index] == '-' && chars.get(index + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            index += 1;
+        }
+
+        let condition = if chars[index] == '"' {
+            Condition::Text(read_quoted(&chars, &mut index)?)
+        } else {
+            let start = index;
+            let word = read_until(&chars, &mut index, |c| {
+                c.is_whitespace() || c == ':' || c == '"'
+            });
+            match chars.get(index) {
+                Some(':') => {
+                    index += 1;
+                    let value_start = index;
+                    let value = match chars.get(index) {
+                        Some('"') => read_quoted(&chars, &mut index)?,
+                        _ => read_until(&chars, &mut index, char::is_whitespace),
+                    };
+                    if value.is_empty() {
+                        return Err(ParseError::new(
+                            start,
+                            index - start,
+                            format!("missing value for '{}'", word),
+                        ));
+                    }
+
+                    build(&word, start, &value, value_start, index - value_start)?
+                }
+                Some('"') => {
+                    return Err(ParseError::new(index, 1, String::from("unexpected quote")))
+                }
+                _ => Condition::Text(word),
+            }
+        };
+
+        terms.push(Term { negated, condition });
+    }
+
+    Ok(terms)
+}
+
+fn read_until<F: Fn(char) -> bool>(chars: &[char], index: &mut usize, stop: F) -> String {
+    let mut value = String::new();
+    while let Some(c) = chars.get(*index).filter(|c| !stop(**c)) {
+        value.push(*c);
+        *index += 1;
+    }
+
+    value
+}
+
+fn read_quoted(chars: &[char], index: &mut usize) -> Result<String, ParseError> {
+    let start = *index;
+    *index += 1;
+    let value = read_until(chars, index, |c| c == '"');
+    if *index >= chars.len() {
+        return Err(ParseError::new(
+            start,
+            *index - start,
+            String::from("missing closing quote"),
+        ));
+    }
+    *index += 1;
+
+    Ok(value)
+}
+
+fn build(
+    key: &str,
+    key_start: usize,
+    value: &str,
+    value_start: usize,
+    value_length: usize,
+) -> Result<Condition, ParseError> {
+    let invalid = |message: String| ParseError::new(value_start, value_length, message);
+
+    match key.to_lowercase().as_str() {
+        "priority" | "p" => {
+            let (comparison, name) = split_comparison(value);
+            if name.is_empty() {
+                return Err(invalid(String::from("missing priority name")));
+            }
+
+            Ok(Condition::Priority(comparison, name.to_string()))
+        }
+        "state" | "s" => Ok(Condition::State(value.to_string())),
+        "board" | "b" => Ok(Condition::Board(value.to_string())),
+        "group" | "g" => Ok(Condition::Group(value.to_string())),
+        "label" | "l" => Ok(Condition::Label(value.to_string())),
+        "due" => {
+            let (comparison, due) = split_comparison(value);
+            let due =
+                parse_due(due).ok_or_else(|| invalid(format!("invalid due date '{}'", due)))?;
+            if due == DueValue::None && comparison != Comparison::Equal {
+                return Err(invalid(String::from("'none' cannot be compared")));
+            }
+
+            Ok(match due.clone() {
+                // overdue means before today.
+                DueValue::Days(0) if value.eq_ignore_ascii_case("overdue") => {
+                    Condition::Due(Comparison::Less, due)
+                }
+                _ => Condition::Due(comparison, due),
+            })
+        }
+        "updated" | "u" => {
+            let (comparison, updated) = split_comparison(value);
+            let updated = match is_date(updated) {
+                true => Some(UpdatedValue::Date(updated.to_string())),
+                false => parse_days(updated)
+                    .filter(|days| *days >= 0)
+                    .map(UpdatedValue::Age),
+            };
+
+            Ok(Condition::Updated(
+                comparison,
+                updated.ok_or_else(|| invalid(format!("invalid update age '{}'", value)))?,
+            ))
+        }
+        _ => Err(ParseError::new(
+            key_start,
+            key.chars().count(),
+            format!("unknown filter '{}'", key),
+        )),
+    }
+}
+
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (comparison, rest);
+        }
+    }
+
+    (Comparison::Equal, value)
+}
+
+fn parse_due(value: &str) -> Option<DueValue> {
+    match value.to_lowercase().as_str() {
+        "none" => return Some(DueValue::None),
+        "today" | "overdue" => return Some(DueValue::Days(0)),
+        "tomorrow" => return Some(DueValue::Days(1)),
+        "yesterday" => return Some(DueValue::Days(-1)),
+        _ => {}
+    }
+
+    if is_date(value) {
+        return Some(DueValue::Date(value.to_string()));
+    }
+
+    parse_days(value).map(DueValue::Days)
+}
+
+/// reads `3d` or `2w` as days.
+fn parse_days(value: &str) -> Option<i64> {
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    match unit.to_ascii_lowercase() {
+        'd' => Some(amount),
+        'w' => amount.checked_mul(7),
+        _ => None,
+    }
+}
+
+/// checks `YYYY-MM-DD` format.
+fn is_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(&parts)
+            .all(|(size, part)| part.len() == *size && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::{parse, Comparison, Condition, DueValue, ParseError, Term, UpdatedValue};
+
+    fn term(condition: Condition) -> Term {
+        Term {
+            negated: false,
+            condition,
+        }
+    }
+
+    #[test]
+    fn test_parse_full_filter() {
+        let terms = parse(r#"priority:high state:Doing label:bug due:<7d "login""#).unwrap();
+
+        assert_eq!(
+            vec![
+                term(Condition::Priority(Comparison::Equal, String::from("high"))),
+                term(Condition::State(String::from("Doing"))),
+                term(Condition::Label(String::from("bug"))),
+                term(Condition::Due(Comparison::Less, DueValue::Days(7))),
+                term(Condition::Text(String::from("login"))),
+            ],
+            terms
+        );
+    }
+
+    #[test]
+    fn test_parse_quotes_negation_and_dates() {
+        let terms = parse(r#"-state:"In Review" p:>=normal due:2024-05-01 due:overdue"#).unwrap();
+
+        assert_eq!(
+            vec![
+                Term {
+                    negated: true,
+                    condition: Condition::State(String::from("In Review")),
+                },
+                term(Condition::Priority(
+                    Comparison::GreaterOrEqual,
+                    String::from("normal")
+                )),
+                term(Condition::Due(
+                    Comparison::Equal,
+                    DueValue::Date(String::from("2024-05-01"))
+                )),
+                term(Condition::Due(Comparison::Less, DueValue::Days(0))),
+            ],
+            terms
+        );
+        assert_eq!(
+            vec![term(Condition::Text(String::from("-")))],
+            parse(" - ").unwrap()
+        );
+        assert_eq!(
+            vec![
+                term(Condition::Updated(
+                    Comparison::Greater,
+                    UpdatedValue::Age(14)
+                )),
+                term(Condition::Updated(
+                    Comparison::Less,
+                    UpdatedValue::Date(String::from("2024-05-01"))
+                )),
+            ],
+            parse("updated:>2w u:<2024-05-01").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_positions() {
+        assert_eq!(
+            Err(ParseError {
+                position: 9,
+                length: 2,
+                message: String::from("missing priority name"),
+            }),
+            parse("priority:>=")
+        );
+        assert_eq!(
+            Err(ParseError {
+                position: 4,
+                length: 6,
+                message: String::from("unknown filter 'colour'"),
+            }),
+            parse("bug colour:red")
+        );
+        assert_eq!(
+            Err(ParseError {
+                position: 6,
+                length: 4,
+                message: String::from("missing closing quote"),
+            }),
+            parse("state:\"In ")
+        );
+        assert_eq!(
+            Err(ParseError {
+                position: 0,
+                length: 6,
+                message: String::from("missing value for 'label'"),
+            }),
+            parse("label: bug")
+        );
+        assert_eq!(
+            Err(ParseError {
+                position: 4,
+                length: 5,
+                message: String::from("invalid due date 'soon'"),
+            }),
+            parse("due:<soon")
+        );
+        assert_eq!(
+            Err(ParseError {
+                position: 8,
+                length: 4,
+                message: String::from("invalid update age '<-3d'"),
+            }),
+            parse("updated:<-3d")
+        );
+    }
+}
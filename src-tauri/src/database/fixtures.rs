@@ -1,8 +1,8 @@
 use rusqlite::{Connection, Result};
 
-pub fn create_tables() -> Result<()> {
-    let connection = Connection::open("./.my-board-db.db")?;
+use super::migrations::migrate;
 
+pub fn create_tables(connection: &Connection) -> Result<()> {
     let create_states_table_query = r#"CREATE TABLE IF NOT EXISTS states(
             id INTEGER PRIMARY KEY
             , name VARCHAR(255) NOT NULL
@@ -27,7 +27,7 @@ pub fn create_tables() -> Result<()> {
         , position INTEGER NOT NULL
     )"#;
     connection.execute(&create_groups_table_query, ())?;
-    let create_tasks_table_query = r#"CREATE TABLE IF NOT EXISTS board(
+    let create_tasks_table_query = r#"CREATE TABLE IF NOT EXISTS tasks(
         id INTEGER PRIMARY KEY
         , name VARCHAR(255) NOT NULL
         , description LONGTEXT DEFAULT NULL
@@ -41,6 +41,7 @@ pub fn create_tables() -> Result<()> {
         , ended_at TEXT DEFAULT NULL
     )"#;
     connection.execute(&create_tasks_table_query, ())?;
+    migrate(connection)?;
 
     println!("created all tables!");
     return Ok(());
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use super::audit::{self, Origin};
+use super::models::EntityKind;
+
+/// Max number of operations kept in history.
+pub const MAX_HISTORY: i64 = 100;
+
+/// Database row stored as column name -> value.
+pub type RowSnapshot = Map<String, Value>;
+
+/// An entity row, identified by its kind and id.
+pub type Target = (EntityKind, i64);
+
+/// Mutating actions recorded in history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+    Move,
+    Duplicate,
+    Archive,
+    Restore,
+    Purge,
+    /// changes made reverting an operation, only seen in audit log.
+    Undo,
+    /// changes made applying again an operation, only seen in audit log.
+    Redo,
+}
+
+/// Row state before and after an operation. Missing snapshot means
+/// the row did not exist at that moment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Change {
+    kind: EntityKind,
+    id: i64,
+    before: Option<RowSnapshot>,
+    after: Option<RowSnapshot>,
+}
+
+/// Reversible operation stored in history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Operation {
+    action: Action,
+    kind: EntityKind,
+    entity_id: Option<i64>,
+    changes: Vec<Change>,
+}
+
+/// Resume of an operation undone or redone, returned to views.
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    id: i64,
+    action: Action,
+    kind: EntityKind,
+    entity_id: Option<i64>,
+    created_at: String,
+}
+
+/// Run `action` over `target`, storing in history the changes made to
+/// `touched` rows plus the rows that `action` reports as created.
+/// Each change is also written to the audit log, tagged with `origin`.
+pub fn record<T, F>(
+    conn: &Connection,
+    origin: Origin,
+    action: Action,
+    target: (EntityKind, Option<i64>),
+    touched: &[Target],
+    run: F,
+) -> Result<T>
+where
+    F: FnOnce(&Connection) -> Result<(T, Vec<Target>)>,
+{
+    let mut before = HashMap::new();
+    for row in touched {
+        before.insert(*row, snapshot(conn, *row)?);
+    }
+
+    let (result, created) = run(conn)?;
+
+    // created rows go first, so undo removes them once the rows
+    // referencing them are restored, and redo adds them back before.
+    let mut changes = vec![];
+    for row in created.iter().chain(touched.iter()) {
+        if changes
+            .iter()
+            .any(|change: &Change| (change.kind, change.id) == *row)
+        {
+            continue;
+        }
+        let previous = before.remove(row).flatten();
+        let current = snapshot(conn, *row)?;
+        if previous != current {
+            audit::log(
+                conn,
+                origin,
+                action,
+                *row,
+                previous.as_ref(),
+                current.as_ref(),
+            )?;
+            changes.push(Change {
+                kind: row.0,
+                id: row.1,
+                before: previous,
+                after: current,
+            });
+        }
+    }
+
+    if !changes.is_empty() {
+        let entity_id = target.1.or(created.first().map(|row| row.1));
+        push(
+            conn,
+            &Operation {
+                action,
+                kind: target.0,
+                entity_id,
+                changes,
+            },
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// Revert last operation not undone yet.
+pub fn undo(conn: &Connection, origin: Origin) -> Result<Option<HistoryEntry>> {
+    let Some((entry, operation)) = find(
+        conn,
+        r#"SELECT id, operation, created_at FROM history
+        WHERE undone = 0 ORDER BY id DESC LIMIT 1;"#,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    replay(conn, entry.id, || {
+        for change in operation.changes.iter().rev() {
+            let target = (change.kind, change.id);
+            write(conn, target, change.before.as_ref())?;
+            audit::log(
+                conn,
+                origin,
+                Action::Undo,
+                target,
+                change.after.as_ref(),
+                change.before.as_ref(),
+            )?;
+        }
+
+        Ok(())
+    })?;
+    conn.execute(
+        "UPDATE history SET undone = 1 WHERE id = ?1;",
+        params![entry.id],
+    )?;
+
+    Ok(Some(entry))
+}
+
+/// Run `writes` of history entry `entry_id` as a replay. Rows of an
+/// operation are written one by one but only valid all together, like
+/// a board scheme and the tasks moved to it, so row checks spanning
+/// several tables are skipped meanwhile.
+fn replay<F>(conn: &Connection, entry_id: i64, writes: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()>,
+{
+    conn.execute(
+        "INSERT INTO history_replay(history_id) VALUES (?1);",
+        params![entry_id],
+    )?;
+    let result = writes();
+    conn.execute(
+        "DELETE FROM history_replay WHERE history_id = ?1;",
+        params![entry_id],
+    )?;
+
+    result
+}
+
+/// Apply again the last undone operation.
+pub fn redo(conn: &Connection, origin: Origin) -> Result<Option<HistoryEntry>> {
+    let Some((entry, operation)) = find(
+        conn,
+        r#"SELECT id, operation, created_at FROM history
+        WHERE undone = 1 ORDER BY id ASC LIMIT 1;"#,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    replay(conn, entry.id, || {
+        for change in operation.changes.iter() {
+            let target = (change.kind, change.id);
+            write(conn, target, change.after.as_ref())?;
+            audit::log(
+                conn,
+                origin,
+                Action::Redo,
+                target,
+                change.before.as_ref(),
+                change.after.as_ref(),
+            )?;
+        }
+
+        Ok(())
+    })?;
+    conn.execute(
+        "UPDATE history SET undone = 0 WHERE id = ?1;",
+        params![entry.id],
+    )?;
+
+    Ok(Some(entry))
+}
+
+/// List rows of an entity and everything inside it.
+pub fn entity_tree(conn: &Connection, target: Target) -> Result<Vec<Target>> {
+    let (kind, id) = target;
+    let children: &[(EntityKind, &str)] = match kind {
+        EntityKind::Group => &[(EntityKind::Board, "group_id")],
+        EntityKind::Board => &[
+            (EntityKind::State, "board_id"),
+            (EntityKind::Task, "board_id"),
+            (EntityKind::Priority, "board_id"),
+            (EntityKind::Milestone, "board_id"),
+        ],
+        EntityKind::State => &[(EntityKind::Task, "state_id")],
+        EntityKind::Task => &[
+            (EntityKind::ChecklistItem, "task_id"),
+            (EntityKind::TimeLog, "task_id"),
+            (EntityKind::Task, "parent_id"),
+        ],
+        EntityKind::View
+        | EntityKind::Priority
+        | EntityKind::ChecklistItem
+        | EntityKind::TimeLog
+        | EntityKind::Milestone => &[],
+    };
+    let mut rows = vec![target];
+
+    for (child, column) in children {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM {} WHERE {} = ?1;",
+            child.table(),
+            column
+        ))?;
+        let ids = stmt
+            .query_map(params![id], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<i64>>>()?;
+        for child_id in ids {
+            // subtasks are listed without their own children, which
+            // are reached from their state instead.
+            let tree = match *child == kind {
+                true => vec![(*child, child_id)],
+                false => entity_tree(conn, (*child, child_id))?,
+            };
+            for row in tree {
+                if !rows.contains(&row) {
+                    rows.push(row);
+                }
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// List an entity row with its siblings, the rows affected by position changes.
+pub fn siblings(conn: &Connection, target: Target) -> Result<Vec<Target>> {
+    let (kind, id) = target;
+    let query = match kind.parent_column() {
+        Some(parent) => format!(
+            "SELECT id FROM {0} WHERE {1} = (SELECT {1} FROM {0} WHERE id = ?1);",
+            kind.table(),
+            parent
+        ),
+        None => format!("SELECT id FROM {} WHERE ?1 IS NOT NULL;", kind.table()),
+    };
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = vec![target];
+    for sibling in stmt.query_map(params![id], |row| row.get::<_, i64>(0))? {
+        let sibling = sibling?;
+        if sibling != id {
+            rows.push((kind, sibling));
+        }
+    }
+
+    Ok(rows)
+}
+
+fn push(conn: &Connection, operation: &Operation) -> Result<()> {
+    let operation = serde_json::to_string(operation)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+
+    // a new operation invalidates the redo stack.
+    conn.execute("DELETE FROM history WHERE undone = 1;", [])?;
+    conn.execute(
+        "INSERT INTO history(operation) VALUES (?1);",
+        params![operation],
+    )?;
+    conn.execute(
+        r#"DELETE FROM history WHERE id NOT IN (
+            SELECT id FROM history ORDER BY id DESC LIMIT ?1
+        );"#,
+        params![MAX_HISTORY],
+    )?;
+
+    Ok(())
+}
+
+fn find(conn: &Connection, query: &str) -> Result<Option<(HistoryEntry, Operation)>> {
+    let Some((id, operation, created_at)) = conn
+        .query_row(query, [], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let operation: Operation = serde_json::from_str(&operation).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(err))
+    })?;
+
+    Ok(Some((
+        HistoryEntry {
+            id,
+            action: operation.action,
+            kind: operation.kind,
+            entity_id: operation.entity_id,
+            created_at,
+        },
+        operation,
+    )))
+}
+
+/// Read full row of `target`, if it exists.
+pub fn snapshot(conn: &Connection, target: Target) -> Result<Option<RowSnapshot>> {
+    let (kind, id) = target;
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE id = ?1;", kind.table()))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    stmt.query_row(params![id], |row| {
+        let mut snapshot = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            snapshot.insert(column.clone(), to_json(row.get_ref(index)?));
+        }
+
+        Ok(snapshot)
+    })
+    .optional()
+}
+
+/// Get snapshots of every row of `kind`, ordered by id.
+pub fn snapshot_all(conn: &Connection, kind: EntityKind) -> Result<Vec<RowSnapshot>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} ORDER BY id;", kind.table()))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt
+        .query_map([], |row| {
+            let mut snapshot = Map::new();
+            for (index, column) in columns.iter().enumerate() {
+                snapshot.insert(column.clone(), to_json(row.get_ref(index)?));
+            }
+
+            Ok(snapshot)
+        })?
+        .collect::<Result<Vec<RowSnapshot>>>()?;
+
+    Ok(rows)
+}
+
+/// Write back `snapshot` as the whole row of `target`, removing the row
+/// when there's no snapshot. Rows still there are updated in place, as
+/// delete triggers would take their children, or refuse to run.
+fn write(conn: &Connection, target: Target, snapshot: Option<&RowSnapshot>) -> Result<()> {
+    let (kind, id) = target;
+    let Some(snapshot) = snapshot else {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE id = ?1;", kind.table()),
+            params![id],
+        )?;
+        return Ok(());
+    };
+
+    let columns: Vec<&str> = snapshot.keys().map(String::as_str).collect();
+    let values = snapshot.values().map(from_json);
+    let exists: bool = conn.query_row(
+        &format!(
+            "SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?1);",
+            kind.table()
+        ),
+        params![id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        let assignments: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| format!("{} = ?{}", column, index + 2))
+            .collect();
+        conn.execute(
+            &format!(
+                "UPDATE {} SET {} WHERE id = ?1;",
+                kind.table(),
+                assignments.join(", ")
+            ),
+            params_from_iter(std::iter::once(SqlValue::Integer(id)).chain(values)),
+        )?;
+        return Ok(());
+    }
+
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+    conn.execute(
+        &format!(
+            "INSERT INTO {}({}) VALUES ({});",
+            kind.table(),
+            columns.join(", "),
+            placeholders.join(", ")
+        ),
+        params_from_iter(values),
+    )?;
+
+    Ok(())
+}
+
+fn to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(value) => Value::from(value),
+        ValueRef::Real(value) => Number::from_f64(value).map_or(Value::Null, Value::Number),
+        ValueRef::Text(value) => Value::String(String::from_utf8_lossy(value).into_owned()),
+        ValueRef::Blob(value) => Value::from(value.to_vec()),
+    }
+}
+
+pub(super) fn from_json(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(value) => SqlValue::Integer(*value as i64),
+        Value::Number(value) => match value.as_i64() {
+            Some(value) => SqlValue::Integer(value),
+            None => SqlValue::Real(value.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => SqlValue::Text(value.clone()),
+        Value::Array(values) => SqlValue::Blob(
+            values
+                .iter()
+                .map(|value| value.as_u64().unwrap_or_default() as u8)
+                .collect(),
+        ),
+        Value::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use rusqlite::Connection;
+
+    use super::{entity_tree, record, redo, undo, Action};
+    use crate::database::audit::Origin;
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::{EntityKind, Task};
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1), (2, 'Done', 1, 1);
+            INSERT INTO tasks(id, name, duration, priority, state_id, board_id, position)
+                VALUES (1, 'login', 30, 3, 1, 1, 0), (2, 'logout', 10, 1, 2, 1, 0);"#,
+        )
+        .unwrap();
+
+        conn
+    }
+
+    fn task_place(conn: &Connection, id: i64) -> (i64, u32) {
+        conn.query_row(
+            "SELECT state_id, position FROM tasks WHERE id = ?1;",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    fn move_login(conn: &Connection) {
+        let mut touched = entity_tree(conn, (EntityKind::State, 2)).unwrap();
+        touched.push((EntityKind::Task, 1));
+        record(
+            conn,
+            Origin::Gui,
+            Action::Move,
+            (EntityKind::Task, Some(1)),
+            &touched,
+            |conn| Ok((Task::move_to(conn, 1, 2, 0)?, vec![])),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_undo_and_redo_move() {
+        let conn = setup();
+        move_login(&conn);
+        assert_eq!((2, 0), task_place(&conn, 1));
+        assert_eq!((2, 1), task_place(&conn, 2));
+
+        let entry = undo(&conn, Origin::Gui).unwrap().unwrap();
+        assert_eq!(Action::Move, entry.action);
+        assert_eq!((1, 0), task_place(&conn, 1));
+        assert_eq!((2, 0), task_place(&conn, 2));
+        assert!(undo(&conn, Origin::Gui).unwrap().is_none());
+
+        redo(&conn, Origin::Gui).unwrap().unwrap();
+        assert_eq!((2, 0), task_place(&conn, 1));
+        assert_eq!((2, 1), task_place(&conn, 2));
+        assert!(redo(&conn, Origin::Gui).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_move_keeps_children() {
+        let conn = setup();
+        conn.execute_batch(
+            r#"INSERT INTO checklist_items(id, name, position, task_id)
+                VALUES (1, 'form', 0, 1), (2, 'api', 1, 1);
+            INSERT INTO time_logs(id, minutes, task_id) VALUES (1, 45, 1);
+            INSERT INTO tasks(id, name, duration, state_id, board_id, position, parent_id)
+                VALUES (3, 'oauth', 20, 1, 1, 1, 1);"#,
+        )
+        .unwrap();
+        move_login(&conn);
+        undo(&conn, Origin::Gui).unwrap().unwrap();
+        redo(&conn, Origin::Gui).unwrap().unwrap();
+        undo(&conn, Origin::Gui).unwrap().unwrap();
+
+        let children: (i64, i64, Option<i64>) = conn
+            .query_row(
+                r#"SELECT (SELECT COUNT(*) FROM checklist_items WHERE task_id = 1)
+                    , (SELECT COUNT(*) FROM time_logs WHERE task_id = 1)
+                    , (SELECT parent_id FROM tasks WHERE id = 3);"#,
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((2, 1, Some(1)), children);
+        assert_eq!((1, 0), task_place(&conn, 1));
+    }
+
+    #[test]
+    fn test_new_operation_clears_redo() {
+        let conn = setup();
+        move_login(&conn);
+        undo(&conn, Origin::Gui).unwrap();
+        record(
+            &conn,
+            Origin::Gui,
+            Action::Create,
+            (EntityKind::State, None),
+            &[],
+            |conn| {
+                conn.execute(
+                    "INSERT INTO states(id, name, position, board_id) VALUES (3, 'Review', 2, 1);",
+                    [],
+                )?;
+                Ok(((), vec![(EntityKind::State, 3)]))
+            },
+        )
+        .unwrap();
+
+        assert!(redo(&conn, Origin::Gui).unwrap().is_none());
+        undo(&conn, Origin::Gui).unwrap();
+        let states: i64 = conn
+            .query_row("SELECT COUNT(*) FROM states;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(2, states);
+    }
+}
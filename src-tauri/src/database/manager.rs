@@ -1,29 +1,793 @@
+use std::rc::Rc;
+
 use rusqlite::{Connection, Result};
 
-use super::models::ModelQueryBuilder;
+use super::archive::{self, ArchivedItem};
+use super::audit::{self, AuditEntry, Origin};
+use super::backup::{self, BackupConfig, BackupError, BackupInfo, BackupReason};
+use super::dashboard::{self, DashboardEntry, UrgencyWeights};
+use super::duplicate::{self, DuplicateOptions};
+use super::exchange::csv::{self, CsvExport, CsvImport, CsvImportReport};
+use super::exchange::github::{self, GitHubImport, GitHubSummary};
+use super::exchange::ical::{self, CalendarFeed, IcsOptions};
+use super::exchange::jira::{self, JiraImport, JiraMapping, JiraReport};
+use super::exchange::markdown::{self, MarkdownOptions};
+use super::exchange::taskwarrior::{
+    self, TaskwarriorExport, TaskwarriorImport, TaskwarriorSummary,
+};
+use super::exchange::todo_txt::{self, TodoTxtImport, TodoTxtReport};
+use super::exchange::trello::{self, TrelloSummary};
+use super::exchange::workspace::{self, ImportError, ImportMode, ImportSummary, Workspace};
+use super::filter::{self, FilterError, FilterPage, Scope};
+use super::fixtures;
+use super::history::{self, Action, HistoryEntry, Target};
+use super::migrations;
+use super::models::{
+    Board, ChecklistItem, EntityKind, Group, Milestone, ModelQueryBuilder, Priority, SavedView,
+    State, Task, TaskSummary, TimeLog, WorkingTime,
+};
+use super::pagination::{self, Page, PageRequest, StateTasks};
+use super::progress::{self, Rollup};
+use super::reports::burndown::{self, Burndown, BurndownRequest};
+use super::reports::flow::{self, CumulativeFlow, FlowRequest};
+use super::reports::metrics::{self, Distribution, WeekCount};
+use super::reports::ReportFilter;
+use super::search::{self, SearchHit};
+use super::views::{self, ViewResult};
 
 pub struct Manager {
     connection: Connection,
+    origin: Origin,
+    calendar_feed: Option<CalendarFeed>,
+    /// why the calendar feed could not be written last time.
+    calendar_feed_error: Option<String>,
+    backup_config: BackupConfig,
+    /// why the last backup failed, cleared once one succeeds.
+    last_backup_error: Option<BackupError>,
 }
 
 impl Manager {
     pub fn new(database: String) -> Result<Self> {
         let connection = Connection::open(database)?;
-        Ok(Manager { connection })
+        Ok(Manager {
+            connection,
+            origin: Origin::default(),
+            calendar_feed: None,
+            calendar_feed_error: None,
+            backup_config: BackupConfig::default(),
+            last_backup_error: None,
+        })
+    }
+
+    /// Sets where next changes come from, used by audit log.
+    pub fn set_origin(&mut self, origin: Origin) -> &mut Self {
+        self.origin = origin;
+
+        return self;
+    }
+
+    /// Create database tables if missing.
+    pub fn create_tables(&self) -> Result<()> {
+        fixtures::create_tables(&self.connection)
+    }
+
+    /// Get where backups go and how many are kept.
+    pub fn get_backup_config(&self) -> &BackupConfig {
+        &self.backup_config
+    }
+
+    /// Sets where backups go and how many are kept.
+    pub fn set_backup_config(&mut self, config: BackupConfig) -> &mut Self {
+        self.backup_config = config;
+
+        return self;
+    }
+
+    /// Get why the last backup failed, if it did. Startup and scheduled
+    /// backups have no caller to report to, views read it from here.
+    pub fn get_last_backup_error(&self) -> &Option<BackupError> {
+        &self.last_backup_error
+    }
+
+    /// Back up the database, `None` when it is still empty.
+    pub fn backup(
+        &mut self,
+        reason: BackupReason,
+    ) -> std::result::Result<Option<BackupInfo>, BackupError> {
+        let result = match backup::has_data(&self.connection) {
+            Ok(true) => backup::create(&self.connection, &self.backup_config, reason).map(Some),
+            Ok(false) => Ok(None),
+            Err(error) => Err(BackupError::from(error)),
+        };
+        self.last_backup_error = result.as_ref().err().cloned();
+
+        result
+    }
+
+    /// Back up the database when [`create_tables`](Self::create_tables)
+    /// has migrations to run on it. One backup covers the whole batch:
+    /// it holds the version the batch starts from, and as each migration
+    /// commits on its own, a failed one leaves the database at the last
+    /// version applied, which a later start migrates from.
+    pub fn backup_before_migrations(
+        &mut self,
+    ) -> std::result::Result<Option<BackupInfo>, BackupError> {
+        match migrations::pending(&self.connection)? {
+            0 => Ok(None),
+            _ => self.backup(BackupReason::Migration),
+        }
+    }
+
+    /// Back up the database when the scheduled backup is due.
+    pub fn backup_if_due(&mut self) -> std::result::Result<Option<BackupInfo>, BackupError> {
+        match backup::is_due(&self.connection, &self.backup_config) {
+            Ok(true) => self.backup(BackupReason::Scheduled),
+            Ok(false) => Ok(None),
+            Err(error) => {
+                self.last_backup_error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+
+    /// List backups, newest first.
+    pub fn list_backups(&self) -> std::io::Result<Vec<BackupInfo>> {
+        backup::list(&self.backup_config)
+    }
+
+    /// Replace the database with backup `file`, once validated. The
+    /// current database is backed up first, and the restored one migrated.
+    pub fn restore_backup(&mut self, file: &str) -> std::result::Result<(), BackupError> {
+        let path = backup::path(&self.backup_config, file)?;
+        let problems = backup::validate(&path);
+        if !problems.is_empty() {
+            return Err(BackupError::Invalid { problems });
+        }
+
+        self.backup(BackupReason::BeforeRestore)?;
+        backup::restore(&mut self.connection, &path)?;
+        self.create_tables()?;
+        self.write_calendar_feed()
+            .map_err(|message| BackupError::Io { message })?;
+
+        Ok(())
+    }
+
+    /// Run `action` inside a transaction, committing only when it succeeds.
+    fn in_transaction<T, F>(&mut self, action: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T>,
+    {
+        let transaction = self.connection.transaction()?;
+        let result = action(&transaction)?;
+        transaction.commit()?;
+        // a calendar left behind must not fail the change, its error is
+        // kept for views and the next change retries.
+        self.write_calendar_feed().ok();
+
+        Ok(result)
+    }
+
+    /// Rewrite the calendar feed file, if any, keeping why it failed.
+    fn write_calendar_feed(&mut self) -> std::result::Result<(), String> {
+        let Some(feed) = &self.calendar_feed else {
+            self.calendar_feed_error = None;
+            return Ok(());
+        };
+        let result = ical::write_feed(&self.connection, feed);
+        self.calendar_feed_error = result.as_ref().err().cloned();
+
+        result
+    }
+
+    /// Run `run` inside a transaction, recording the changes it makes on
+    /// the rows listed by `touched` into undo history and audit log.
+    fn record<T, G, F>(
+        &mut self,
+        action: Action,
+        target: (EntityKind, Option<i64>),
+        touched: G,
+        run: F,
+    ) -> Result<T>
+    where
+        G: FnOnce(&Connection) -> Result<Vec<Target>>,
+        F: FnOnce(&Connection) -> Result<(T, Vec<Target>)>,
+    {
+        self.record_as(self.origin, action, target, touched, run)
+    }
+
+    /// Same as [`Manager::record`], logging changes as made from `origin`
+    /// whatever the current one is. Imports are logged as automation.
+    fn record_as<T, G, F>(
+        &mut self,
+        origin: Origin,
+        action: Action,
+        target: (EntityKind, Option<i64>),
+        touched: G,
+        run: F,
+    ) -> Result<T>
+    where
+        G: FnOnce(&Connection) -> Result<Vec<Target>>,
+        F: FnOnce(&Connection) -> Result<(T, Vec<Target>)>,
+    {
+        self.in_transaction(|conn| {
+            let touched = touched(conn)?;
+            history::record(conn, origin, action, target, &touched, run)
+        })
     }
 
     /// Manage insert model in DB
-    pub fn insert<M: ModelQueryBuilder>(&self, model: &mut M) -> Result<bool> {
-        return model.insert(&self.connection);
+    pub fn insert<M: ModelQueryBuilder>(&mut self, model: &mut M) -> Result<bool> {
+        let kind = model.kind();
+        let origin = self.origin;
+        self.in_transaction(|conn| {
+            let touched = model.affected(conn)?;
+            history::record(
+                conn,
+                origin,
+                Action::Create,
+                (kind, None),
+                &touched,
+                |conn| {
+                    let inserted = model.insert(conn)?;
+                    let created = model.entity_id().map(|id| (kind, id));
+
+                    Ok((inserted, created.into_iter().collect()))
+                },
+            )
+        })
     }
 
     /// Manage update model in DB
-    pub fn update<M: ModelQueryBuilder>(&self, model: &M) -> Result<bool> {
-        return model.update(&self.connection);
+    pub fn update<M: ModelQueryBuilder>(&mut self, model: &M) -> Result<bool> {
+        let target = (model.kind(), model.entity_id());
+        self.record(
+            Action::Update,
+            target,
+            |conn| {
+                let mut touched: Vec<Target> =
+                    target.1.map(|id| (target.0, id)).into_iter().collect();
+                touched.extend(model.affected(conn)?);
+
+                Ok(touched)
+            },
+            |conn| Ok((model.update(conn)?, vec![])),
+        )
     }
 
     /// Manage deletion of models in DB
-    pub fn delete<M: ModelQueryBuilder>(&self, model: &M) -> Result<bool> {
-        return model.delete(&self.connection);
+    pub fn delete<M: ModelQueryBuilder>(&mut self, model: &M) -> Result<bool> {
+        let target = (model.kind(), model.entity_id());
+        self.record(
+            Action::Delete,
+            target,
+            // rows removed along by triggers are restored on undo too.
+            |conn| match target.1 {
+                Some(id) => history::entity_tree(conn, (target.0, id)),
+                None => Ok(vec![]),
+            },
+            |conn| Ok((model.delete(conn)?, vec![])),
+        )
+    }
+
+    /// Move a task into `state_id` at `position`.
+    pub fn move_task(&mut self, task_id: i64, state_id: i64, position: u32) -> Result<bool> {
+        self.record(
+            Action::Move,
+            (EntityKind::Task, Some(task_id)),
+            |conn| {
+                let mut touched = history::entity_tree(conn, (EntityKind::State, state_id))?;
+                touched.push((EntityKind::Task, task_id));
+
+                Ok(touched)
+            },
+            |conn| Ok((Task::move_to(conn, task_id, state_id, position)?, vec![])),
+        )
+    }
+
+    /// Load not archived groups with their boards, by position. Not paged,
+    /// like active states: this is the sidebar tree, that views show whole.
+    pub fn load_groups(&self) -> Result<Vec<Group>> {
+        Group::load_all(&self.connection)
+    }
+
+    /// Load a not archived board with its states.
+    pub fn load_board(&self, board_id: i64) -> Result<Board> {
+        let mut board = Board::load(&self.connection, board_id)?;
+        let states = State::load_by_board(&self.connection, board_id)?;
+        board.set_states(states.into_iter().map(Rc::new).collect());
+
+        Ok(board)
+    }
+
+    /// Load not archived tasks of a board, grouped by state. Done states
+    /// only get their first page.
+    pub fn load_tasks(&self, board_id: i64, request: &PageRequest) -> Result<Vec<StateTasks>> {
+        pagination::load_board_tasks(&self.connection, board_id, request)
+    }
+
+    /// Load a page of not archived tasks of a state.
+    pub fn load_state_tasks(
+        &self,
+        state_id: i64,
+        request: &PageRequest,
+    ) -> Result<Page<TaskSummary>> {
+        TaskSummary::load_by_state(&self.connection, state_id, request)
+    }
+
+    /// Deep-copy a board in a single transaction, returning the copy id.
+    pub fn duplicate_board(&mut self, board_id: i64, options: &DuplicateOptions) -> Result<i64> {
+        self.record(
+            Action::Duplicate,
+            (EntityKind::Board, None),
+            |_| Ok(vec![]),
+            |conn| {
+                let id = duplicate::duplicate_board(conn, board_id, options)?;
+
+                Ok((id, history::entity_tree(conn, (EntityKind::Board, id))?))
+            },
+        )
+    }
+
+    /// Deep-copy a group in a single transaction, returning the copy id.
+    pub fn duplicate_group(&mut self, group_id: i64, options: &DuplicateOptions) -> Result<i64> {
+        self.record(
+            Action::Duplicate,
+            (EntityKind::Group, None),
+            |_| Ok(vec![]),
+            |conn| {
+                let id = duplicate::duplicate_group(conn, group_id, options)?;
+
+                Ok((id, history::entity_tree(conn, (EntityKind::Group, id))?))
+            },
+        )
+    }
+
+    /// Export the whole workspace.
+    pub fn export_workspace(&self) -> Result<Workspace> {
+        workspace::export(&self.connection)
+    }
+
+    /// Import a workspace file as a single operation, which can be undone.
+    /// The file is fully validated first, nothing is written when any
+    /// problem is found.
+    pub fn import_workspace(
+        &mut self,
+        contents: &str,
+        mode: ImportMode,
+    ) -> std::result::Result<ImportSummary, ImportError> {
+        let file = workspace::parse(contents)?;
+        let problems = workspace::validate(&self.connection, &file)?;
+        if !problems.is_empty() {
+            return Err(ImportError::Invalid { problems });
+        }
+        let action = match mode {
+            ImportMode::Merge => Action::Create,
+            ImportMode::Replace => Action::Update,
+        };
+
+        Ok(self.record_as(
+            Origin::Automation,
+            action,
+            (EntityKind::Group, None),
+            |conn| match mode {
+                ImportMode::Merge => Ok(vec![]),
+                ImportMode::Replace => workspace::current(conn, &file),
+            },
+            |conn| workspace::import(conn, &file, mode),
+        )?)
+    }
+
+    /// Export tasks as CSV.
+    pub fn export_tasks_csv(
+        &self,
+        request: &CsvExport,
+    ) -> std::result::Result<String, FilterError> {
+        csv::export(&self.connection, request)
+    }
+
+    /// Import tasks from CSV in a single undoable step. On dry-run every
+    /// row is written and checked, then rolled back.
+    pub fn import_tasks_csv(
+        &mut self,
+        contents: &str,
+        request: &CsvImport,
+    ) -> Result<CsvImportReport> {
+        if request.dry_run {
+            let transaction = self.connection.transaction()?;
+            let (report, _) = csv::import(&transaction, contents, request)?;
+            transaction.rollback()?;
+
+            return Ok(report);
+        }
+
+        self.record_as(
+            Origin::Automation,
+            Action::Create,
+            (EntityKind::Task, None),
+            |_| Ok(vec![]),
+            |conn| csv::import(conn, contents, request),
+        )
+    }
+
+    /// Render a board or a group as Markdown.
+    pub fn render_markdown(
+        &self,
+        kind: EntityKind,
+        id: i64,
+        options: &MarkdownOptions,
+    ) -> Result<String> {
+        markdown::render(&self.connection, kind, id, options)
+    }
+
+    /// Import a Trello board export as a new board of `group_id`, in a
+    /// single undoable step.
+    pub fn import_trello(
+        &mut self,
+        contents: &str,
+        group_id: i64,
+    ) -> std::result::Result<TrelloSummary, ImportError> {
+        let board = trello::parse(contents)?;
+
+        Ok(self.record_as(
+            Origin::Automation,
+            Action::Create,
+            (EntityKind::Board, None),
+            |_| Ok(vec![]),
+            |conn| {
+                let summary = trello::import(conn, &board, group_id)?;
+                let touched = history::entity_tree(conn, (EntityKind::Board, summary.board_id()))?;
+
+                Ok((summary, touched))
+            },
+        )?)
+    }
+
+    /// Import a `gh issue list --json` dump, updating issues imported before.
+    pub fn import_github(
+        &mut self,
+        contents: &str,
+        request: &GitHubImport,
+    ) -> std::result::Result<GitHubSummary, ImportError> {
+        let issues = github::parse(contents)?;
+        let states = github::states(&self.connection, request)?;
+
+        Ok(self.record_as(
+            Origin::Automation,
+            Action::Create,
+            (EntityKind::Task, None),
+            |conn| github::imported(conn, &issues),
+            |conn| github::import(conn, &issues, request.board_id, states),
+        )?)
+    }
+
+    /// Statuses and priorities of a Jira CSV export, with suggested mapping
+    /// and the rows that could not be read.
+    pub fn preview_jira(&self, contents: &str, request: &JiraImport) -> Result<JiraMapping> {
+        let (issues, errors) = jira::parse(contents);
+        let mut mapping = jira::mapping(&self.connection, &issues, request)?;
+        mapping.add_errors(errors);
+
+        Ok(mapping)
+    }
+
+    /// Import a Jira CSV export, skipping issues imported before.
+    pub fn import_jira(&mut self, contents: &str, request: &JiraImport) -> Result<JiraReport> {
+        let (issues, errors) = jira::parse(contents);
+
+        self.record_as(
+            Origin::Automation,
+            Action::Create,
+            (EntityKind::Task, None),
+            |_| Ok(vec![]),
+            |conn| {
+                let (mut report, written) = jira::import(conn, &issues, request)?;
+                report.add_errors(errors);
+
+                Ok((report, written))
+            },
+        )
+    }
+
+    /// Export a board as todo.txt lines.
+    pub fn export_todo_txt(&self, board_id: i64) -> Result<String> {
+        todo_txt::export(&self.connection, board_id)
+    }
+
+    /// Sync a todo.txt file into a board.
+    pub fn import_todo_txt(
+        &mut self,
+        contents: &str,
+        request: &TodoTxtImport,
+    ) -> Result<TodoTxtReport> {
+        let (items, errors) = todo_txt::parse(contents);
+
+        self.record_as(
+            Origin::Automation,
+            Action::Update,
+            (EntityKind::Board, Some(request.board_id)),
+            |conn| todo_txt::synced(conn, &items, request.board_id),
+            |conn| {
+                let (mut report, created) = todo_txt::import(conn, &items, request)?;
+                report.add_errors(errors);
+
+                Ok((report, created))
+            },
+        )
+    }
+
+    /// Tasks in Taskwarrior import format, keeping their uuids.
+    pub fn export_taskwarrior(&self, options: &TaskwarriorExport) -> Result<String> {
+        taskwarrior::export(&self.connection, options)
+    }
+
+    /// Import a `task export` dump, updating tasks of matching uuid.
+    pub fn import_taskwarrior(
+        &mut self,
+        contents: &str,
+        request: &TaskwarriorImport,
+    ) -> std::result::Result<TaskwarriorSummary, ImportError> {
+        let tasks = taskwarrior::parse(contents)?;
+        taskwarrior::check(&self.connection, request)?;
+
+        Ok(self.record_as(
+            Origin::Automation,
+            Action::Create,
+            (EntityKind::Task, None),
+            |conn| taskwarrior::imported(conn, &tasks),
+            |conn| taskwarrior::import(conn, &tasks, request),
+        )?)
+    }
+
+    /// Render tasks, milestones and time logs as an iCalendar file.
+    pub fn export_ics(&self, options: &IcsOptions) -> Result<String> {
+        ical::render(&self.connection, options)
+    }
+
+    /// Keep a calendar file up to date with every change, or stop when
+    /// `feed` is `None`. The file is written right away; the feed is kept
+    /// even when that fails, and retried on every change.
+    pub fn set_calendar_feed(
+        &mut self,
+        feed: Option<CalendarFeed>,
+    ) -> std::result::Result<(), String> {
+        self.calendar_feed = feed;
+
+        self.write_calendar_feed()
+    }
+
+    /// Get why the calendar feed could not be written on last change.
+    pub fn get_calendar_feed_error(&self) -> &Option<String> {
+        &self.calendar_feed_error
+    }
+
+    /// Archive an entity, hiding it from loads.
+    pub fn archive(&mut self, kind: EntityKind, id: i64) -> Result<bool> {
+        self.record(
+            Action::Archive,
+            (kind, Some(id)),
+            |_| Ok(vec![(kind, id)]),
+            |conn| Ok((archive::archive(conn, kind, id)?, vec![])),
+        )
+    }
+
+    /// List a page of archived entities, optionally of a single kind.
+    pub fn list_archived(
+        &self,
+        kind: Option<EntityKind>,
+        request: &PageRequest,
+    ) -> Result<Page<ArchivedItem>> {
+        archive::list_archived(&self.connection, kind, request)
+    }
+
+    /// Restore an archived entity at its original position.
+    pub fn restore(&mut self, kind: EntityKind, id: i64) -> Result<bool> {
+        self.record(
+            Action::Restore,
+            (kind, Some(id)),
+            |conn| history::siblings(conn, (kind, id)),
+            |conn| Ok((archive::restore(conn, kind, id)?, vec![])),
+        )
+    }
+
+    /// Permanently delete an archived entity with its children.
+    pub fn purge(&mut self, kind: EntityKind, id: i64) -> Result<bool> {
+        self.record(
+            Action::Purge,
+            (kind, Some(id)),
+            |conn| history::entity_tree(conn, (kind, id)),
+            |conn| Ok((archive::purge(conn, kind, id)?, vec![])),
+        )
+    }
+
+    /// Revert last recorded operation.
+    pub fn undo(&mut self) -> Result<Option<HistoryEntry>> {
+        let origin = self.origin;
+        self.in_transaction(|conn| history::undo(conn, origin))
+    }
+
+    /// Apply again last undone operation.
+    pub fn redo(&mut self) -> Result<Option<HistoryEntry>> {
+        let origin = self.origin;
+        self.in_transaction(|conn| history::redo(conn, origin))
+    }
+
+    /// Get audit log of an entity, optionally of a single field.
+    pub fn entity_history(
+        &self,
+        kind: EntityKind,
+        id: i64,
+        field: Option<&str>,
+    ) -> Result<Vec<AuditEntry>> {
+        audit::entity_history(&self.connection, kind, id, field)
+    }
+
+    /// Find when an entity field was last set to `value`.
+    pub fn changed_to(
+        &self,
+        kind: EntityKind,
+        id: i64,
+        field: &str,
+        value: serde_json::Value,
+    ) -> Result<Option<AuditEntry>> {
+        audit::changed_to(&self.connection, kind, id, field, value)
+    }
+
+    /// Full-text search over tasks, a page at a time.
+    pub fn search(&self, query: &str, request: &PageRequest) -> Result<Page<SearchHit>> {
+        search::search(&self.connection, query, request)
+    }
+
+    /// Get a page of tasks matching a filter like `priority:high label:bug`.
+    pub fn filter_tasks(
+        &self,
+        filter: &str,
+        scope: Scope,
+        request: &PageRequest,
+    ) -> std::result::Result<FilterPage, FilterError> {
+        let terms = filter::parse(filter)?;
+
+        Ok(filter::filter_tasks(
+            &self.connection,
+            &terms,
+            scope,
+            request,
+        )?)
+    }
+
+    /// List what matters today across every group, most urgent first.
+    pub fn today(&self, weights: &UrgencyWeights) -> Result<Vec<DashboardEntry>> {
+        dashboard::today(&self.connection, weights)
+    }
+
+    /// Working time of a board, or the default one without board.
+    pub fn working_time(&self, board_id: Option<i64>) -> Result<WorkingTime> {
+        match board_id {
+            Some(board_id) => Ok(Board::load(&self.connection, board_id)?.get_working_time()),
+            None => Ok(WorkingTime::default()),
+        }
+    }
+
+    /// Load priorities used by a board, or the workspace ones.
+    pub fn load_priorities(&self, board_id: Option<i64>) -> Result<Vec<Priority>> {
+        Priority::load_scheme(&self.connection, board_id)
+    }
+
+    /// Give board `board_id` a priority scheme of its own in a single
+    /// undoable step, moving its tasks to the levels of closest rank.
+    pub fn create_priority_scheme(
+        &mut self,
+        board_id: i64,
+        levels: &mut [Priority],
+    ) -> Result<bool> {
+        self.record(
+            Action::Create,
+            (EntityKind::Priority, None),
+            |conn| Priority::workspace_tasks(conn, board_id),
+            |conn| Ok((true, Priority::create_scheme(conn, board_id, levels)?)),
+        )
+    }
+
+    /// Delete priority `priority_id`, failing while tasks use it.
+    pub fn delete_priority(&mut self, priority_id: i64) -> Result<bool> {
+        let priority = Priority::load(&self.connection, priority_id)?;
+
+        self.delete(&priority)
+    }
+
+    /// Load checklist of task `task_id`.
+    pub fn load_checklist(&self, task_id: i64) -> Result<Vec<ChecklistItem>> {
+        ChecklistItem::load_by_task(&self.connection, task_id)
+    }
+
+    /// Delete checklist item `item_id`.
+    pub fn delete_checklist_item(&mut self, item_id: i64) -> Result<bool> {
+        let item = ChecklistItem::load(&self.connection, item_id)?;
+
+        self.delete(&item)
+    }
+
+    /// Load time logged on task `task_id`.
+    pub fn load_time_logs(&self, task_id: i64) -> Result<Vec<TimeLog>> {
+        TimeLog::load_by_task(&self.connection, task_id)
+    }
+
+    /// Delete time log `log_id`.
+    pub fn delete_time_log(&mut self, log_id: i64) -> Result<bool> {
+        let log = TimeLog::load(&self.connection, log_id)?;
+
+        self.delete(&log)
+    }
+
+    /// Load milestones of board `board_id`.
+    pub fn load_milestones(&self, board_id: i64) -> Result<Vec<Milestone>> {
+        Milestone::load_by_board(&self.connection, board_id)
+    }
+
+    /// Delete milestone `milestone_id`, keeping its tasks.
+    pub fn delete_milestone(&mut self, milestone_id: i64) -> Result<bool> {
+        let milestone = Milestone::load(&self.connection, milestone_id)?;
+
+        self.delete(&milestone)
+    }
+
+    /// Roll up progress of a board, milestone or group.
+    pub fn progress_rollup(&self, kind: EntityKind, id: i64) -> Result<Rollup> {
+        progress::rollup(&self.connection, kind, id)
+    }
+
+    /// Days from creation to completion of tasks matching `report`.
+    pub fn lead_time(&self, report: &ReportFilter) -> Result<Distribution> {
+        metrics::lead_time(&self.connection, report)
+    }
+
+    /// Days from work start to completion of tasks matching `report`.
+    pub fn cycle_time(&self, report: &ReportFilter) -> Result<Distribution> {
+        metrics::cycle_time(&self.connection, report)
+    }
+
+    /// Tasks matching `report` completed per week.
+    pub fn throughput(&self, report: &ReportFilter) -> Result<Vec<WeekCount>> {
+        metrics::throughput(&self.connection, report)
+    }
+
+    /// Age of tasks in progress matching `report`.
+    pub fn wip_age(&self, report: &ReportFilter) -> Result<Distribution> {
+        metrics::wip_age(&self.connection, report)
+    }
+
+    /// Daily burndown and burnup series of a board or milestone.
+    pub fn burndown(&self, request: &BurndownRequest) -> Result<Burndown> {
+        burndown::burndown(&self.connection, request)
+    }
+
+    /// Daily count of tasks per state of a board.
+    pub fn cumulative_flow(&self, request: &FlowRequest) -> Result<CumulativeFlow> {
+        flow::cumulative_flow(&self.connection, request)
+    }
+
+    /// Load saved views, optionally only the ones pinned to sidebar, by
+    /// position. Not paged, views are a short list the user orders by hand.
+    pub fn load_views(&self, pinned_only: bool) -> Result<Vec<SavedView>> {
+        SavedView::load_all(&self.connection, pinned_only)
+    }
+
+    /// Delete saved view `view_id`.
+    pub fn delete_view(&mut self, view_id: i64) -> Result<bool> {
+        let view = SavedView::load(&self.connection, view_id)?;
+
+        self.delete(&view)
+    }
+
+    /// Get tasks matching saved view `view_id`, grouped as the view says.
+    pub fn evaluate_view(
+        &self,
+        view_id: i64,
+        limit: Option<u32>,
+        cursor: Option<String>,
+    ) -> std::result::Result<ViewResult, FilterError> {
+        let view = SavedView::load(&self.connection, view_id)?;
+
+        views::evaluate(&self.connection, &view, limit, cursor)
     }
 }
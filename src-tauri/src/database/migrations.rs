@@ -0,0 +1,446 @@
+use rusqlite::{Connection, Result};
+
+/// Schema changes applied over the tables created by [`create_tables`](super::fixtures::create_tables).
+/// Each entry runs once, the last applied one is tracked by sqlite `user_version`.
+const MIGRATIONS: &[&str] = &[
+    // archiving support.
+    r#"ALTER TABLE groups ADD COLUMN archived_at TEXT DEFAULT NULL;
+    ALTER TABLE boards ADD COLUMN archived_at TEXT DEFAULT NULL;
+    ALTER TABLE states ADD COLUMN archived_at TEXT DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN archived_at TEXT DEFAULT NULL;"#,
+    // undo/redo history.
+    r#"CREATE TABLE IF NOT EXISTS history(
+        id INTEGER PRIMARY KEY
+        , operation TEXT NOT NULL
+        , undone TINYINT NOT NULL DEFAULT 0
+        , created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );"#,
+    // per field audit log.
+    r#"CREATE TABLE IF NOT EXISTS audit_log(
+        id INTEGER PRIMARY KEY
+        , entity VARCHAR(20) NOT NULL
+        , entity_id BIGINT NOT NULL
+        , action VARCHAR(20) NOT NULL
+        , field VARCHAR(100) NOT NULL
+        , old_value TEXT DEFAULT NULL
+        , new_value TEXT DEFAULT NULL
+        , origin VARCHAR(20) NOT NULL
+        , changed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS audit_log_entity ON audit_log(entity, entity_id, field);"#,
+    // full-text search over tasks.
+    r#"CREATE VIRTUAL TABLE IF NOT EXISTS tasks_search USING fts5(
+        name, description, content='tasks', content_rowid='id', tokenize='unicode61 remove_diacritics 2'
+    );
+    CREATE TRIGGER IF NOT EXISTS tasks_search_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_search(rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_delete AFTER DELETE ON tasks BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description)
+            VALUES ('delete', old.id, old.name, old.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_update AFTER UPDATE OF name, description ON tasks BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description)
+            VALUES ('delete', old.id, old.name, old.description);
+        INSERT INTO tasks_search(rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    INSERT INTO tasks_search(tasks_search) VALUES ('rebuild');"#,
+    // task due dates and labels.
+    r#"ALTER TABLE tasks ADD COLUMN due_at TEXT DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN labels TEXT NOT NULL DEFAULT '[]';"#,
+    // saved views.
+    r#"CREATE TABLE IF NOT EXISTS saved_views(
+        id INTEGER PRIMARY KEY
+        , name VARCHAR(100) NOT NULL
+        , filter TEXT NOT NULL DEFAULT ''
+        , sort TEXT NOT NULL DEFAULT '"Position"'
+        , grouping TEXT DEFAULT NULL
+        , scope TEXT NOT NULL DEFAULT '{"type":"Workspace"}'
+        , pinned TINYINT NOT NULL DEFAULT 0
+        , position INTEGER NOT NULL DEFAULT 0
+        , archived_at TEXT DEFAULT NULL
+    );"#,
+    // done states and task timestamps used by pagination.
+    r#"ALTER TABLE states ADD COLUMN done TINYINT NOT NULL DEFAULT 0;
+    ALTER TABLE tasks ADD COLUMN created_at TEXT DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN updated_at TEXT DEFAULT NULL;
+    UPDATE tasks SET created_at = COALESCE(started_at, datetime('now')), updated_at = datetime('now');
+    CREATE TRIGGER IF NOT EXISTS tasks_created AFTER INSERT ON tasks WHEN new.created_at IS NULL BEGIN
+        UPDATE tasks SET created_at = datetime('now'), updated_at = datetime('now') WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_updated AFTER UPDATE ON tasks
+    WHEN new.updated_at IS old.updated_at BEGIN
+        UPDATE tasks SET updated_at = datetime('now') WHERE id = new.id;
+    END;
+    CREATE INDEX IF NOT EXISTS tasks_state_position ON tasks(state_id, position);"#,
+    // states where tasks are being worked on.
+    r#"ALTER TABLE states ADD COLUMN active TINYINT NOT NULL DEFAULT 0;"#,
+    // user defined priorities, `tasks.priority` now references `priorities.id`.
+    // old codes 1 to 4 become the workspace levels with same ids.
+    r#"CREATE TABLE IF NOT EXISTS priorities(
+        id INTEGER PRIMARY KEY
+        , name VARCHAR(100) NOT NULL
+        , color VARCHAR(50) DEFAULT NULL
+        , rank INTEGER NOT NULL
+        , icon VARCHAR(255) DEFAULT NULL
+        , board_id BIGINT DEFAULT NULL
+    );
+    INSERT INTO priorities(id, name, rank) VALUES (1, 'Low', 1), (2, 'Normal', 2), (3, 'High', 3), (4, 'Now', 4);
+    UPDATE tasks SET priority = 1 WHERE priority IS NULL OR priority NOT IN (1, 2, 3, 4);
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_insert BEFORE INSERT ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id IS NULL OR p.board_id = new.board_id)
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_update BEFORE UPDATE OF priority, board_id ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id IS NULL OR p.board_id = new.board_id)
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;
+    CREATE TRIGGER IF NOT EXISTS priorities_in_use BEFORE DELETE ON priorities
+    WHEN EXISTS (SELECT 1 FROM tasks WHERE priority = old.id) BEGIN
+        SELECT RAISE(ABORT, 'priority in use');
+    END;"#,
+    // optional estimates and story points, `tasks` is rebuilt since sqlite
+    // cannot drop the `NOT NULL` of `duration`. Boards get working time
+    // used to convert days and weeks.
+    r#"CREATE TABLE tasks_new(
+        id INTEGER PRIMARY KEY
+        , name VARCHAR(255) NOT NULL
+        , description LONGTEXT DEFAULT NULL
+        , duration INTEGER DEFAULT NULL CHECK (duration IS NULL OR duration >= 0)
+        , story_points REAL DEFAULT NULL CHECK (story_points IS NULL OR story_points >= 0)
+        , progress INTEGER DEFAULT NULL
+        , priority TINY INT
+        , state_id INTEGER NOT NULL
+        , board_id BIGINT NOT NULL
+        , position INTEGER NOT NULL
+        , started_at TEXT DEFAULT NULL
+        , ended_at TEXT DEFAULT NULL
+        , archived_at TEXT DEFAULT NULL
+        , due_at TEXT DEFAULT NULL
+        , labels TEXT NOT NULL DEFAULT '[]'
+        , created_at TEXT DEFAULT NULL
+        , updated_at TEXT DEFAULT NULL
+    );
+    INSERT INTO tasks_new(
+        id, name, description, duration, progress, priority, state_id, board_id, position
+        , started_at, ended_at, archived_at, due_at, labels, created_at, updated_at
+    ) SELECT id, name, description, duration, progress, priority, state_id, board_id, position
+        , started_at, ended_at, archived_at, due_at, labels, created_at, updated_at
+    FROM tasks;
+    DROP TRIGGER IF EXISTS priorities_in_use;
+    DROP TABLE tasks;
+    ALTER TABLE tasks_new RENAME TO tasks;
+    CREATE INDEX IF NOT EXISTS tasks_state_position ON tasks(state_id, position);
+    CREATE TRIGGER IF NOT EXISTS tasks_search_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_search(rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_delete AFTER DELETE ON tasks BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description)
+            VALUES ('delete', old.id, old.name, old.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_update AFTER UPDATE OF name, description ON tasks BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description)
+            VALUES ('delete', old.id, old.name, old.description);
+        INSERT INTO tasks_search(rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_created AFTER INSERT ON tasks WHEN new.created_at IS NULL BEGIN
+        UPDATE tasks SET created_at = datetime('now'), updated_at = datetime('now') WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_updated AFTER UPDATE ON tasks
+    WHEN new.updated_at IS old.updated_at BEGIN
+        UPDATE tasks SET updated_at = datetime('now') WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_insert BEFORE INSERT ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id IS NULL OR p.board_id = new.board_id)
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_update BEFORE UPDATE OF priority, board_id ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id IS NULL OR p.board_id = new.board_id)
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;
+    CREATE TRIGGER IF NOT EXISTS priorities_in_use BEFORE DELETE ON priorities
+    WHEN EXISTS (SELECT 1 FROM tasks WHERE priority = old.id) BEGIN
+        SELECT RAISE(ABORT, 'priority in use');
+    END;
+    ALTER TABLE boards ADD COLUMN hours_per_day REAL NOT NULL DEFAULT 8
+        CHECK (hours_per_day > 0 AND hours_per_day <= 24);
+    ALTER TABLE boards ADD COLUMN days_per_week REAL NOT NULL DEFAULT 5
+        CHECK (days_per_week > 0 AND days_per_week <= 7);"#,
+    // progress strategies and what they are computed from: checklists,
+    // logged time and subtasks. Tasks may also belong to a milestone.
+    // Progress out of 0.0 to 1.0 is brought back in range, each change
+    // logged in the audit log so tasks show what they had.
+    r#"ALTER TABLE boards ADD COLUMN progress_strategy VARCHAR(20) NOT NULL DEFAULT 'Manual';
+    ALTER TABLE tasks ADD COLUMN parent_id BIGINT DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN milestone_id BIGINT DEFAULT NULL;
+    CREATE TABLE IF NOT EXISTS checklist_items(
+        id INTEGER PRIMARY KEY
+        , name VARCHAR(255) NOT NULL
+        , done TINYINT NOT NULL DEFAULT 0
+        , position INTEGER NOT NULL
+        , task_id BIGINT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS checklist_items_task ON checklist_items(task_id, position);
+    CREATE TABLE IF NOT EXISTS time_logs(
+        id INTEGER PRIMARY KEY
+        , minutes INTEGER NOT NULL CHECK (minutes >= 0)
+        , note TEXT DEFAULT NULL
+        , logged_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        , task_id BIGINT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS time_logs_task ON time_logs(task_id);
+    CREATE TABLE IF NOT EXISTS milestones(
+        id INTEGER PRIMARY KEY
+        , name VARCHAR(255) NOT NULL
+        , due_at TEXT DEFAULT NULL
+        , position INTEGER NOT NULL
+        , board_id BIGINT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_parent ON tasks(parent_id);
+    INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin)
+        SELECT 'Task', id, 'Update', 'progress', CAST(progress AS TEXT)
+            , CAST(MIN(1.0, MAX(0.0, progress)) AS TEXT), 'Automation'
+        FROM tasks WHERE progress < 0 OR progress > 1;
+    UPDATE tasks SET progress = MIN(1.0, MAX(0.0, progress)) WHERE progress < 0 OR progress > 1;
+    CREATE TRIGGER IF NOT EXISTS tasks_progress_insert BEFORE INSERT ON tasks
+    WHEN new.progress < 0 OR new.progress > 1 BEGIN
+        SELECT RAISE(ABORT, 'invalid progress');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_progress_update BEFORE UPDATE OF progress ON tasks
+    WHEN new.progress < 0 OR new.progress > 1 BEGIN
+        SELECT RAISE(ABORT, 'invalid progress');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_parent_update BEFORE UPDATE OF parent_id ON tasks
+    WHEN new.parent_id = new.id BEGIN
+        SELECT RAISE(ABORT, 'invalid parent');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_children_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM checklist_items WHERE task_id = old.id;
+        DELETE FROM time_logs WHERE task_id = old.id;
+        UPDATE tasks SET parent_id = NULL WHERE parent_id = old.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS milestones_delete AFTER DELETE ON milestones BEGIN
+        UPDATE tasks SET milestone_id = NULL WHERE milestone_id = old.id;
+    END;"#,
+    // tool and id tasks were imported from, so importing again updates
+    // them instead of creating copies.
+    r#"ALTER TABLE tasks ADD COLUMN external_source VARCHAR(20) DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN external_id VARCHAR(255) DEFAULT NULL;
+    ALTER TABLE tasks ADD COLUMN external_url TEXT DEFAULT NULL;
+    CREATE INDEX IF NOT EXISTS tasks_external ON tasks(external_source, external_id);"#,
+    // random version 4 uuid of each task, kept by exports for round-trips.
+    r#"ALTER TABLE tasks ADD COLUMN uuid VARCHAR(36) DEFAULT NULL;
+    DROP TRIGGER IF EXISTS tasks_updated;
+    UPDATE tasks SET uuid = lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2)))
+        || '-4' || substr(lower(hex(randomblob(2))), 2)
+        || '-' || substr('89ab', 1 + abs(random()) % 4, 1) || substr(lower(hex(randomblob(2))), 2)
+        || '-' || lower(hex(randomblob(6)));
+    CREATE TRIGGER IF NOT EXISTS tasks_updated AFTER UPDATE ON tasks
+    WHEN new.updated_at IS old.updated_at BEGIN
+        UPDATE tasks SET updated_at = datetime('now') WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_uuid_insert AFTER INSERT ON tasks WHEN new.uuid IS NULL BEGIN
+        UPDATE tasks SET uuid = lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2)))
+            || '-4' || substr(lower(hex(randomblob(2))), 2)
+            || '-' || substr('89ab', 1 + abs(random()) % 4, 1) || substr(lower(hex(randomblob(2))), 2)
+            || '-' || lower(hex(randomblob(6)))
+        WHERE id = new.id;
+    END;
+    CREATE INDEX IF NOT EXISTS tasks_uuid ON tasks(uuid);"#,
+    // tasks follow their board scheme: levels in use can't change board,
+    // and tasks on workspace levels move to the board level of closest
+    // rank once the board gets levels of its own.
+    r#"CREATE TRIGGER IF NOT EXISTS priorities_board_update BEFORE UPDATE OF board_id ON priorities
+    WHEN new.board_id IS NOT old.board_id AND EXISTS (SELECT 1 FROM tasks WHERE priority = old.id)
+    BEGIN
+        SELECT RAISE(ABORT, 'priority in use');
+    END;
+    CREATE TRIGGER IF NOT EXISTS priorities_scheme_insert AFTER INSERT ON priorities
+    WHEN new.board_id IS NOT NULL BEGIN
+        UPDATE tasks SET priority = (
+            SELECT p.id FROM priorities p JOIN priorities w ON w.id = tasks.priority
+            WHERE p.board_id = new.board_id
+            ORDER BY abs(p.rank - w.rank)
+                , p.rank DESC, p.id
+            LIMIT 1
+        ) WHERE board_id = new.board_id
+            AND priority IN (SELECT id FROM priorities WHERE board_id IS NULL);
+    END;
+    CREATE TRIGGER IF NOT EXISTS priorities_scheme_update AFTER UPDATE OF board_id ON priorities
+    WHEN new.board_id IS NOT NULL BEGIN
+        UPDATE tasks SET priority = (
+            SELECT p.id FROM priorities p JOIN priorities w ON w.id = tasks.priority
+            WHERE p.board_id = new.board_id
+            ORDER BY abs(p.rank - w.rank)
+                , p.rank DESC, p.id
+            LIMIT 1
+        ) WHERE board_id = new.board_id
+            AND priority IN (SELECT id FROM priorities WHERE board_id IS NULL);
+    END;
+    UPDATE tasks SET priority = (
+        SELECT p.id FROM priorities p JOIN priorities w ON w.id = tasks.priority
+        WHERE p.board_id = tasks.board_id
+        ORDER BY abs(p.rank - w.rank)
+            , p.rank DESC, p.id
+        LIMIT 1
+    ) WHERE priority IN (SELECT id FROM priorities WHERE board_id IS NULL)
+        AND EXISTS (SELECT 1 FROM priorities p WHERE p.board_id = tasks.board_id);"#,
+    // a task can't be its own ancestor. Search also finds checklist items:
+    // the index reads its content from a view, deletes run before a change
+    // and inserts after it, so the view gives what is indexed.
+    r#"DROP TRIGGER IF EXISTS tasks_parent_update;
+    CREATE TRIGGER IF NOT EXISTS tasks_parent_update BEFORE UPDATE OF parent_id ON tasks
+    WHEN new.id IN (
+        WITH RECURSIVE ancestors(id) AS (
+            SELECT new.parent_id
+            UNION SELECT t.parent_id FROM tasks t JOIN ancestors a ON t.id = a.id
+        ) SELECT id FROM ancestors
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid parent');
+    END;
+    DROP TRIGGER IF EXISTS tasks_search_insert;
+    DROP TRIGGER IF EXISTS tasks_search_delete;
+    DROP TRIGGER IF EXISTS tasks_search_update;
+    DROP TABLE IF EXISTS tasks_search;
+    CREATE VIEW IF NOT EXISTS tasks_search_content AS
+        SELECT t.id, t.name, t.description, (
+            SELECT group_concat(c.name, char(10)) FROM (
+                SELECT name FROM checklist_items WHERE task_id = t.id ORDER BY position, id
+            ) c
+        ) AS checklist
+        FROM tasks t;
+    CREATE VIRTUAL TABLE IF NOT EXISTS tasks_search USING fts5(
+        name, description, checklist, content='tasks_search_content', content_rowid='id'
+        , tokenize='unicode61 remove_diacritics 2'
+    );
+    CREATE TRIGGER IF NOT EXISTS tasks_search_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO tasks_search(rowid, name, description, checklist)
+            SELECT id, name, description, checklist FROM tasks_search_content WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_delete BEFORE DELETE ON tasks BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description, checklist)
+            SELECT 'delete', id, name, description, checklist
+            FROM tasks_search_content WHERE id = old.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_update_old BEFORE UPDATE OF name, description ON tasks
+    BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description, checklist)
+            SELECT 'delete', id, name, description, checklist
+            FROM tasks_search_content WHERE id = old.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_search_update AFTER UPDATE OF name, description ON tasks
+    BEGIN
+        INSERT INTO tasks_search(rowid, name, description, checklist)
+            SELECT id, name, description, checklist FROM tasks_search_content WHERE id = new.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_insert_old BEFORE INSERT ON checklist_items
+    BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description, checklist)
+            SELECT 'delete', id, name, description, checklist
+            FROM tasks_search_content WHERE id = new.task_id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_insert AFTER INSERT ON checklist_items BEGIN
+        INSERT INTO tasks_search(rowid, name, description, checklist)
+            SELECT id, name, description, checklist FROM tasks_search_content WHERE id = new.task_id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_delete_old BEFORE DELETE ON checklist_items
+    BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description, checklist)
+            SELECT 'delete', id, name, description, checklist
+            FROM tasks_search_content WHERE id = old.task_id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_delete AFTER DELETE ON checklist_items BEGIN
+        INSERT INTO tasks_search(rowid, name, description, checklist)
+            SELECT id, name, description, checklist FROM tasks_search_content WHERE id = old.task_id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_update_old
+    BEFORE UPDATE OF name, position, task_id ON checklist_items BEGIN
+        INSERT INTO tasks_search(tasks_search, rowid, name, description, checklist)
+            SELECT 'delete', id, name, description, checklist
+            FROM tasks_search_content WHERE id IN (old.task_id, new.task_id);
+    END;
+    CREATE TRIGGER IF NOT EXISTS checklist_search_update
+    AFTER UPDATE OF name, position, task_id ON checklist_items BEGIN
+        INSERT INTO tasks_search(rowid, name, description, checklist)
+            SELECT id, name, description, checklist
+            FROM tasks_search_content WHERE id IN (old.task_id, new.task_id);
+    END;
+    INSERT INTO tasks_search(tasks_search) VALUES ('rebuild');"#,
+    // board schemes are created at once and move their tasks then, since
+    // moving them level by level demoted them to the first level added.
+    // Tasks only take levels of their board scheme, as
+    // `Priority::scheme_condition` reads it, except while history replays
+    // an operation: levels and the tasks using them change back together.
+    r#"CREATE TABLE IF NOT EXISTS history_replay(history_id INTEGER PRIMARY KEY);
+    DROP TRIGGER IF EXISTS priorities_scheme_insert;
+    DROP TRIGGER IF EXISTS priorities_scheme_update;
+    DROP TRIGGER IF EXISTS tasks_priority_insert;
+    DROP TRIGGER IF EXISTS tasks_priority_update;
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_insert BEFORE INSERT ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (SELECT 1 FROM history_replay) AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id = new.board_id OR (p.board_id IS NULL
+            AND NOT EXISTS (SELECT 1 FROM priorities scheme WHERE scheme.board_id = new.board_id)))
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;
+    CREATE TRIGGER IF NOT EXISTS tasks_priority_update BEFORE UPDATE OF priority, board_id ON tasks
+    WHEN new.priority IS NOT NULL AND NOT EXISTS (SELECT 1 FROM history_replay) AND NOT EXISTS (
+        SELECT 1 FROM priorities p
+        WHERE p.id = new.priority AND (p.board_id = new.board_id OR (p.board_id IS NULL
+            AND NOT EXISTS (SELECT 1 FROM priorities scheme WHERE scheme.board_id = new.board_id)))
+    ) BEGIN
+        SELECT RAISE(ABORT, 'invalid priority');
+    END;"#,
+    // boards only take known progress strategies, `ProgressStrategy::name`.
+    r#"CREATE TRIGGER IF NOT EXISTS boards_progress_strategy_insert BEFORE INSERT ON boards
+    WHEN new.progress_strategy NOT IN ('Manual', 'Checklist', 'TimeLogged', 'Subtasks') BEGIN
+        SELECT RAISE(ABORT, 'invalid progress strategy');
+    END;
+    CREATE TRIGGER IF NOT EXISTS boards_progress_strategy_update
+    BEFORE UPDATE OF progress_strategy ON boards
+    WHEN new.progress_strategy NOT IN ('Manual', 'Checklist', 'TimeLogged', 'Subtasks') BEGIN
+        SELECT RAISE(ABORT, 'invalid progress strategy');
+    END;"#,
+];
+
+/// Get current database schema version.
+pub fn current_version(conn: &Connection) -> Result<usize> {
+    conn.query_row("PRAGMA user_version;", [], |row| row.get(0))
+}
+
+/// Get schema version reached once every migration is applied.
+pub fn latest_version() -> usize {
+    MIGRATIONS.len()
+}
+
+/// Get how many migrations are not applied yet.
+pub fn pending(conn: &Connection) -> Result<usize> {
+    Ok(latest_version().saturating_sub(current_version(conn)?))
+}
+
+/// Run all migrations not applied yet, each one in its own transaction.
+pub fn migrate(conn: &Connection) -> Result<()> {
+    let version = current_version(conn)?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = conn.unchecked_transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
@@ -1,3 +1,17 @@
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod dashboard;
+pub mod duplicate;
+pub mod exchange;
+pub mod filter;
 pub mod fixtures;
+pub mod history;
 pub mod manager;
+pub mod migrations;
 pub mod models;
+pub mod pagination;
+pub mod progress;
+pub mod reports;
+pub mod search;
+pub mod views;
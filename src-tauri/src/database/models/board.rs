@@ -2,13 +2,16 @@ use core::option::Option;
 use std::fmt::Debug;
 use std::rc::{Rc, Weak};
 
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 
+use crate::database::models::EntityKind;
 use crate::database::models::Group;
 use crate::database::models::ModelQueryBuilder;
 use crate::database::models::State;
 use crate::database::models::Task;
+use crate::database::models::WorkingTime;
+use crate::database::progress::ProgressStrategy;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Board {
@@ -21,6 +24,11 @@ pub struct Board {
     position: u32,
     #[serde(skip)]
     group: Weak<Group>,
+    /// hours per day and days per week used by estimates.
+    #[serde(flatten)]
+    working_time: WorkingTime,
+    #[serde(default)]
+    progress_strategy: ProgressStrategy,
 }
 
 impl Board {
@@ -32,6 +40,8 @@ impl Board {
             tasks: vec![],
             position,
             group,
+            working_time: WorkingTime::default(),
+            progress_strategy: ProgressStrategy::default(),
         }
     }
 
@@ -114,15 +124,95 @@ impl Board {
 
         return self;
     }
+
+    /// Get [`Board`] working time, used to convert estimates.
+    pub fn get_working_time(&self) -> WorkingTime {
+        self.working_time
+    }
+
+    /// Sets the working time of this [`Board`].
+    pub fn set_working_time(&mut self, working_time: WorkingTime) -> &mut Board {
+        self.working_time = working_time;
+
+        return self;
+    }
+
+    /// Get how progress of [`Board`] tasks is computed.
+    pub fn get_progress_strategy(&self) -> ProgressStrategy {
+        self.progress_strategy
+    }
+
+    /// Sets how progress of [`Board`] tasks is computed.
+    pub fn set_progress_strategy(&mut self, progress_strategy: ProgressStrategy) -> &mut Board {
+        self.progress_strategy = progress_strategy;
+
+        return self;
+    }
+
+    fn from_row(row: &Row) -> Result<Board> {
+        let mut board = Board::new(
+            row.get("id")?,
+            row.get("name")?,
+            Weak::new(),
+            row.get("position")?,
+        );
+        board
+            .set_working_time(WorkingTime {
+                hours_per_day: row.get("hours_per_day")?,
+                days_per_week: row.get("days_per_week")?,
+            })
+            .set_progress_strategy(ProgressStrategy::from_name(
+                &row.get::<_, String>("progress_strategy")?,
+            )?);
+
+        Ok(board)
+    }
+
+    /// Load not archived [`Board`] by id, without states and tasks.
+    pub fn load(conn: &Connection, id: i64) -> Result<Board> {
+        conn.query_row(
+            r#"SELECT id, name, position, hours_per_day, days_per_week, progress_strategy
+            FROM boards
+            WHERE id = ?1 AND archived_at IS NULL;"#,
+            params![id],
+            Board::from_row,
+        )
+    }
+
+    /// Load not archived [`Board`]s from a group, ordered by position.
+    pub fn load_by_group(conn: &Connection, group_id: i64) -> Result<Vec<Board>> {
+        let mut stmt = conn.prepare(
+            r#"SELECT id, name, position, hours_per_day, days_per_week, progress_strategy
+            FROM boards
+            WHERE group_id = ?1 AND archived_at IS NULL ORDER BY position;"#,
+        )?;
+        let boards = stmt
+            .query_map(params![group_id], Board::from_row)?
+            .collect::<Result<Vec<Board>>>()?;
+
+        Ok(boards)
+    }
 }
 
 impl ModelQueryBuilder for Board {
+    fn kind(&self) -> EntityKind {
+        EntityKind::Board
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
     fn insert_query(&self) -> &str {
-        r#"INSERT INTO boards(name, position, group_id) VALUES (?1, ?2, ?3);"#
+        r#"INSERT INTO boards(
+            name, position, group_id, hours_per_day, days_per_week, progress_strategy
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6);"#
     }
 
     fn update_query(&self) -> &str {
-        r#"UPDATE boards SET name = ?1,position = ?2, group_id = ?3 WHERE id = ?4;"#
+        r#"UPDATE boards SET name = ?1,position = ?2, group_id = ?3
+            , hours_per_day = ?4, days_per_week = ?5, progress_strategy = ?6
+        WHERE id = ?7;"#
     }
 
     fn delete_query(&self) -> &str {
@@ -133,7 +223,14 @@ impl ModelQueryBuilder for Board {
         let group = self.get_group().get_id();
         conn.execute(
             self.insert_query(),
-            params![self.get_name(), self.get_position(), group,],
+            params![
+                self.get_name(),
+                self.get_position(),
+                group,
+                self.working_time.hours_per_day,
+                self.working_time.days_per_week,
+                self.progress_strategy.name(),
+            ],
         )?;
 
         self.set_id(Some(conn.last_insert_rowid()));
@@ -148,6 +245,9 @@ impl ModelQueryBuilder for Board {
                 self.get_name(),
                 self.get_position(),
                 self.get_group().get_id(),
+                self.working_time.hours_per_day,
+                self.working_time.days_per_week,
+                self.progress_strategy.name(),
                 self.get_id().expect("Entity not persisted!"),
             ],
         )?;
@@ -0,0 +1,175 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::{EntityKind, ModelQueryBuilder};
+
+/// An item of a task checklist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    id: Option<i64>,
+    name: String,
+    #[serde(default)]
+    done: bool,
+    position: u32,
+    task_id: i64,
+}
+
+impl ChecklistItem {
+    pub fn new(name: String, task_id: i64, position: u32) -> ChecklistItem {
+        ChecklistItem {
+            id: None,
+            name,
+            done: false,
+            position,
+            task_id,
+        }
+    }
+
+    /// Get [`ChecklistItem`] identifier if exists.
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// Sets the id of this [`ChecklistItem`].
+    pub fn set_id(&mut self, id: Option<i64>) -> &mut Self {
+        self.id = id;
+
+        return self;
+    }
+
+    /// Get [`ChecklistItem`] name.
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Sets the name of this [`ChecklistItem`].
+    pub fn set_name(&mut self, name: String) -> &mut Self {
+        self.name = name;
+
+        return self;
+    }
+
+    /// Check if [`ChecklistItem`] is done.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Mark this [`ChecklistItem`] as done or not.
+    pub fn set_done(&mut self, done: bool) -> &mut Self {
+        self.done = done;
+
+        return self;
+    }
+
+    /// Get [`ChecklistItem`] position on checklist.
+    pub fn get_position(&self) -> u32 {
+        self.position
+    }
+
+    /// Sets new [`ChecklistItem`] position.
+    pub fn set_position(&mut self, position: u32) -> &mut Self {
+        self.position = position;
+
+        return self;
+    }
+
+    /// Get task owning this [`ChecklistItem`].
+    pub fn get_task_id(&self) -> i64 {
+        self.task_id
+    }
+
+    fn from_row(row: &Row) -> Result<ChecklistItem> {
+        Ok(ChecklistItem {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            done: row.get("done")?,
+            position: row.get("position")?,
+            task_id: row.get("task_id")?,
+        })
+    }
+
+    /// Load a [`ChecklistItem`] by id.
+    pub fn load(conn: &Connection, id: i64) -> Result<ChecklistItem> {
+        conn.query_row(
+            "SELECT * FROM checklist_items WHERE id = ?1;",
+            params![id],
+            ChecklistItem::from_row,
+        )
+    }
+
+    /// Load checklist of a task, ordered by position.
+    pub fn load_by_task(conn: &Connection, task_id: i64) -> Result<Vec<ChecklistItem>> {
+        let mut stmt = conn
+            .prepare("SELECT * FROM checklist_items WHERE task_id = ?1 ORDER BY position, id;")?;
+        let items = stmt
+            .query_map(params![task_id], ChecklistItem::from_row)?
+            .collect::<Result<Vec<ChecklistItem>>>()?;
+
+        Ok(items)
+    }
+}
+
+impl ModelQueryBuilder for ChecklistItem {
+    fn kind(&self) -> EntityKind {
+        EntityKind::ChecklistItem
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
+    fn insert_query(&self) -> &str {
+        r#"INSERT INTO checklist_items(name, done, position, task_id) VALUES (?1, ?2, ?3, ?4);"#
+    }
+
+    fn update_query(&self) -> &str {
+        r#"UPDATE checklist_items SET name = ?1, done = ?2, position = ?3, task_id = ?4
+        WHERE id = ?5;"#
+    }
+
+    fn delete_query(&self) -> &str {
+        r#"DELETE FROM checklist_items WHERE id = ?1;"#
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<bool> {
+        conn.execute(
+            self.insert_query(),
+            params![
+                self.get_name(),
+                self.is_done(),
+                self.get_position(),
+                self.get_task_id(),
+            ],
+        )?;
+        self.set_id(Some(conn.last_insert_rowid()));
+
+        Ok(true)
+    }
+
+    fn update(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Cannot update a checklist item not persisted before!");
+        let count = conn.execute(
+            self.update_query(),
+            params![
+                self.get_name(),
+                self.is_done(),
+                self.get_position(),
+                self.get_task_id(),
+                id,
+            ],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    fn delete(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Could not delete checklist item that is not persisted");
+        let count = conn.execute(self.delete_query(), params![id])?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Working time of a [`Board`](super::Board), used to convert days and
+/// weeks of estimates into minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkingTime {
+    pub hours_per_day: f32,
+    pub days_per_week: f32,
+}
+
+impl Default for WorkingTime {
+    fn default() -> Self {
+        WorkingTime {
+            hours_per_day: 8.0,
+            days_per_week: 5.0,
+        }
+    }
+}
+
+impl WorkingTime {
+    /// Minutes in a working day, at least one.
+    pub fn day_minutes(&self) -> u32 {
+        ((self.hours_per_day as f64 * 60.0).round() as u32).max(1)
+    }
+
+    /// Minutes in a working week, at least one.
+    pub fn week_minutes(&self) -> u32 {
+        ((self.day_minutes() as f64 * self.days_per_week as f64).round() as u32).max(1)
+    }
+}
+
+/// Duration estimate of a task, stored as minutes. Days and weeks are
+/// working ones, see [`WorkingTime`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DurationInput {
+    Minutes(u32),
+    HoursAndMinutes(u32, u32),
+    /// days, hours and minutes.
+    Days(u32, u32, u32),
+    /// weeks, days, hours and minutes.
+    Weeks(u32, u32, u32, u32),
+}
+
+impl DurationInput {
+    /// Minutes of duration, using default [`WorkingTime`].
+    pub fn value(&self) -> Result<u32, DurationError> {
+        self.value_in(&WorkingTime::default())
+    }
+
+    /// Minutes of duration, converting days and weeks with `time`. Fails
+    /// when there are more minutes than fit in a `u32`.
+    pub fn value_in(&self, time: &WorkingTime) -> Result<u32, DurationError> {
+        let (weeks, days, hours, minutes) = self.parts();
+
+        weeks
+            .checked_mul(time.week_minutes())
+            .zip(days.checked_mul(time.day_minutes()))
+            .zip(hours.checked_mul(60))
+            .and_then(|((weeks, days), hours)| weeks.checked_add(days)?.checked_add(hours))
+            .and_then(|total| total.checked_add(minutes))
+            .ok_or_else(|| DurationError::new(0, 0, String::from("duration too long")))
+    }
+
+    /// weeks, days, hours and minutes, as given.
+    fn parts(&self) -> (u32, u32, u32, u32) {
+        match *self {
+            Self::Minutes(minutes) => (0, 0, 0, minutes),
+            Self::HoursAndMinutes(hours, minutes) => (0, 0, hours, minutes),
+            Self::Days(days, hours, minutes) => (0, days, hours, minutes),
+            Self::Weeks(weeks, days, hours, minutes) => (weeks, days, hours, minutes),
+        }
+    }
+
+    pub fn from(value: u32) -> Self {
+        if value < 60 {
+            return Self::Minutes(value);
+        }
+
+        return Self::HoursAndMinutes(value / 60, value % 60);
+    }
+
+    /// Split `value` minutes using the largest unit it fills.
+    pub fn split(value: u32, time: &WorkingTime) -> Self {
+        let (week, day) = (time.week_minutes(), time.day_minutes());
+        let rest = value % week % day;
+
+        if value >= week {
+            return Self::Weeks(value / week, value % week / day, rest / 60, rest % 60);
+        }
+        if value >= day {
+            return Self::Days(value / day, rest / 60, rest % 60);
+        }
+
+        return Self::from(value);
+    }
+
+    /// Parse a duration like `2d 3h`, `1h30m`, `90m` or `1.5h`. Units are
+    /// `w`, `d`, `h` and `m`, values may have decimals and the total is
+    /// rounded to minutes.
+    pub fn parse(input: &str, time: &WorkingTime) -> Result<Self, DurationError> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut index = 0;
+        let mut total = 0.0;
+        let mut found = false;
+
+        loop {
+            while index < chars.len() && chars[index].is_whitespace() {
+                index += 1;
+            }
+            if index >= chars.len() {
+                break;
+            }
+
+            let start = index;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            let number: String = chars[start..index].iter().collect();
+            let value: f64 = number.parse().map_err(|_| {
+                DurationError::new(start, index - start, String::from("expected a number"))
+            })?;
+
+            let unit = match chars.get(index) {
+                Some('w') => time.week_minutes(),
+                Some('d') => time.day_minutes(),
+                Some('h') => 60,
+                Some('m') => 1,
+                Some(_) => return Err(DurationError::new(index, 1, String::from("unknown unit"))),
+                None => {
+                    return Err(DurationError::new(
+                        start,
+                        index - start,
+                        String::from("missing unit"),
+                    ))
+                }
+            };
+            index += 1;
+            total += value * unit as f64;
+            found = true;
+        }
+
+        if !found {
+            return Err(DurationError::new(0, 0, String::from("empty duration")));
+        }
+        if total.round() > u32::MAX as f64 {
+            return Err(DurationError::new(
+                0,
+                chars.len(),
+                String::from("duration too long"),
+            ));
+        }
+
+        Ok(Self::split(total.round() as u32, time))
+    }
+
+    /// Format as parsed by [`DurationInput::parse`], like `1w 2d 3h 30m`.
+    /// Durations too long to count in minutes keep their units as given.
+    pub fn format(&self, time: &WorkingTime) -> String {
+        let (weeks, days, hours, minutes) = self
+            .value_in(time)
+            .map_or(*self, |value| Self::split(value, time))
+            .parts();
+        let parts: Vec<String> = [(weeks, 'w'), (days, 'd'), (hours, 'h'), (minutes, 'm')]
+            .iter()
+            .filter(|(value, _)| *value > 0)
+            .map(|(value, unit)| format!("{}{}", value, unit))
+            .collect();
+
+        if parts.is_empty() {
+            return String::from("0m");
+        }
+
+        parts.join(" ")
+    }
+}
+
+/// Invalid duration text, `position` and `length` are counted in characters.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct DurationError {
+    pub position: usize,
+    pub length: usize,
+    pub message: String,
+}
+
+impl DurationError {
+    fn new(position: usize, length: usize, message: String) -> DurationError {
+        DurationError {
+            position,
+            length: length.max(1),
+            message,
+        }
+    }
+}
+
+impl Display for DurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for DurationError {}
+
+#[cfg(test)]
+mod duration_tests {
+    use super::{DurationInput, WorkingTime};
+
+    #[test]
+    fn test_from_and_value_round_trip() {
+        for value in [0, 1, 59, 60, 61, 90, 480, 2400, 100_000] {
+            assert_eq!(Ok(value), DurationInput::from(value).value());
+        }
+        for input in [
+            DurationInput::Minutes(45),
+            DurationInput::HoursAndMinutes(1, 30),
+            DurationInput::HoursAndMinutes(26, 0),
+        ] {
+            assert_eq!(input, DurationInput::from(input.value().unwrap()));
+        }
+
+        assert_eq!(Ok(8 * 60 + 90), DurationInput::Days(1, 1, 30).value());
+        assert_eq!(
+            DurationInput::HoursAndMinutes(51, 0),
+            DurationInput::from(DurationInput::Weeks(1, 1, 3, 0).value().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_split_follows_working_time() {
+        let time = WorkingTime {
+            hours_per_day: 7.5,
+            days_per_week: 4.0,
+        };
+
+        for input in [
+            DurationInput::Minutes(5),
+            DurationInput::HoursAndMinutes(7, 29),
+            DurationInput::Days(1, 0, 0),
+            DurationInput::Days(3, 7, 29),
+            DurationInput::Weeks(2, 1, 3, 15),
+        ] {
+            assert_eq!(
+                input,
+                DurationInput::split(input.value_in(&time).unwrap(), &time)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let time = WorkingTime::default();
+        let parse =
+            |input: &str| DurationInput::parse(input, &time).and_then(|d| d.value_in(&time));
+
+        assert_eq!(Ok(2 * 480 + 180), parse("2d 3h"));
+        assert_eq!(Ok(90), parse("90m"));
+        assert_eq!(Ok(90), parse("1.5h"));
+        assert_eq!(Ok(90), parse("1h30m"));
+        assert_eq!(Ok(2400 + 60), parse(" 1w 1h "));
+
+        assert_eq!(
+            "1w 2d 3h 30m",
+            DurationInput::Weeks(1, 2, 3, 30).format(&time)
+        );
+        assert_eq!("1h 30m", DurationInput::Minutes(90).format(&time));
+        assert_eq!("0m", DurationInput::Minutes(0).format(&time));
+        for text in ["2d 3h", "45m", "1w 4d 7h 59m"] {
+            let duration = DurationInput::parse(text, &time).unwrap();
+            assert_eq!(text, duration.format(&time));
+        }
+
+        let error = DurationInput::parse("2d 3x", &time).unwrap_err();
+        assert_eq!(
+            (4, "unknown unit"),
+            (error.position, error.message.as_str())
+        );
+        assert_eq!(3, DurationInput::parse("2d 3", &time).unwrap_err().position);
+        assert_eq!(0, DurationInput::parse("h", &time).unwrap_err().position);
+        assert!(DurationInput::parse("  ", &time).is_err());
+
+        // minutes must fit in a u32.
+        assert!(DurationInput::Weeks(u32::MAX, 0, 0, 1)
+            .value_in(&time)
+            .is_err());
+        assert!(DurationInput::Minutes(u32::MAX).value_in(&time).is_ok());
+        assert_eq!(
+            "9000000w",
+            DurationInput::Weeks(9_000_000, 0, 0, 0).format(&time)
+        );
+        assert!(DurationInput::parse("9000000w", &time).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// Kinds of persisted entities, used by actions that works over any model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntityKind {
+    Group,
+    Board,
+    State,
+    Task,
+    View,
+    Priority,
+    ChecklistItem,
+    TimeLog,
+    Milestone,
+}
+
+impl EntityKind {
+    /// Get table name where entity is stored.
+    pub fn table(&self) -> &'static str {
+        match self {
+            EntityKind::Group => "groups",
+            EntityKind::Board => "boards",
+            EntityKind::State => "states",
+            EntityKind::Task => "tasks",
+            EntityKind::View => "saved_views",
+            EntityKind::Priority => "priorities",
+            EntityKind::ChecklistItem => "checklist_items",
+            EntityKind::TimeLog => "time_logs",
+            EntityKind::Milestone => "milestones",
+        }
+    }
+
+    /// Get column that references the entity parent, used to order
+    /// entities by position. Groups, views, priorities and time logs
+    /// has no parent.
+    pub fn parent_column(&self) -> Option<&'static str> {
+        match self {
+            EntityKind::Group | EntityKind::View | EntityKind::Priority | EntityKind::TimeLog => {
+                None
+            }
+            EntityKind::Board => Some("group_id"),
+            EntityKind::State => Some("board_id"),
+            EntityKind::Task => Some("state_id"),
+            EntityKind::ChecklistItem => Some("task_id"),
+            EntityKind::Milestone => Some("board_id"),
+        }
+    }
+}
@@ -3,10 +3,11 @@ use std::fmt::Debug;
 use std::rc::Rc;
 
 use crate::database::models::Board;
+use crate::database::models::EntityKind;
 use crate::database::models::ModelQueryBuilder;
 use crate::database::models::Task;
 
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, Result, Row};
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -122,9 +123,42 @@ impl Group {
 
         return self;
     }
+
+    fn from_row(row: &Row) -> Result<Group> {
+        let mut group = Group::new(row.get("name")?, row.get("icon")?, row.get("position")?);
+        group.set_id(row.get("id")?);
+
+        Ok(group)
+    }
+
+    /// Load all not archived [`Group`]s, with its boards, ordered by position.
+    pub fn load_all(conn: &Connection) -> Result<Vec<Group>> {
+        let mut stmt = conn.prepare(
+            r#"SELECT id, name, icon, position FROM groups
+            WHERE archived_at IS NULL ORDER BY position;"#,
+        )?;
+        let mut groups = stmt
+            .query_map(params![], Group::from_row)?
+            .collect::<Result<Vec<Group>>>()?;
+
+        for group in groups.iter_mut() {
+            let boards = Board::load_by_group(conn, group.get_id().unwrap())?;
+            group.set_boards(boards.into_iter().map(Rc::new).collect());
+        }
+
+        Ok(groups)
+    }
 }
 
 impl ModelQueryBuilder for Group {
+    fn kind(&self) -> EntityKind {
+        EntityKind::Group
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
     fn insert_query(&self) -> &str {
         r#"INSERT INTO groups(name, icon, position) VALUES (?1, ?2, ?3);"#
     }
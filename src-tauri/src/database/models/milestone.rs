@@ -0,0 +1,176 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::{EntityKind, ModelQueryBuilder};
+
+/// A board milestone grouping tasks, like a release.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Milestone {
+    id: Option<i64>,
+    name: String,
+    #[serde(default)]
+    due_at: Option<String>,
+    position: u32,
+    board_id: i64,
+}
+
+impl Milestone {
+    pub fn new(name: String, board_id: i64, position: u32) -> Milestone {
+        Milestone {
+            id: None,
+            name,
+            due_at: None,
+            position,
+            board_id,
+        }
+    }
+
+    /// Get [`Milestone`] identifier if exists.
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// Sets the id of this [`Milestone`].
+    pub fn set_id(&mut self, id: Option<i64>) -> &mut Self {
+        self.id = id;
+
+        return self;
+    }
+
+    /// Get [`Milestone`] name.
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Sets the name of this [`Milestone`].
+    pub fn set_name(&mut self, name: String) -> &mut Self {
+        self.name = name;
+
+        return self;
+    }
+
+    /// Get [`Milestone`] due date if exists.
+    pub fn get_due_at(&self) -> &Option<String> {
+        &self.due_at
+    }
+
+    /// Sets the due date of this [`Milestone`].
+    pub fn set_due_at(&mut self, due_at: Option<String>) -> &mut Self {
+        self.due_at = due_at;
+
+        return self;
+    }
+
+    /// Get [`Milestone`] position on board.
+    pub fn get_position(&self) -> u32 {
+        self.position
+    }
+
+    /// Sets new [`Milestone`] position.
+    pub fn set_position(&mut self, position: u32) -> &mut Self {
+        self.position = position;
+
+        return self;
+    }
+
+    /// Get board owning this [`Milestone`].
+    pub fn get_board_id(&self) -> i64 {
+        self.board_id
+    }
+
+    fn from_row(row: &Row) -> Result<Milestone> {
+        Ok(Milestone {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            due_at: row.get("due_at")?,
+            position: row.get("position")?,
+            board_id: row.get("board_id")?,
+        })
+    }
+
+    /// Load a [`Milestone`] by id.
+    pub fn load(conn: &Connection, id: i64) -> Result<Milestone> {
+        conn.query_row(
+            "SELECT * FROM milestones WHERE id = ?1;",
+            params![id],
+            Milestone::from_row,
+        )
+    }
+
+    /// Load milestones of a board, ordered by position.
+    pub fn load_by_board(conn: &Connection, board_id: i64) -> Result<Vec<Milestone>> {
+        let mut stmt =
+            conn.prepare("SELECT * FROM milestones WHERE board_id = ?1 ORDER BY position, id;")?;
+        let milestones = stmt
+            .query_map(params![board_id], Milestone::from_row)?
+            .collect::<Result<Vec<Milestone>>>()?;
+
+        Ok(milestones)
+    }
+}
+
+impl ModelQueryBuilder for Milestone {
+    fn kind(&self) -> EntityKind {
+        EntityKind::Milestone
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
+    fn insert_query(&self) -> &str {
+        r#"INSERT INTO milestones(name, due_at, position, board_id) VALUES (?1, ?2, ?3, ?4);"#
+    }
+
+    fn update_query(&self) -> &str {
+        r#"UPDATE milestones SET name = ?1, due_at = ?2, position = ?3, board_id = ?4
+        WHERE id = ?5;"#
+    }
+
+    fn delete_query(&self) -> &str {
+        r#"DELETE FROM milestones WHERE id = ?1;"#
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<bool> {
+        conn.execute(
+            self.insert_query(),
+            params![
+                self.get_name(),
+                self.get_due_at(),
+                self.get_position(),
+                self.get_board_id(),
+            ],
+        )?;
+        self.set_id(Some(conn.last_insert_rowid()));
+
+        Ok(true)
+    }
+
+    fn update(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Cannot update a milestone not persisted before!");
+        let count = conn.execute(
+            self.update_query(),
+            params![
+                self.get_name(),
+                self.get_due_at(),
+                self.get_position(),
+                self.get_board_id(),
+                id,
+            ],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    /// Tasks of the milestone are kept, without milestone.
+    fn delete(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Could not delete milestone that is not persisted");
+        let count = conn.execute(self.delete_query(), params![id])?;
+
+        Ok(count > 0)
+    }
+}
@@ -1,19 +1,41 @@
 mod board;
+mod checklist_item;
+mod duration;
+mod entity_kind;
 mod group;
+mod milestone;
 mod priority;
+mod saved_view;
 mod state;
 mod task;
+mod task_summary;
+mod time_log;
 
 pub use board::Board;
+pub use checklist_item::ChecklistItem;
+pub use duration::{DurationError, DurationInput, WorkingTime};
+pub use entity_kind::EntityKind;
 pub use group::Group;
+pub use milestone::Milestone;
 pub use priority::Priority;
+pub use saved_view::{Grouping, SavedView};
 pub use state::State;
 pub use task::*;
+pub use task_summary::TaskSummary;
+pub use time_log::TimeLog;
 
 use rusqlite::{Connection, Result};
 
+use super::history::Target;
+
 /// trait for help to insert, update and delete data for each model.
 pub trait ModelQueryBuilder {
+    /// returns kind of entity handled by model.
+    fn kind(&self) -> EntityKind;
+
+    /// returns model identifier, if already persisted.
+    fn entity_id(&self) -> Option<i64>;
+
     /// returns model insert sql query.
     fn insert_query(&self) -> &str;
 
@@ -31,4 +53,10 @@ pub trait ModelQueryBuilder {
 
     /// runs model delete action.
     fn delete(&self, conn: &Connection) -> Result<bool>;
+
+    /// returns other rows that triggers change along with the model
+    /// insert or update, so history can restore them.
+    fn affected(&self, _conn: &Connection) -> Result<Vec<Target>> {
+        Ok(vec![])
+    }
 }
@@ -1,52 +1,449 @@
-use serde::{Deserialize,  Serialize};
-
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Priority {
-    Low,
-    Normal,
-    High,
-    Now,
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::history::Target;
+use crate::database::models::{EntityKind, ModelQueryBuilder};
+
+/// A priority level. Levels without board belong to the workspace
+/// scheme, used by every board that has no levels of its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Priority {
+    id: Option<i64>,
+    name: String,
+    color: Option<String>,
+    /// higher ranks are more urgent.
+    rank: i64,
+    icon: Option<String>,
+    #[serde(default)]
+    board_id: Option<i64>,
 }
 
 impl Priority {
-    pub fn code(&self) -> i32 {
-        return match self {
-            Priority::Low => 1,
-            Priority::Normal => 2,
-            Priority::High => 3,
-            Priority::Now => 4,
-        };
+    pub fn new(name: String, color: Option<String>, rank: i64, board_id: Option<i64>) -> Priority {
+        Priority {
+            id: None,
+            name,
+            color,
+            rank,
+            icon: None,
+            board_id,
+        }
+    }
+
+    /// Get [`Priority`] identifier if exists.
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// Sets the id of this [`Priority`].
+    pub fn set_id(&mut self, id: Option<i64>) -> &mut Self {
+        self.id = id;
+
+        return self;
+    }
+
+    /// Get [`Priority`] name.
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Sets the name of this [`Priority`].
+    pub fn set_name(&mut self, name: String) -> &mut Self {
+        self.name = name;
+
+        return self;
+    }
+
+    /// Get [`Priority`] color if exists.
+    pub fn get_color(&self) -> &Option<String> {
+        &self.color
+    }
+
+    /// Sets the color of this [`Priority`].
+    pub fn set_color(&mut self, color: Option<String>) -> &mut Self {
+        self.color = color;
+
+        return self;
+    }
+
+    /// Get [`Priority`] rank, higher is more urgent.
+    pub fn get_rank(&self) -> i64 {
+        self.rank
+    }
+
+    /// Sets the rank of this [`Priority`].
+    pub fn set_rank(&mut self, rank: i64) -> &mut Self {
+        self.rank = rank;
+
+        return self;
     }
 
-    pub fn new(code: i32) -> Priority {
-        return match code {
-            2 => Priority::Normal,
-            3 => Priority::High,
-            4 => Priority::Now,
-            _ => Priority::Low,
+    /// Get [`Priority`] icon if exists.
+    pub fn get_icon(&self) -> &Option<String> {
+        &self.icon
+    }
+
+    /// Sets the icon of this [`Priority`].
+    pub fn set_icon(&mut self, icon: Option<String>) -> &mut Self {
+        self.icon = icon;
+
+        return self;
+    }
+
+    /// Get board owning this [`Priority`], `None` for workspace levels.
+    pub fn get_board_id(&self) -> Option<i64> {
+        self.board_id
+    }
+
+    /// Sets the board owning this [`Priority`].
+    pub fn set_board_id(&mut self, board_id: Option<i64>) -> &mut Self {
+        self.board_id = board_id;
+
+        return self;
+    }
+
+    fn from_row(row: &Row) -> Result<Priority> {
+        Ok(Priority {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            color: row.get("color")?,
+            rank: row.get("rank")?,
+            icon: row.get("icon")?,
+            board_id: row.get("board_id")?,
+        })
+    }
+
+    /// Load a [`Priority`] by id.
+    pub fn load(conn: &Connection, id: i64) -> Result<Priority> {
+        conn.query_row(
+            "SELECT * FROM priorities WHERE id = ?1;",
+            params![id],
+            Priority::from_row,
+        )
+    }
+
+    /// SQL condition telling if priorities aliased as `alias` are in the
+    /// scheme used by board `board`, an SQL expression.
+    pub fn scheme_condition(alias: &str, board: &str) -> String {
+        format!(
+            r#"({0}.board_id = {1} OR ({0}.board_id IS NULL
+                AND NOT EXISTS (SELECT 1 FROM priorities scheme WHERE scheme.board_id = {1})))"#,
+            alias, board
+        )
+    }
+
+    /// Load priorities used by `board_id`, or the workspace ones when
+    /// missing, most urgent first.
+    pub fn load_scheme(conn: &Connection, board_id: Option<i64>) -> Result<Vec<Priority>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM priorities p WHERE {} ORDER BY rank DESC, id;",
+            Priority::scheme_condition("p", "?1")
+        ))?;
+        let priorities = stmt
+            .query_map(params![board_id], Priority::from_row)?
+            .collect::<Result<Vec<Priority>>>()?;
+
+        Ok(priorities)
+    }
+
+    /// Tasks of `board_id` on workspace levels, the ones
+    /// [`Priority::create_scheme`] moves.
+    pub fn workspace_tasks(conn: &Connection, board_id: i64) -> Result<Vec<Target>> {
+        let mut stmt = conn.prepare(
+            r#"SELECT t.id FROM tasks t JOIN priorities p ON p.id = t.priority
+            WHERE t.board_id = ?1 AND p.board_id IS NULL;"#,
+        )?;
+        let tasks = stmt
+            .query_map(params![board_id], |row| Ok((EntityKind::Task, row.get(0)?)))?
+            .collect::<Result<Vec<Target>>>()?;
+
+        Ok(tasks)
+    }
+
+    /// Give `board_id` a scheme of its own made of `levels`. Every level
+    /// is inserted first, then tasks of the board on workspace levels move
+    /// once to the board level of closest rank, the most urgent on ties.
+    pub fn create_scheme(
+        conn: &Connection,
+        board_id: i64,
+        levels: &mut [Priority],
+    ) -> Result<Vec<Target>> {
+        let has_scheme: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM priorities WHERE board_id = ?1);",
+            params![board_id],
+            |row| row.get(0),
+        )?;
+        if has_scheme || levels.is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "board {} already has a priority scheme, or no level was given",
+                board_id
+            )));
+        }
+
+        let mut created = vec![];
+        for level in levels.iter_mut() {
+            level.set_board_id(Some(board_id));
+            level.insert_row(conn)?;
+            created.push((EntityKind::Priority, level.get_id().unwrap()));
+        }
+        conn.execute(
+            r#"UPDATE tasks SET priority = (
+                SELECT p.id FROM priorities p JOIN priorities w ON w.id = tasks.priority
+                WHERE p.board_id = tasks.board_id
+                ORDER BY abs(p.rank - w.rank), p.rank DESC, p.id
+                LIMIT 1
+            ) WHERE board_id = ?1
+                AND priority IN (SELECT id FROM priorities WHERE board_id IS NULL);"#,
+            params![board_id],
+        )?;
+
+        Ok(created)
+    }
+
+    /// Fails when the level would start the scheme of a board whose tasks
+    /// use workspace levels: such schemes are made at once with
+    /// [`Priority::create_scheme`], so tasks are moved knowing every level.
+    fn check_scheme_start(&self, conn: &Connection) -> Result<()> {
+        let Some(board_id) = self.get_board_id() else {
+            return Ok(());
         };
+        let starts: bool = conn.query_row(
+            r#"SELECT NOT EXISTS (SELECT 1 FROM priorities WHERE board_id = ?1 AND id IS NOT ?2)
+            AND EXISTS (
+                SELECT 1 FROM tasks t JOIN priorities p ON p.id = t.priority
+                WHERE t.board_id = ?1 AND p.board_id IS NULL
+            );"#,
+            params![board_id, self.get_id()],
+            |row| row.get(0),
+        )?;
+        if starts {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "board {} tasks use workspace priorities, create its scheme at once",
+                board_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn insert_row(&mut self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            self.insert_query(),
+            params![
+                self.get_name(),
+                self.get_color(),
+                self.get_rank(),
+                self.get_icon(),
+                self.get_board_id(),
+            ],
+        )?;
+        self.set_id(Some(conn.last_insert_rowid()));
+
+        Ok(())
+    }
+}
+
+impl ModelQueryBuilder for Priority {
+    fn kind(&self) -> EntityKind {
+        EntityKind::Priority
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
+    fn insert_query(&self) -> &str {
+        r#"INSERT INTO priorities(name, color, rank, icon, board_id) VALUES (?1, ?2, ?3, ?4, ?5);"#
+    }
+
+    fn update_query(&self) -> &str {
+        r#"UPDATE priorities SET name = ?1, color = ?2, rank = ?3, icon = ?4, board_id = ?5
+        WHERE id = ?6;"#
+    }
+
+    fn delete_query(&self) -> &str {
+        r#"DELETE FROM priorities WHERE id = ?1;"#
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<bool> {
+        self.check_scheme_start(conn)?;
+        self.insert_row(conn)?;
+
+        Ok(true)
+    }
+
+    fn update(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Cannot update a priority not persisted before!");
+        self.check_scheme_start(conn)?;
+        let count = conn.execute(
+            self.update_query(),
+            params![
+                self.get_name(),
+                self.get_color(),
+                self.get_rank(),
+                self.get_icon(),
+                self.get_board_id(),
+                id,
+            ],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    /// Fails while tasks still use the priority.
+    fn delete(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Could not delete priority that is not persisted");
+        let count = conn.execute(self.delete_query(), params![id])?;
+
+        Ok(count > 0)
     }
 }
 
 #[cfg(test)]
 mod priority_tests {
+    use rusqlite::Connection;
+
     use super::Priority;
+    use crate::database::audit::Origin;
+    use crate::database::fixtures::create_tables;
+    use crate::database::history::{self, Action};
+    use crate::database::models::{EntityKind, ModelQueryBuilder};
+
+    #[test]
+    fn test_codes_are_migrated_to_workspace_scheme() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let names: Vec<(Option<i64>, String)> = Priority::load_scheme(&conn, Some(1))
+            .unwrap()
+            .into_iter()
+            .map(|priority| (priority.get_id(), priority.get_name().clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(4), String::from("Now")),
+                (Some(3), String::from("High")),
+                (Some(2), String::from("Normal")),
+                (Some(1), String::from("Low")),
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn test_board_scheme_and_invalid_codes() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let mut urgent = Priority::new(String::from("Urgent"), None, 10, Some(2));
+        urgent.insert(&conn).unwrap();
+        let urgent_id = urgent.get_id().unwrap();
+
+        assert_eq!(1, Priority::load_scheme(&conn, Some(2)).unwrap().len());
+        assert_eq!(4, Priority::load_scheme(&conn, Some(1)).unwrap().len());
+
+        let insert = |priority: i64, board: i64| {
+            conn.execute(
+                r#"INSERT INTO tasks(name, duration, priority, state_id, board_id, position)
+                VALUES ('task', 1, ?1, 1, ?2, 0);"#,
+                [priority, board],
+            )
+        };
+        assert!(insert(urgent_id, 2).is_ok());
+        assert!(insert(urgent_id, 1).is_err());
+        assert!(insert(5, 1).is_err());
+        assert!(insert(1, 1).is_ok());
+        assert!(insert(1, 2).is_err());
+        assert!(urgent.delete(&conn).is_err());
+    }
 
     #[test]
-    fn test_code() {
-        assert_eq!(1, Priority::Low.code());
-        assert_eq!(2, Priority::Normal.code());
-        assert_eq!(3, Priority::High.code());
-        assert_eq!(4, Priority::Now.code());
+    fn test_scheme_changes_keep_tasks_valid_and_undo() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            r#"INSERT INTO tasks(id, name, duration, priority, state_id, board_id, position)
+            VALUES (1, 'task', 1, 3, 1, 1, 0);"#,
+            [],
+        )
+        .unwrap();
+        let task_priority = || -> i64 {
+            conn.query_row("SELECT priority FROM tasks WHERE id = 1;", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+
+        let mut high = Priority::load(&conn, 3).unwrap();
+        high.set_name(String::from("Urgent"));
+        history::record(
+            &conn,
+            Origin::Gui,
+            Action::Update,
+            (EntityKind::Priority, Some(3)),
+            &[(EntityKind::Priority, 3)],
+            |conn| Ok((high.update(conn)?, vec![])),
+        )
+        .unwrap();
+        history::undo(&conn, Origin::Gui).unwrap().unwrap();
+        assert_eq!("High", Priority::load(&conn, 3).unwrap().get_name());
+
+        high.set_name(String::from("High")).set_board_id(Some(2));
+        assert!(high.update(&conn).is_err());
+
+        // a scheme started level by level would move tasks to its first one.
+        let mut minor = Priority::new(String::from("Minor"), None, 1, Some(1));
+        assert!(minor.insert(&conn).is_err());
+
+        let mut levels = vec![
+            Priority::new(String::from("Minor"), None, 1, None),
+            Priority::new(String::from("Major"), None, 3, None),
+            Priority::new(String::from("Critical"), None, 4, None),
+        ];
+        let touched = Priority::workspace_tasks(&conn, 1).unwrap();
+        history::record(
+            &conn,
+            Origin::Gui,
+            Action::Create,
+            (EntityKind::Priority, None),
+            &touched,
+            |conn| Ok(((), Priority::create_scheme(conn, 1, &mut levels)?)),
+        )
+        .unwrap();
+        // High keeps its rank on the board scheme.
+        assert_eq!(levels[1].get_id().unwrap(), task_priority());
+        assert!(Priority::create_scheme(&conn, 1, &mut [minor]).is_err());
+
+        history::undo(&conn, Origin::Gui).unwrap().unwrap();
+        assert_eq!(3, task_priority());
+        assert_eq!(4, Priority::load_scheme(&conn, Some(1)).unwrap().len());
     }
 
     #[test]
-    fn test_new() {
-        assert_eq!(Priority::Low, Priority::new(1));
-        assert_eq!(Priority::Normal, Priority::new(2));
-        assert_eq!(Priority::High, Priority::new(3));
-        assert_eq!(Priority::Now, Priority::new(4));
-        assert_eq!(Priority::Low, Priority::new(5));
-        assert_eq!(Priority::Low, Priority::new(10));
+    fn test_task_triggers_follow_scheme_condition() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let condition: String = Priority::scheme_condition("p", "new.board_id")
+            .split_whitespace()
+            .collect();
+
+        for name in ["tasks_priority_insert", "tasks_priority_update"] {
+            let sql: String = conn
+                .query_row(
+                    "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = ?1;",
+                    [name],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            let sql: String = sql.split_whitespace().collect();
+            assert!(
+                sql.contains(&condition),
+                "{} doesn't use the scheme condition",
+                name
+            );
+        }
     }
 }
@@ -0,0 +1,270 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::database::filter::Scope;
+use crate::database::models::{EntityKind, ModelQueryBuilder};
+use crate::database::pagination::SortKey;
+
+/// How tasks of a [`SavedView`] are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Grouping {
+    State,
+    Priority,
+    Board,
+    Label,
+}
+
+/// A named filter, like "all Now tasks" or "stale tasks in Review",
+/// that can be pinned to the sidebar.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedView {
+    id: Option<i64>,
+    name: String,
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    sort: SortKey,
+    #[serde(default)]
+    grouping: Option<Grouping>,
+    #[serde(default)]
+    scope: Scope,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    position: u32,
+}
+
+impl SavedView {
+    pub fn new(name: String, filter: String, position: u32) -> SavedView {
+        SavedView {
+            id: None,
+            name,
+            filter,
+            sort: SortKey::default(),
+            grouping: None,
+            scope: Scope::default(),
+            pinned: false,
+            position,
+        }
+    }
+
+    /// Get [`SavedView`] identifier if exists
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// Sets the id of this [`SavedView`].
+    pub fn set_id(&mut self, id: Option<i64>) -> &mut Self {
+        self.id = id;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] name.
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Sets the name of this [`SavedView`].
+    pub fn set_name(&mut self, name: String) -> &mut Self {
+        self.name = name;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] filter query.
+    pub fn get_filter(&self) -> &String {
+        &self.filter
+    }
+
+    /// Sets the filter query of this [`SavedView`].
+    pub fn set_filter(&mut self, filter: String) -> &mut Self {
+        self.filter = filter;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] tasks order.
+    pub fn get_sort(&self) -> SortKey {
+        self.sort
+    }
+
+    /// Sets the tasks order of this [`SavedView`].
+    pub fn set_sort(&mut self, sort: SortKey) -> &mut Self {
+        self.sort = sort;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] tasks grouping, if any.
+    pub fn get_grouping(&self) -> Option<Grouping> {
+        self.grouping
+    }
+
+    /// Sets the tasks grouping of this [`SavedView`].
+    pub fn set_grouping(&mut self, grouping: Option<Grouping>) -> &mut Self {
+        self.grouping = grouping;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] scope.
+    pub fn get_scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Sets the scope of this [`SavedView`].
+    pub fn set_scope(&mut self, scope: Scope) -> &mut Self {
+        self.scope = scope;
+
+        return self;
+    }
+
+    /// Check if [`SavedView`] is pinned to sidebar.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /// Pin or unpin this [`SavedView`] from sidebar.
+    pub fn set_pinned(&mut self, pinned: bool) -> &mut Self {
+        self.pinned = pinned;
+
+        return self;
+    }
+
+    /// Get [`SavedView`] position on list.
+    pub fn get_position(&self) -> u32 {
+        self.position
+    }
+
+    /// Sets new [`SavedView`] position.
+    pub fn set_position(&mut self, position: u32) -> &mut Self {
+        self.position = position;
+
+        return self;
+    }
+
+    fn from_row(row: &Row) -> Result<SavedView> {
+        Ok(SavedView {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            filter: row.get("filter")?,
+            sort: from_json(row.get("sort")?)?,
+            grouping: match row.get::<_, Option<String>>("grouping")? {
+                Some(grouping) => Some(from_json(grouping)?),
+                None => None,
+            },
+            scope: from_json(row.get("scope")?)?,
+            pinned: row.get("pinned")?,
+            position: row.get("position")?,
+        })
+    }
+
+    /// Load a not archived [`SavedView`] by id.
+    pub fn load(conn: &Connection, id: i64) -> Result<SavedView> {
+        conn.query_row(
+            "SELECT * FROM saved_views WHERE id = ?1 AND archived_at IS NULL;",
+            params![id],
+            SavedView::from_row,
+        )
+    }
+
+    /// Load not archived [`SavedView`]s ordered by position, optionally
+    /// only the ones pinned to sidebar.
+    pub fn load_all(conn: &Connection, pinned_only: bool) -> Result<Vec<SavedView>> {
+        let mut stmt = conn.prepare(
+            r#"SELECT * FROM saved_views
+            WHERE archived_at IS NULL AND (?1 = 0 OR pinned = 1)
+            ORDER BY position, id;"#,
+        )?;
+        let views = stmt
+            .query_map(params![pinned_only], SavedView::from_row)?
+            .collect::<Result<Vec<SavedView>>>()?;
+
+        Ok(views)
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn from_json<T: DeserializeOwned>(text: String) -> Result<T> {
+    serde_json::from_str(&text).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+impl ModelQueryBuilder for SavedView {
+    fn kind(&self) -> EntityKind {
+        EntityKind::View
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
+    fn insert_query(&self) -> &str {
+        r#"INSERT INTO saved_views(name, filter, sort, grouping, scope, pinned, position)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);"#
+    }
+
+    fn update_query(&self) -> &str {
+        r#"UPDATE saved_views SET name = ?1, filter = ?2, sort = ?3, grouping = ?4
+            , scope = ?5, pinned = ?6, position = ?7
+        WHERE id = ?8;"#
+    }
+
+    fn delete_query(&self) -> &str {
+        r#"DELETE FROM saved_views WHERE id = ?1;"#
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<bool> {
+        conn.execute(
+            self.insert_query(),
+            params![
+                self.get_name(),
+                self.get_filter(),
+                to_json(&self.get_sort()),
+                self.get_grouping().map(|grouping| to_json(&grouping)),
+                to_json(&self.get_scope()),
+                self.is_pinned(),
+                self.get_position(),
+            ],
+        )?;
+        self.set_id(Some(conn.last_insert_rowid()));
+
+        Ok(true)
+    }
+
+    fn update(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Cannot update a view not persisted before!");
+        let count = conn.execute(
+            self.update_query(),
+            params![
+                self.get_name(),
+                self.get_filter(),
+                to_json(&self.get_sort()),
+                self.get_grouping().map(|grouping| to_json(&grouping)),
+                to_json(&self.get_scope()),
+                self.is_pinned(),
+                self.get_position(),
+                id,
+            ],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    fn delete(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Could not delete view that is not persisted");
+        let count = conn.execute(self.delete_query(), params![id])?;
+
+        Ok(count > 0)
+    }
+}
@@ -3,10 +3,11 @@ use std::cell::RefCell;
 use std::fmt::Debug;
 use std::rc::{Rc, Weak};
 
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 
 use crate::database::models::Board;
+use crate::database::models::EntityKind;
 use crate::database::models::ModelQueryBuilder;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +16,10 @@ pub struct State {
     name: String,
     color: Option<String>,
     position: u32,
+    #[serde(default)]
+    active: bool,
+    #[serde(default)]
+    done: bool,
     board: Option<Weak<RefCell<Board>>>,
 }
 
@@ -31,6 +36,8 @@ impl State {
             name,
             color,
             position,
+            active: false,
+            done: false,
             board,
         }
     }
@@ -83,6 +90,30 @@ impl State {
         return self;
     }
 
+    /// Check if tasks in this [`State`] are being worked on.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Mark [`State`] as one where tasks are being worked on.
+    pub fn set_active(&mut self, active: bool) -> &mut State {
+        self.active = active;
+
+        return self;
+    }
+
+    /// Check if tasks in this [`State`] are finished.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Mark [`State`] as one where tasks are finished.
+    pub fn set_done(&mut self, done: bool) -> &mut State {
+        self.done = done;
+
+        return self;
+    }
+
     /// Get [`Board`] from [`State`]
     pub fn get_board(&self) -> Option<Rc<RefCell<Board>>> {
         match &self.board {
@@ -97,11 +128,48 @@ impl State {
 
         return self;
     }
+
+    fn from_row(row: &Row) -> Result<State> {
+        let mut state = State::new(
+            row.get("id")?,
+            row.get("name")?,
+            row.get("color")?,
+            None,
+            row.get("position")?,
+        );
+        state
+            .set_active(row.get("active")?)
+            .set_done(row.get("done")?);
+
+        Ok(state)
+    }
+
+    /// Load not archived [`State`]s from a board, ordered by position.
+    pub fn load_by_board(conn: &Connection, board_id: i64) -> Result<Vec<State>> {
+        let mut stmt = conn.prepare(
+            r#"SELECT id, name, color, position, active, done FROM states
+            WHERE board_id = ?1 AND archived_at IS NULL ORDER BY position;"#,
+        )?;
+        let states = stmt
+            .query_map(params![board_id], State::from_row)?
+            .collect::<Result<Vec<State>>>()?;
+
+        Ok(states)
+    }
 }
 
 impl ModelQueryBuilder for State {
+    fn kind(&self) -> EntityKind {
+        EntityKind::State
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
     fn insert_query(&self) -> &str {
-        r#"INSERT states(name, color, position, board_id) VALUES (?1, ?2, ?3, ?4);"#
+        r#"INSERT INTO states(name, color, position, board_id, active, done)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6);"#
     }
 
     fn insert(&mut self, conn: &Connection) -> Result<bool> {
@@ -115,6 +183,8 @@ impl ModelQueryBuilder for State {
                     .expect("Cannot create state without board")
                     .borrow()
                     .get_id(),
+                self.is_active(),
+                self.is_done(),
             ],
         )?;
         self.set_id(Some(conn.last_insert_rowid()));
@@ -123,7 +193,8 @@ impl ModelQueryBuilder for State {
     }
 
     fn update_query(&self) -> &str {
-        r#"UPDATE states SET name = ?1, color = ?2, position = ?3 WHERE id = ?4;"#
+        r#"UPDATE states SET name = ?1, color = ?2, position = ?3, active = ?4, done = ?5
+        WHERE id = ?6;"#
     }
 
     fn update(&self, conn: &Connection) -> Result<bool> {
@@ -133,6 +204,8 @@ impl ModelQueryBuilder for State {
                 self.get_name(),
                 self.get_color(),
                 self.get_position(),
+                self.is_active(),
+                self.is_done(),
                 self.get_id()
             ],
         )?;
@@ -141,7 +214,7 @@ impl ModelQueryBuilder for State {
     }
 
     fn delete_query(&self) -> &str {
-        r#"DELETE * FROM states WHERE id = ?1;"#
+        r#"DELETE FROM states WHERE id = ?1;"#
     }
 
     fn delete(&self, conn: &Connection) -> Result<bool> {
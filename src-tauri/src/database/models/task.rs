@@ -1,58 +1,53 @@
 use core::option::Option;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::rc::Rc;
 
 use crate::database::models::Board;
+use crate::database::models::DurationError;
+use crate::database::models::DurationInput;
+use crate::database::models::EntityKind;
 use crate::database::models::ModelQueryBuilder;
-use crate::database::models::Priority;
 use crate::database::models::State;
-use rusqlite::{params, Connection, Result};
+use crate::database::models::WorkingTime;
+use rusqlite::{params, Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum DurationInput {
-    Minutes(u32),
-    HoursAndMinutes(u32, u32),
-}
-
-impl DurationInput {
-    pub fn value(&self) -> u32 {
-        match self {
-            Self::Minutes(val) => *val,
-            Self::HoursAndMinutes(hours, minutes) => *hours * 60 + *minutes,
-        }
-    }
-
-    pub fn from(value: u32) -> Self {
-        if value < 60 {
-            return Self::Minutes(value);
-        }
-
-        return Self::HoursAndMinutes(value / 60, value % 60);
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Task {
     id: Option<i64>,
     name: String,
     description: Option<String>,
-    duration: u32,
+    /// estimate in minutes.
+    #[serde(default)]
+    duration: Option<u32>,
+    #[serde(default)]
+    story_points: Option<f32>,
     progress: Option<f32>,
-    priority: Priority,
+    /// id of task [`Priority`](super::Priority).
+    priority_id: Option<i64>,
     state: Rc<State>,
     board: Option<Rc<Board>>,
     position: u32,
     started_at: Option<String>,
     ended_at: Option<String>,
+    #[serde(default)]
+    due_at: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    /// task this one is a subtask of.
+    #[serde(default)]
+    parent_id: Option<i64>,
+    #[serde(default)]
+    milestone_id: Option<i64>,
 }
 
 impl Task {
     pub fn new(
         name: String,
         description: Option<String>,
-        duration: u32,
-        priority: Priority,
+        duration: Option<u32>,
+        priority_id: Option<i64>,
         state: Rc<State>,
         board: Option<Rc<Board>>,
         position: u32,
@@ -62,13 +57,18 @@ impl Task {
             name,
             description,
             duration,
+            story_points: None,
             progress: None,
-            priority,
+            priority_id,
             state,
             board,
             position,
             started_at: None,
             ended_at: None,
+            due_at: None,
+            labels: vec![],
+            parent_id: None,
+            milestone_id: None,
         };
     }
 
@@ -108,19 +108,49 @@ impl Task {
         return self;
     }
 
-    /// get duration from [`Task`] and returns
-    /// tuple with duration splited in (hours, minutes).
-    pub fn get_duration(&self) -> DurationInput {
-        return DurationInput::from(self.duration);
+    /// get duration estimate from [`Task`], if any, splited
+    /// using its board working time.
+    pub fn get_duration(&self) -> Option<DurationInput> {
+        let time = self.working_time();
+
+        self.duration
+            .map(|duration| DurationInput::split(duration, &time))
+    }
+
+    /// Sets the duration estimate of this [`Task`], failing when it has
+    /// too many minutes to store.
+    pub fn set_duration(
+        &mut self,
+        duration: Option<DurationInput>,
+    ) -> Result<&mut Self, DurationError> {
+        let time = self.working_time();
+        self.duration = duration
+            .map(|duration| duration.value_in(&time))
+            .transpose()?;
+
+        Ok(self)
+    }
+
+    /// get story points estimate from [`Task`], if any.
+    pub fn get_story_points(&self) -> Option<f32> {
+        self.story_points
     }
 
-    /// Sets the duration of this [`Task`].
-    pub fn set_duration(&mut self, duration: DurationInput) -> &mut Self {
-        self.duration = duration.value();
+    /// Sets the story points estimate of this [`Task`].
+    pub fn set_story_points(&mut self, story_points: Option<f32>) -> &mut Self {
+        self.story_points = story_points;
 
         return self;
     }
 
+    /// working time of [`Task`] board, default one when board is unknown.
+    fn working_time(&self) -> WorkingTime {
+        self.board
+            .as_ref()
+            .map(|board| board.get_working_time())
+            .unwrap_or_default()
+    }
+
     /// obtains current [`Task`] progress.
     pub fn get_progress(&self) -> Option<f32> {
         self.progress
@@ -133,19 +163,14 @@ impl Task {
         return self;
     }
 
-    /// obtains current [`Task`] priority.
-    pub fn get_priority(&self) -> Priority {
-        match self.priority {
-            Priority::High => Priority::High,
-            Priority::Normal => Priority::Normal,
-            Priority::Low => Priority::Low,
-            Priority::Now => Priority::Now,
-        }
+    /// obtains current [`Task`] priority id.
+    pub fn get_priority_id(&self) -> Option<i64> {
+        self.priority_id
     }
 
-    /// update current [`Task`] priority.
-    pub fn set_priority(&mut self, priority: Priority) -> &mut Self {
-        self.priority = priority;
+    /// update current [`Task`] priority id.
+    pub fn set_priority_id(&mut self, priority_id: Option<i64>) -> &mut Self {
+        self.priority_id = priority_id;
 
         return self;
     }
@@ -224,26 +249,165 @@ impl Task {
 
         return self;
     }
+
+    /// obtains [`Task`] due date.
+    pub fn get_due_at(&self) -> &Option<String> {
+        &self.due_at
+    }
+
+    /// update [`Task`] due date.
+    pub fn set_due_at(&mut self, due_at: Option<String>) -> &mut Self {
+        self.due_at = due_at;
+
+        return self;
+    }
+
+    /// obtains [`Task`] labels.
+    pub fn get_labels(&self) -> &Vec<String> {
+        &self.labels
+    }
+
+    /// update [`Task`] labels.
+    pub fn set_labels(&mut self, labels: Vec<String>) -> &mut Self {
+        self.labels = labels;
+
+        return self;
+    }
+
+    /// obtains parent of [`Task`], when it is a subtask.
+    pub fn get_parent_id(&self) -> Option<i64> {
+        self.parent_id
+    }
+
+    /// update parent of [`Task`], making it a subtask.
+    pub fn set_parent_id(&mut self, parent_id: Option<i64>) -> &mut Self {
+        self.parent_id = parent_id;
+
+        return self;
+    }
+
+    /// obtains [`Task`] milestone.
+    pub fn get_milestone_id(&self) -> Option<i64> {
+        self.milestone_id
+    }
+
+    /// update [`Task`] milestone.
+    pub fn set_milestone_id(&mut self, milestone_id: Option<i64>) -> &mut Self {
+        self.milestone_id = milestone_id;
+
+        return self;
+    }
+
+    /// [`Task`] labels encoded as stored in database.
+    fn labels_json(&self) -> String {
+        serde_json::to_string(&self.labels).unwrap_or_else(|_| String::from("[]"))
+    }
+
+    fn from_row(row: &Row, state: Rc<State>, board: Rc<Board>) -> Result<Task> {
+        Ok(Task {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            duration: row.get("duration")?,
+            story_points: row.get("story_points")?,
+            progress: row.get("progress")?,
+            priority_id: row.get("priority")?,
+            state,
+            board: Some(board),
+            position: row.get("position")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            due_at: row.get("due_at")?,
+            labels: parse_labels(row.get("labels")?),
+            parent_id: row.get("parent_id")?,
+            milestone_id: row.get("milestone_id")?,
+        })
+    }
+
+    /// Move [`Task`] `id` to `position` inside state `state_id`, pushing
+    /// down tasks already at or after that position.
+    pub fn move_to(conn: &Connection, id: i64, state_id: i64, position: u32) -> Result<bool> {
+        conn.execute(
+            r#"UPDATE tasks SET position = position + 1
+            WHERE state_id = ?1 AND position >= ?2 AND id <> ?3 AND archived_at IS NULL;"#,
+            params![state_id, position, id],
+        )?;
+        let count = conn.execute(
+            r#"UPDATE tasks SET state_id = ?1, position = ?2
+                , board_id = (SELECT board_id FROM states WHERE id = ?1)
+            WHERE id = ?3;"#,
+            params![state_id, position, id],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    /// Load not archived [`Task`]s from a board, skipping the ones
+    /// inside archived states. Tasks are ordered by state and position.
+    pub fn load_by_board(conn: &Connection, board_id: i64) -> Result<Vec<Task>> {
+        let board = Rc::new(Board::load(conn, board_id)?);
+        let states: HashMap<i64, Rc<State>> = State::load_by_board(conn, board_id)?
+            .into_iter()
+            .map(|state| (state.get_id().unwrap(), Rc::new(state)))
+            .collect();
+
+        let mut stmt = conn.prepare(
+            r#"SELECT t.id, t.name, t.description, t.duration, t.story_points, t.progress
+                , t.priority, t.state_id, t.position, t.started_at, t.ended_at, t.due_at, t.labels
+                , t.parent_id, t.milestone_id
+            FROM tasks t JOIN states s ON s.id = t.state_id
+            WHERE t.board_id = ?1 AND t.archived_at IS NULL AND s.archived_at IS NULL
+            ORDER BY s.position, t.position;"#,
+        )?;
+        let mut rows = stmt.query(params![board_id])?;
+        let mut tasks = vec![];
+
+        while let Some(row) = rows.next()? {
+            let Some(state) = states.get(&row.get("state_id")?) else {
+                continue;
+            };
+            tasks.push(Task::from_row(row, Rc::clone(state), Rc::clone(&board))?);
+        }
+
+        Ok(tasks)
+    }
+}
+
+/// Decode labels stored as a json array, ignoring malformed values.
+pub fn parse_labels(labels: Option<String>) -> Vec<String> {
+    labels
+        .and_then(|labels| serde_json::from_str(&labels).ok())
+        .unwrap_or_default()
 }
 
 impl ModelQueryBuilder for Task {
+    fn kind(&self) -> EntityKind {
+        EntityKind::Task
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
     fn insert_query(&self) -> &str {
-        r#"INSERT states(
-            name, description, duration, priority
-            , state_id, board_id, position
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);"#
+        r#"INSERT INTO tasks(
+            name, description, duration, story_points, priority
+            , state_id, board_id, position, due_at, labels
+            , parent_id, milestone_id
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12);"#
     }
 
     fn update_query(&self) -> &str {
-        r#"UPDATE states SET
-            name = ?1, description = ?2, duration = ?3, progress = ?4
-            , priority = ?5, state_id = ?6, board_id = ?7, position = ?8
-            , started_at = ?9, ended_at = ?10
-        WHERE id = ?11;"#
+        r#"UPDATE tasks SET
+            name = ?1, description = ?2, duration = ?3, story_points = ?4
+            , progress = ?5, priority = ?6, state_id = ?7, board_id = ?8, position = ?9
+            , started_at = ?10, ended_at = ?11, due_at = ?12, labels = ?13
+            , parent_id = ?14, milestone_id = ?15
+        WHERE id = ?16;"#
     }
 
     fn delete_query(&self) -> &str {
-        r#"DELETE * FROM states WHERE id = ?1;"#
+        r#"DELETE FROM tasks WHERE id = ?1;"#
     }
 
     fn insert(&mut self, conn: &Connection) -> Result<bool> {
@@ -262,11 +426,16 @@ impl ModelQueryBuilder for Task {
             params![
                 self.get_name(),
                 self.get_description(),
-                self.get_duration().value(),
-                self.get_priority().code(),
+                self.duration,
+                self.get_story_points(),
+                self.get_priority_id(),
                 state,
                 board,
-                self.get_position()
+                self.get_position(),
+                self.get_due_at(),
+                self.labels_json(),
+                self.get_parent_id(),
+                self.get_milestone_id(),
             ],
         )?;
 
@@ -293,14 +462,19 @@ impl ModelQueryBuilder for Task {
             params![
                 self.get_name(),
                 self.get_description(),
-                self.get_duration().value(),
+                self.duration,
+                self.get_story_points(),
                 self.get_progress(),
-                self.get_priority().code(),
+                self.get_priority_id(),
                 state,
                 board,
                 self.get_position(),
                 self.get_started_at(),
                 self.get_ended_at(),
+                self.get_due_at(),
+                self.labels_json(),
+                self.get_parent_id(),
+                self.get_milestone_id(),
                 id
             ],
         )?;
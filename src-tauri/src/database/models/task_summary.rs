@@ -0,0 +1,152 @@
+use rusqlite::types::Value;
+use rusqlite::{Connection, Result, Row};
+use serde::Serialize;
+
+use crate::database::models::parse_labels;
+use crate::database::pagination::{query_page, Page, PageRequest};
+
+/// Read only view of a [`Task`](super::Task) with its state, board
+/// and group, used by lists that cross boards.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskSummary {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// estimate in minutes.
+    pub duration: Option<u32>,
+    pub story_points: Option<f32>,
+    /// computed progress, from 0.0 to 1.0.
+    pub progress: Option<f32>,
+    pub priority_id: Option<i64>,
+    pub priority_name: Option<String>,
+    pub priority_color: Option<String>,
+    /// higher ranks are more urgent.
+    pub priority_rank: Option<i64>,
+    pub position: u32,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub due_at: Option<String>,
+    pub labels: Vec<String>,
+    pub parent_id: Option<i64>,
+    pub milestone_id: Option<i64>,
+    /// tool the task was imported from, like `GitHub` or `Jira`.
+    pub external_source: Option<String>,
+    /// task id in `external_source`.
+    pub external_id: Option<String>,
+    pub external_url: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub state_id: i64,
+    pub state_name: String,
+    pub state_active: bool,
+    pub state_done: bool,
+    pub board_id: i64,
+    pub board_name: String,
+    pub group_id: i64,
+    pub group_name: String,
+}
+
+impl TaskSummary {
+    /// Query selecting summaries, tables are aliased as `t`, `s`, `b`, `g` and `p`.
+    /// Parents positions are selected too, so pages can be sorted like boards.
+    /// Progress follows the board [`ProgressStrategy`](crate::database::progress::ProgressStrategy):
+    /// tasks in done states are complete, the others fall back to their
+    /// manual progress when the strategy has nothing to compute from.
+    pub const SELECT: &'static str = r#"SELECT t.id, t.name, t.description, t.duration
+            , t.story_points, t.priority AS priority_id, t.position, t.started_at, t.ended_at
+            , t.due_at, t.labels, t.created_at, t.updated_at, t.parent_id, t.milestone_id
+            , t.external_source, t.external_id, t.external_url
+            , CASE WHEN s.done THEN 1.0 ELSE MIN(1.0, MAX(0.0, COALESCE(
+                CASE b.progress_strategy
+                    WHEN 'Checklist' THEN (
+                        SELECT AVG(c.done) FROM checklist_items c WHERE c.task_id = t.id
+                    )
+                    WHEN 'TimeLogged' THEN (
+                        SELECT SUM(l.minutes) FROM time_logs l WHERE l.task_id = t.id
+                    ) * 1.0 / NULLIF(t.duration, 0)
+                    WHEN 'Subtasks' THEN (
+                        SELECT COALESCE(
+                            SUM(COALESCE(sub.duration, 0) * sub.progress)
+                                / NULLIF(SUM(COALESCE(sub.duration, 0)), 0)
+                            , AVG(sub.progress)
+                        ) FROM (
+                            SELECT st.duration
+                                , CASE WHEN ss.done THEN 1.0 ELSE COALESCE(st.progress, 0) END
+                                    AS progress
+                            FROM tasks st JOIN states ss ON ss.id = st.state_id
+                            WHERE st.parent_id = t.id AND st.archived_at IS NULL
+                        ) sub
+                    )
+                END
+                , t.progress
+            ))) END AS progress
+            , p.name AS priority_name, p.color AS priority_color, p.rank AS priority_rank
+            , s.id AS state_id, s.name AS state_name
+            , s.active AS state_active, s.done AS state_done
+            , s.position AS state_position
+            , b.id AS board_id, b.name AS board_name, b.position AS board_position
+            , g.id AS group_id, g.name AS group_name, g.position AS group_position
+        FROM tasks t
+            JOIN states s ON s.id = t.state_id
+            JOIN boards b ON b.id = t.board_id
+            JOIN groups g ON g.id = b.group_id
+            LEFT JOIN priorities p ON p.id = t.priority"#;
+
+    /// Condition filtering out archived tasks and tasks inside archived entities.
+    pub const NOT_ARCHIVED: &'static str = r#"t.archived_at IS NULL AND s.archived_at IS NULL
+        AND b.archived_at IS NULL AND g.archived_at IS NULL"#;
+
+    pub fn from_row(row: &Row) -> Result<TaskSummary> {
+        Ok(TaskSummary {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            duration: row.get("duration")?,
+            story_points: row.get("story_points")?,
+            progress: row.get("progress")?,
+            priority_id: row.get("priority_id")?,
+            priority_name: row.get("priority_name")?,
+            priority_color: row.get("priority_color")?,
+            priority_rank: row.get("priority_rank")?,
+            position: row.get("position")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            due_at: row.get("due_at")?,
+            labels: parse_labels(row.get("labels")?),
+            parent_id: row.get("parent_id")?,
+            milestone_id: row.get("milestone_id")?,
+            external_source: row.get("external_source")?,
+            external_id: row.get("external_id")?,
+            external_url: row.get("external_url")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+            state_id: row.get("state_id")?,
+            state_name: row.get("state_name")?,
+            state_active: row.get("state_active")?,
+            state_done: row.get("state_done")?,
+            board_id: row.get("board_id")?,
+            board_name: row.get("board_name")?,
+            group_id: row.get("group_id")?,
+            group_name: row.get("group_name")?,
+        })
+    }
+
+    /// Load a page of not archived tasks inside state `state_id`.
+    pub fn load_by_state(
+        conn: &Connection,
+        state_id: i64,
+        request: &PageRequest,
+    ) -> Result<Page<TaskSummary>> {
+        query_page(
+            conn,
+            &format!(
+                "{} WHERE t.state_id = ? AND {}",
+                TaskSummary::SELECT,
+                TaskSummary::NOT_ARCHIVED
+            ),
+            vec![Value::Integer(state_id)],
+            request,
+            TaskSummary::from_row,
+        )
+    }
+}
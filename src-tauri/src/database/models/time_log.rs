@@ -0,0 +1,179 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::{EntityKind, ModelQueryBuilder};
+
+/// Time spent working on a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeLog {
+    id: Option<i64>,
+    minutes: u32,
+    #[serde(default)]
+    note: Option<String>,
+    /// when work was logged, now when missing.
+    #[serde(default)]
+    logged_at: Option<String>,
+    task_id: i64,
+}
+
+impl TimeLog {
+    pub fn new(minutes: u32, task_id: i64) -> TimeLog {
+        TimeLog {
+            id: None,
+            minutes,
+            note: None,
+            logged_at: None,
+            task_id,
+        }
+    }
+
+    /// Get [`TimeLog`] identifier if exists.
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    /// Sets the id of this [`TimeLog`].
+    pub fn set_id(&mut self, id: Option<i64>) -> &mut Self {
+        self.id = id;
+
+        return self;
+    }
+
+    /// Get [`TimeLog`] minutes spent.
+    pub fn get_minutes(&self) -> u32 {
+        self.minutes
+    }
+
+    /// Sets the minutes spent of this [`TimeLog`].
+    pub fn set_minutes(&mut self, minutes: u32) -> &mut Self {
+        self.minutes = minutes;
+
+        return self;
+    }
+
+    /// Get [`TimeLog`] note if exists.
+    pub fn get_note(&self) -> &Option<String> {
+        &self.note
+    }
+
+    /// Sets the note of this [`TimeLog`].
+    pub fn set_note(&mut self, note: Option<String>) -> &mut Self {
+        self.note = note;
+
+        return self;
+    }
+
+    /// Get when [`TimeLog`] work was logged.
+    pub fn get_logged_at(&self) -> &Option<String> {
+        &self.logged_at
+    }
+
+    /// Sets when [`TimeLog`] work was logged.
+    pub fn set_logged_at(&mut self, logged_at: Option<String>) -> &mut Self {
+        self.logged_at = logged_at;
+
+        return self;
+    }
+
+    /// Get task of this [`TimeLog`].
+    pub fn get_task_id(&self) -> i64 {
+        self.task_id
+    }
+
+    fn from_row(row: &Row) -> Result<TimeLog> {
+        Ok(TimeLog {
+            id: row.get("id")?,
+            minutes: row.get("minutes")?,
+            note: row.get("note")?,
+            logged_at: row.get("logged_at")?,
+            task_id: row.get("task_id")?,
+        })
+    }
+
+    /// Load a [`TimeLog`] by id.
+    pub fn load(conn: &Connection, id: i64) -> Result<TimeLog> {
+        conn.query_row(
+            "SELECT * FROM time_logs WHERE id = ?1;",
+            params![id],
+            TimeLog::from_row,
+        )
+    }
+
+    /// Load time logged on a task, oldest first.
+    pub fn load_by_task(conn: &Connection, task_id: i64) -> Result<Vec<TimeLog>> {
+        let mut stmt =
+            conn.prepare("SELECT * FROM time_logs WHERE task_id = ?1 ORDER BY logged_at, id;")?;
+        let logs = stmt
+            .query_map(params![task_id], TimeLog::from_row)?
+            .collect::<Result<Vec<TimeLog>>>()?;
+
+        Ok(logs)
+    }
+}
+
+impl ModelQueryBuilder for TimeLog {
+    fn kind(&self) -> EntityKind {
+        EntityKind::TimeLog
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        self.get_id()
+    }
+
+    fn insert_query(&self) -> &str {
+        r#"INSERT INTO time_logs(minutes, note, logged_at, task_id)
+        VALUES (?1, ?2, COALESCE(?3, CURRENT_TIMESTAMP), ?4);"#
+    }
+
+    fn update_query(&self) -> &str {
+        r#"UPDATE time_logs SET minutes = ?1, note = ?2
+            , logged_at = COALESCE(?3, logged_at), task_id = ?4
+        WHERE id = ?5;"#
+    }
+
+    fn delete_query(&self) -> &str {
+        r#"DELETE FROM time_logs WHERE id = ?1;"#
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<bool> {
+        conn.execute(
+            self.insert_query(),
+            params![
+                self.get_minutes(),
+                self.get_note(),
+                self.get_logged_at(),
+                self.get_task_id(),
+            ],
+        )?;
+        self.set_id(Some(conn.last_insert_rowid()));
+
+        Ok(true)
+    }
+
+    fn update(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Cannot update a time log not persisted before!");
+        let count = conn.execute(
+            self.update_query(),
+            params![
+                self.get_minutes(),
+                self.get_note(),
+                self.get_logged_at(),
+                self.get_task_id(),
+                id,
+            ],
+        )?;
+
+        Ok(count > 0)
+    }
+
+    fn delete(&self, conn: &Connection) -> Result<bool> {
+        let id = self
+            .get_id()
+            .expect("Could not delete time log that is not persisted");
+        let count = conn.execute(self.delete_query(), params![id])?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,325 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::models::{State, TaskSummary};
+
+/// Default number of items per page.
+pub const DEFAULT_LIMIT: u32 = 50;
+
+/// Order of listed tasks. Ties are broken by id, so pages are stable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKey {
+    /// same order of boards, by group, board, state and task positions.
+    #[default]
+    Position,
+    /// most urgent priority first.
+    Priority,
+    Created,
+    /// closest due date first, tasks without due date last.
+    Due,
+    Updated,
+    Name,
+}
+
+impl SortKey {
+    /// Expressions over the columns of [`TaskSummary::SELECT`], id is always the last one.
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            SortKey::Position => &[
+                "group_position",
+                "board_position",
+                "state_position",
+                "position",
+                "id",
+            ],
+            SortKey::Priority => &["-COALESCE(priority_rank, 0)", "id"],
+            SortKey::Created => &["COALESCE(created_at, '')", "id"],
+            SortKey::Due => &["COALESCE(due_at, '9999-12-31')", "id"],
+            SortKey::Updated => &["COALESCE(updated_at, '')", "id"],
+            SortKey::Name => &["name COLLATE NOCASE", "id"],
+        }
+    }
+}
+
+/// Sort and page asked by a list command. Without `limit` every
+/// remaining item is returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PageRequest {
+    pub sort: SortKey,
+    pub descending: bool,
+    pub limit: Option<u32>,
+    /// cursor returned with previous page.
+    pub cursor: Option<String>,
+}
+
+/// A page of items with the cursor of the next one, if there are more items.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Tasks of a board state. Done states are loaded a page at a time.
+#[derive(Debug, Serialize)]
+pub struct StateTasks {
+    state_id: i64,
+    done: bool,
+    #[serde(flatten)]
+    tasks: Page<TaskSummary>,
+}
+
+/// Content of a cursor, it must match the request sort to be used.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: SortKey,
+    descending: bool,
+    after: Vec<Value>,
+}
+
+/// Run `query` sorted and paged by `request`. Sort expressions are
+/// evaluated over the query columns, so it must select every column
+/// used by the sort key.
+pub fn query_page<T, F>(
+    conn: &Connection,
+    query: &str,
+    values: Vec<SqlValue>,
+    request: &PageRequest,
+    from_row: F,
+) -> Result<Page<T>>
+where
+    F: Fn(&Row) -> Result<T>,
+{
+    query_page_by(
+        conn,
+        query,
+        values,
+        request.sort.columns(),
+        request,
+        from_row,
+    )
+}
+
+/// Run `query` paged by `request`, sorted by `columns` instead of the
+/// request sort key, for lists that aren't tasks. The last column must
+/// tell rows apart, so pages are stable.
+pub fn query_page_by<T, F>(
+    conn: &Connection,
+    query: &str,
+    mut values: Vec<SqlValue>,
+    columns: &[&str],
+    request: &PageRequest,
+    from_row: F,
+) -> Result<Page<T>>
+where
+    F: Fn(&Row) -> Result<T>,
+{
+    let (direction, comparison) = match request.descending {
+        true => ("DESC", "<"),
+        false => ("ASC", ">"),
+    };
+    let keys: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| format!("{} AS sort_{}", column, index))
+        .collect();
+    let mut sql = format!("SELECT page.*, {} FROM ({}) page", keys.join(", "), query);
+
+    if let Some(cursor) = &request.cursor {
+        let after = decode_cursor(cursor, request, columns.len())?;
+        sql.push_str(&format!(
+            " WHERE ({}) {} ({})",
+            columns.join(", "),
+            comparison,
+            vec!["?"; after.len()].join(", ")
+        ));
+        values.extend(after);
+    }
+
+    let order: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{} {}", column, direction))
+        .collect();
+    sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+    if let Some(limit) = request.limit {
+        // one more row tells if there is a next page.
+        sql.push_str(" LIMIT ?");
+        values.push(SqlValue::Integer(limit as i64 + 1));
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(params_from_iter(values.iter()))?;
+    let mut items = vec![];
+    let mut last = vec![];
+    let mut next_cursor = None;
+
+    while let Some(row) = rows.next()? {
+        if request
+            .limit
+            .is_some_and(|limit| items.len() >= limit as usize)
+        {
+            next_cursor = Some(encode_cursor(request, &last));
+            break;
+        }
+
+        last = (0..columns.len())
+            .map(|index| row.get::<_, SqlValue>(format!("sort_{}", index).as_str()))
+            .collect::<Result<Vec<SqlValue>>>()?;
+        items.push(from_row(row)?);
+    }
+
+    Ok(Page { items, next_cursor })
+}
+
+/// Load tasks of every state in a board. Active states are loaded in
+/// full, while done states only get a first page.
+pub fn load_board_tasks(
+    conn: &Connection,
+    board_id: i64,
+    request: &PageRequest,
+) -> Result<Vec<StateTasks>> {
+    let mut states = vec![];
+
+    for state in State::load_by_board(conn, board_id)? {
+        let state_id = state.get_id().unwrap();
+        let request = PageRequest {
+            limit: match state.is_done() {
+                true => Some(request.limit.unwrap_or(DEFAULT_LIMIT)),
+                false => None,
+            },
+            cursor: None,
+            ..request.clone()
+        };
+
+        states.push(StateTasks {
+            state_id,
+            done: state.is_done(),
+            tasks: TaskSummary::load_by_state(conn, state_id, &request)?,
+        });
+    }
+
+    Ok(states)
+}
+
+fn encode_cursor(request: &PageRequest, last: &[SqlValue]) -> String {
+    let cursor = Cursor {
+        sort: request.sort,
+        descending: request.descending,
+        after: last
+            .iter()
+            .map(|value| match value {
+                SqlValue::Null | SqlValue::Blob(_) => Value::Null,
+                SqlValue::Integer(value) => Value::from(*value),
+                // serde_json doesn't read floats back exactly, and the
+                // cursor must compare equal to its row.
+                SqlValue::Real(value) => serde_json::json!({ "real": value.to_string() }),
+                SqlValue::Text(value) => Value::from(value.as_str()),
+            })
+            .collect(),
+    };
+
+    serde_json::to_vec(&cursor)
+        .unwrap_or_default()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn decode_cursor(cursor: &str, request: &PageRequest, size: usize) -> Result<Vec<SqlValue>> {
+    let invalid = || rusqlite::Error::ToSqlConversionFailure("invalid page cursor".into());
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|index| {
+            cursor
+                .get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if cursor.sort != request.sort
+        || cursor.descending != request.descending
+        || cursor.after.len() != size
+    {
+        return Err(invalid());
+    }
+
+    Ok(cursor
+        .after
+        .into_iter()
+        .map(|value| match value {
+            Value::Number(number) => match number.as_i64() {
+                Some(value) => SqlValue::Integer(value),
+                None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => SqlValue::Text(value),
+            Value::Object(object) => object
+                .get("real")
+                .and_then(Value::as_str)
+                .and_then(|value| value.parse().ok())
+                .map_or(SqlValue::Null, SqlValue::Real),
+            _ => SqlValue::Null,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod pagination_tests {
+    use rusqlite::Connection;
+
+    use super::{load_board_tasks, PageRequest, SortKey};
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::TaskSummary;
+
+    fn names(tasks: &[TaskSummary]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pages_follow_cursor() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Done', 1, 1, 1);
+            INSERT INTO tasks(name, duration, priority, state_id, board_id, position)
+                VALUES ('a', 1, 3, 1, 1, 0), ('b', 1, 1, 1, 1, 1), ('c', 1, 4, 1, 1, 2)
+                    , ('d', 1, 3, 2, 1, 0), ('e', 1, 2, 2, 1, 1), ('f', 1, 3, 2, 1, 2);"#,
+        )
+        .unwrap();
+
+        let mut request = PageRequest {
+            sort: SortKey::Priority,
+            limit: Some(2),
+            ..PageRequest::default()
+        };
+        let page = TaskSummary::load_by_state(&conn, 2, &request).unwrap();
+        assert_eq!(vec!["d", "f"], names(&page.items));
+
+        request.cursor = page.next_cursor;
+        let page = TaskSummary::load_by_state(&conn, 2, &request).unwrap();
+        assert_eq!(vec!["e"], names(&page.items));
+        assert!(page.next_cursor.is_none());
+
+        request.sort = SortKey::Name;
+        assert!(TaskSummary::load_by_state(&conn, 2, &request).is_err());
+
+        let states = load_board_tasks(
+            &conn,
+            1,
+            &PageRequest {
+                limit: Some(1),
+                ..PageRequest::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(vec!["a", "b", "c"], names(&states[0].tasks.items));
+        assert!(states[0].tasks.next_cursor.is_none());
+        assert_eq!(vec!["d"], names(&states[1].tasks.items));
+        assert!(states[1].tasks.next_cursor.is_some());
+    }
+}
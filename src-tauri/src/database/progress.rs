@@ -0,0 +1,187 @@
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use super::models::{EntityKind, TaskSummary};
+
+/// How progress of the tasks of a board is computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgressStrategy {
+    /// progress is set by hand.
+    #[default]
+    Manual,
+    /// share of checklist items done.
+    Checklist,
+    /// time logged against the estimate, capped at complete.
+    TimeLogged,
+    /// subtasks progress weighted by their estimates.
+    Subtasks,
+}
+
+impl ProgressStrategy {
+    /// Name stored in database.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressStrategy::Manual => "Manual",
+            ProgressStrategy::Checklist => "Checklist",
+            ProgressStrategy::TimeLogged => "TimeLogged",
+            ProgressStrategy::Subtasks => "Subtasks",
+        }
+    }
+
+    /// Strategy stored as `name`, failing for unknown names.
+    pub fn from_name(name: &str) -> Result<ProgressStrategy> {
+        match name {
+            "Manual" => Ok(ProgressStrategy::Manual),
+            "Checklist" => Ok(ProgressStrategy::Checklist),
+            "TimeLogged" => Ok(ProgressStrategy::TimeLogged),
+            "Subtasks" => Ok(ProgressStrategy::Subtasks),
+            _ => Err(rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                format!("unknown progress strategy {}", name).into(),
+            )),
+        }
+    }
+}
+
+/// Progress of the tasks inside a board, milestone or group.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Rollup {
+    kind: EntityKind,
+    id: i64,
+    tasks: u32,
+    done: u32,
+    /// from 0.0 to 1.0.
+    progress: f32,
+}
+
+/// Roll up progress of not archived tasks inside entity `id` of `kind`,
+/// which must be a board, milestone or group. Tasks are weighted by
+/// their estimates, or count the same when none has one. Subtasks are
+/// left out, they already count in their parents.
+pub fn rollup(conn: &Connection, kind: EntityKind, id: i64) -> Result<Rollup> {
+    let condition = match kind {
+        EntityKind::Board => "b.id = ?1",
+        EntityKind::Milestone => "t.milestone_id = ?1",
+        EntityKind::Group => "g.id = ?1",
+        _ => {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "cannot roll up progress of {:?}",
+                kind
+            )))
+        }
+    };
+    let query = format!(
+        r#"SELECT COUNT(*), COALESCE(SUM(state_done), 0), COALESCE(
+            SUM(COALESCE(duration, 0) * COALESCE(progress, 0)) / NULLIF(SUM(COALESCE(duration, 0)), 0)
+            , AVG(COALESCE(progress, 0))
+            , 0
+        ) FROM ({} WHERE {} AND t.parent_id IS NULL AND {}) summary;"#,
+        TaskSummary::SELECT,
+        TaskSummary::NOT_ARCHIVED,
+        condition
+    );
+
+    conn.query_row(&query, params![id], |row| {
+        Ok(Rollup {
+            kind,
+            id,
+            tasks: row.get(0)?,
+            done: row.get(1)?,
+            progress: row.get(2)?,
+        })
+    })
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use rusqlite::Connection;
+
+    use super::{rollup, ProgressStrategy};
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::{EntityKind, TaskSummary};
+
+    fn progress(conn: &Connection, id: i64) -> Option<f32> {
+        conn.query_row(
+            &format!("{} WHERE t.id = ?1", TaskSummary::SELECT),
+            [id],
+            TaskSummary::from_row,
+        )
+        .unwrap()
+        .progress
+    }
+
+    #[test]
+    fn test_strategies_and_rollups() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id, progress_strategy)
+                VALUES (1, 'manual', 0, 1, 'Manual'), (2, 'checklist', 1, 1, 'Checklist')
+                    , (3, 'time', 2, 1, 'TimeLogged'), (4, 'subtasks', 3, 1, 'Subtasks');
+            INSERT INTO states(id, name, position, board_id, done)
+                VALUES (1, 'Todo', 0, 1, 0), (2, 'Todo', 0, 2, 0), (3, 'Todo', 0, 3, 0)
+                    , (4, 'Todo', 0, 4, 0), (5, 'Done', 1, 4, 1);
+            INSERT INTO milestones(id, name, position, board_id) VALUES (1, 'v1', 0, 4);
+            INSERT INTO tasks(id, name, duration, progress, state_id, board_id, position, parent_id, milestone_id)
+                VALUES (1, 'manual', 60, 0.25, 1, 1, 0, NULL, NULL)
+                    , (2, 'checklist', NULL, 0.9, 2, 2, 0, NULL, NULL)
+                    , (3, 'time', 60, NULL, 3, 3, 0, NULL, NULL)
+                    , (4, 'parent', NULL, NULL, 4, 4, 0, NULL, 1)
+                    , (5, 'long', 90, 0.5, 4, 4, 1, 4, NULL)
+                    , (6, 'short', 30, NULL, 5, 4, 0, 4, NULL)
+                    , (7, 'plain', NULL, 0.5, 4, 4, 2, NULL, 1);
+            INSERT INTO checklist_items(name, done, position, task_id)
+                VALUES ('a', 1, 0, 2), ('b', 0, 1, 2), ('c', 0, 2, 2), ('d', 0, 3, 2);
+            INSERT INTO time_logs(minutes, task_id) VALUES (45, 3), (30, 3);"#,
+        )
+        .unwrap();
+
+        assert_eq!(Some(0.25), progress(&conn, 1));
+        assert_eq!(Some(0.25), progress(&conn, 2));
+        assert_eq!(Some(1.0), progress(&conn, 3));
+        assert_eq!(Some(0.625), progress(&conn, 4));
+        assert_eq!(Some(1.0), progress(&conn, 6));
+
+        let milestone = rollup(&conn, EntityKind::Milestone, 1).unwrap();
+        assert_eq!((2, 0), (milestone.tasks, milestone.done));
+        assert_eq!(0.5625, milestone.progress);
+        let group = rollup(&conn, EntityKind::Group, 1).unwrap();
+        assert_eq!(5, group.tasks);
+        assert!(rollup(&conn, EntityKind::Task, 1).is_err());
+
+        assert!(conn
+            .execute("UPDATE tasks SET progress = 1.5 WHERE id = 1;", [])
+            .is_err());
+        // subtasks can't be ancestors of their parent.
+        conn.execute("UPDATE tasks SET parent_id = 5 WHERE id = 7;", [])
+            .unwrap();
+        assert!(conn
+            .execute("UPDATE tasks SET parent_id = 7 WHERE id = 4;", [])
+            .is_err());
+        assert!(conn
+            .execute("UPDATE tasks SET parent_id = 4 WHERE id = 4;", [])
+            .is_err());
+    }
+
+    #[test]
+    fn test_unknown_strategies_are_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);",
+            [],
+        )
+        .unwrap();
+
+        assert!(ProgressStrategy::from_name("Weekly").is_err());
+        assert!(conn
+            .execute(
+                r#"INSERT INTO boards(id, name, position, group_id, progress_strategy)
+                VALUES (1, 'app', 0, 1, 'Weekly');"#,
+                [],
+            )
+            .is_err());
+    }
+}
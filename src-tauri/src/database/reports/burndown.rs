@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{days, History};
+use crate::database::models::EntityKind;
+
+/// What the work of a task is measured in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Estimate {
+    /// task duration estimate, in hours.
+    #[default]
+    Hours,
+    StoryPoints,
+    /// every task counts as one.
+    Tasks,
+}
+
+/// Period and tasks of a burndown: a sprint is a date range of a board,
+/// optionally narrowed to a milestone.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BurndownRequest {
+    pub board_id: i64,
+    #[serde(default)]
+    pub milestone_id: Option<i64>,
+    /// first day, `YYYY-MM-DD`.
+    pub from: String,
+    /// last day, milestone due date or today when missing.
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub estimate: Estimate,
+}
+
+/// Work at the end of a day, in the burndown estimate unit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BurndownDay {
+    date: String,
+    /// all work planned, done or not.
+    scope: f64,
+    completed: f64,
+    remaining: f64,
+    /// scope added since the day before, by new tasks or bigger estimates.
+    added: f64,
+    /// scope removed since the day before.
+    removed: f64,
+    /// remaining work burned at a steady pace, from first day scope.
+    ideal: f64,
+}
+
+/// Daily series of a burndown, usable as burnup with `scope` and `completed`.
+#[derive(Debug, Serialize)]
+pub struct Burndown {
+    estimate: Estimate,
+    days: Vec<BurndownDay>,
+}
+
+impl Burndown {
+    /// Series as CSV, one line per day.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,scope,completed,remaining,added,removed,ideal\n");
+        for day in &self.days {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                day.date,
+                day.scope,
+                day.completed,
+                day.remaining,
+                day.added,
+                day.removed,
+                day.ideal
+            ));
+        }
+
+        csv
+    }
+}
+
+impl History {
+    /// Work of the task at the end of `day`, `None` when out of scope.
+    fn work_at(&self, request: &BurndownRequest, day: &str) -> Option<f64> {
+        let in_scope = self.exists_at(day)
+            && self.value_at("archived_at", day).is_null()
+            && self.value_at("board_id", day).as_i64() == Some(request.board_id)
+            && request.milestone_id.is_none_or(|milestone| {
+                self.value_at("milestone_id", day).as_i64() == Some(milestone)
+            });
+        if !in_scope {
+            return None;
+        }
+
+        Some(match request.estimate {
+            Estimate::Hours => self.value_at("duration", day).as_f64().unwrap_or(0.0) / 60.0,
+            Estimate::StoryPoints => self.value_at("story_points", day).as_f64().unwrap_or(0.0),
+            Estimate::Tasks => 1.0,
+        })
+    }
+}
+
+/// Compute daily remaining, completed and scope changes of work in a
+/// board, as it was at the end of each day. Past days are rebuilt from
+/// the audit log, so later edits, moves, deletions and states marked
+/// done or not do not change them. Days are UTC dates, like audit times.
+pub fn burndown(conn: &Connection, request: &BurndownRequest) -> Result<Burndown> {
+    let to = match &request.to {
+        Some(to) => Some(to.clone()),
+        None => match request.milestone_id {
+            Some(milestone) => conn
+                .query_row(
+                    "SELECT due_at FROM milestones WHERE id = ?1;",
+                    params![milestone],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()?
+                .flatten(),
+            None => None,
+        },
+    };
+    // the day before the period is computed too, to find changes of first day.
+    let before: String =
+        conn.query_row("SELECT date(?1, '-1 day');", params![request.from], |row| {
+            row.get(0)
+        })?;
+    let days = days(conn, &before, to.as_deref())?;
+
+    // states may be marked done or not later, so their flag is rebuilt too.
+    let states: HashMap<i64, History> =
+        History::load_in_board(conn, EntityKind::State, request.board_id)?
+            .into_iter()
+            .map(|state| (state.id, state))
+            .collect();
+    let tasks = History::load_in_board(conn, EntityKind::Task, request.board_id)?;
+
+    let mut series: Vec<BurndownDay> = vec![];
+    let mut previous: Vec<Option<f64>> = vec![];
+    for (day_index, day) in days.iter().enumerate() {
+        let works: Vec<Option<f64>> = tasks
+            .iter()
+            .map(|task| task.work_at(request, day))
+            .collect();
+        let mut current = BurndownDay {
+            date: day.clone(),
+            scope: 0.0,
+            completed: 0.0,
+            remaining: 0.0,
+            added: 0.0,
+            removed: 0.0,
+            ideal: 0.0,
+        };
+
+        for (index, (task, work)) in tasks.iter().zip(&works).enumerate() {
+            let work = work.unwrap_or(0.0);
+            let done = task
+                .value_at("state_id", day)
+                .as_i64()
+                .and_then(|state| states.get(&state))
+                .is_some_and(|state| state.value_at("done", day).as_i64().unwrap_or(0) != 0);
+            current.scope += work;
+            if done {
+                current.completed += work;
+            }
+
+            let before = previous.get(index).copied().flatten().unwrap_or(0.0);
+            if work > before {
+                current.added += work - before;
+            } else {
+                current.removed += before - work;
+            }
+        }
+        current.remaining = current.scope - current.completed;
+
+        if day_index > 0 {
+            series.push(current);
+        }
+        previous = works;
+    }
+
+    let start = series.first().map_or(0.0, |day| day.remaining);
+    let steps = series.len().saturating_sub(1).max(1) as f64;
+    for (index, day) in series.iter_mut().enumerate() {
+        day.ideal = start * (1.0 - index as f64 / steps).max(0.0);
+    }
+
+    Ok(Burndown {
+        estimate: request.estimate,
+        days: series,
+    })
+}
+
+#[cfg(test)]
+mod burndown_tests {
+    use rusqlite::Connection;
+
+    use super::{burndown, BurndownRequest, Estimate};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_burndown_from_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id, done) VALUES (1, 'Todo', 0, 1, 0), (2, 'Done', 1, 1, 1);
+            INSERT INTO tasks(id, name, duration, state_id, board_id, position, created_at)
+                VALUES (1, 'login', 120, 2, 1, 0, '2024-02-01 10:00:00')
+                    , (2, 'signup', 60, 1, 1, 1, '2024-02-01 10:00:00')
+                    , (3, 'logout', 60, 1, 1, 2, '2024-03-03 10:00:00');
+            INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin, changed_at)
+                VALUES ('Task', 1, 'Move', 'state_id', '1', '2', 'Gui', '2024-03-03 10:00:00')
+                    , ('Task', 2, 'Update', 'duration', '240', '60', 'Gui', '2024-03-04 10:00:00')
+                    , ('Task', 3, 'Create', 'name', 'null', '"logout"', 'Gui', '2024-03-03 10:00:00')
+                    , ('Task', 3, 'Create', 'board_id', 'null', '1', 'Gui', '2024-03-03 10:00:00')
+                    , ('Task', 3, 'Create', 'duration', 'null', '60', 'Gui', '2024-03-03 10:00:00')
+                    , ('Task', 4, 'Delete', 'name', '"footer"', 'null', 'Gui', '2024-03-04 10:00:00')
+                    , ('Task', 4, 'Delete', 'board_id', '1', 'null', 'Gui', '2024-03-04 10:00:00')
+                    , ('Task', 4, 'Delete', 'duration', '30', 'null', 'Gui', '2024-03-04 10:00:00')
+                    , ('Task', 4, 'Delete', 'state_id', '1', 'null', 'Gui', '2024-03-04 10:00:00');"#,
+        )
+        .unwrap();
+        let mut request = BurndownRequest {
+            board_id: 1,
+            milestone_id: None,
+            from: String::from("2024-03-01"),
+            to: Some(String::from("2024-03-04")),
+            estimate: Estimate::Hours,
+        };
+
+        let series = burndown(&conn, &request).unwrap();
+        let values: Vec<(&str, f64, f64, f64, f64)> = series
+            .days
+            .iter()
+            .map(|day| {
+                (
+                    day.date.as_str(),
+                    day.scope,
+                    day.completed,
+                    day.added,
+                    day.removed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("2024-03-01", 6.5, 0.0, 0.0, 0.0),
+                ("2024-03-02", 6.5, 0.0, 0.0, 0.0),
+                ("2024-03-03", 7.5, 2.0, 1.0, 0.0),
+                ("2024-03-04", 4.0, 2.0, 0.0, 3.5),
+            ],
+            values
+        );
+        assert_eq!(2.0, series.days[3].remaining);
+        assert_eq!(4.33, (series.days[1].ideal * 100.0).round() / 100.0);
+        assert_eq!(
+            "2024-03-01,6.5,0,6.5,0,0,6.5",
+            series.to_csv().lines().nth(1).unwrap()
+        );
+
+        // Done was only marked done on last day.
+        conn.execute(
+            r#"INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin, changed_at)
+            VALUES ('State', 2, 'Update', 'done', '0', '1', 'Gui', '2024-03-04 09:00:00');"#,
+            [],
+        )
+        .unwrap();
+        let series = burndown(&conn, &request).unwrap();
+        assert_eq!(
+            vec![0.0, 0.0, 0.0, 2.0],
+            series
+                .days
+                .iter()
+                .map(|day| day.completed)
+                .collect::<Vec<f64>>()
+        );
+
+        request.estimate = Estimate::Tasks;
+        let series = burndown(&conn, &request).unwrap();
+        assert_eq!(3.0, series.days[0].scope);
+        assert_eq!(2.0, series.days[3].remaining);
+    }
+}
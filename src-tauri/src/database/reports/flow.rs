@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use rusqlite::{Connection, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{days, History};
+use crate::database::models::EntityKind;
+
+/// Period of a cumulative flow diagram of a board.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowRequest {
+    pub board_id: i64,
+    /// first day, `YYYY-MM-DD`.
+    pub from: String,
+    /// last day, today when missing.
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// A band of the diagram: a state of the board during the period, as it
+/// is now or was when deleted.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FlowState {
+    state_id: i64,
+    name: String,
+    /// names the state had before being renamed in the period, oldest first.
+    former_names: Vec<String>,
+    position: i64,
+    deleted: bool,
+}
+
+/// Tasks in each state at the end of a day, in the order of diagram states.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FlowDay {
+    date: String,
+    counts: Vec<u32>,
+}
+
+/// Daily count of tasks per state, ready to be stacked.
+#[derive(Debug, Serialize)]
+pub struct CumulativeFlow {
+    states: Vec<FlowState>,
+    days: Vec<FlowDay>,
+}
+
+impl History {
+    /// Last value of `field`, the one before deletion for deleted entities.
+    fn last_known(&self, field: &str) -> Value {
+        if let Some(row) = &self.current {
+            return row.get(field).cloned().unwrap_or(Value::Null);
+        }
+
+        match self
+            .changes
+            .iter()
+            .rfind(|change| change.get_field() == field)
+        {
+            Some(change) if change.get_new_value().is_null() => change.get_old_value().clone(),
+            Some(change) => change.get_new_value().clone(),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Count tasks of board `board_id` in each of its states, at the end of
+/// every day of the period. Both tasks and states are rebuilt from the
+/// audit log, so states renamed, reordered or deleted in the period keep
+/// their band, under their last name and position.
+pub fn cumulative_flow(conn: &Connection, request: &FlowRequest) -> Result<CumulativeFlow> {
+    let days = days(conn, &request.from, request.to.as_deref())?;
+    let tasks = History::load_in_board(conn, EntityKind::Task, request.board_id)?;
+    let states: Vec<History> = History::load_in_board(conn, EntityKind::State, request.board_id)?
+        .into_iter()
+        .filter(|state| {
+            days.iter().any(|day| {
+                state.exists_at(day)
+                    && state.value_at("board_id", day).as_i64() == Some(request.board_id)
+            })
+        })
+        .collect();
+
+    let mut bands: Vec<FlowState> = states
+        .iter()
+        .map(|state| {
+            let name = state
+                .last_known("name")
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            let mut former_names: Vec<String> = vec![];
+            for day in &days {
+                if let Value::String(day_name) = state.value_at("name", day) {
+                    if day_name != name && !former_names.contains(&day_name) {
+                        former_names.push(day_name);
+                    }
+                }
+            }
+
+            FlowState {
+                state_id: state.id,
+                name,
+                former_names,
+                position: state.last_known("position").as_i64().unwrap_or(i64::MAX),
+                deleted: state.current.is_none(),
+            }
+        })
+        .collect();
+    bands.sort_by_key(|band| (band.position, band.state_id));
+    let columns: HashMap<i64, usize> = bands
+        .iter()
+        .enumerate()
+        .map(|(column, band)| (band.state_id, column))
+        .collect();
+
+    let flow_days = days
+        .iter()
+        .map(|day| {
+            let mut counts = vec![0; bands.len()];
+            for task in &tasks {
+                let in_board = task.exists_at(day)
+                    && task.value_at("archived_at", day).is_null()
+                    && task.value_at("board_id", day).as_i64() == Some(request.board_id);
+                let column = task
+                    .value_at("state_id", day)
+                    .as_i64()
+                    .and_then(|state| columns.get(&state));
+                if let (true, Some(&column)) = (in_board, column) {
+                    counts[column] += 1;
+                }
+            }
+
+            FlowDay {
+                date: day.clone(),
+                counts,
+            }
+        })
+        .collect();
+
+    Ok(CumulativeFlow {
+        states: bands,
+        days: flow_days,
+    })
+}
+
+#[cfg(test)]
+mod flow_tests {
+    use rusqlite::Connection;
+
+    use super::{cumulative_flow, FlowRequest};
+    use crate::database::fixtures::create_tables;
+
+    #[test]
+    fn test_cumulative_flow_from_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1), (2, 'Review', 1, 1), (3, 'Done', 2, 1);
+            INSERT INTO tasks(id, name, state_id, board_id, position, created_at)
+                VALUES (1, 'login', 2, 1, 0, '2024-02-01 10:00:00')
+                    , (2, 'signup', 1, 1, 1, '2024-02-01 10:00:00')
+                    , (3, 'logout', 3, 1, 0, '2024-02-01 10:00:00')
+                    , (4, 'footer', 1, 1, 2, '2024-03-02 10:00:00');
+            INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin, changed_at)
+                VALUES ('State', 2, 'Update', 'name', '"QA"', '"Review"', 'Gui', '2024-03-02 09:00:00')
+                    , ('State', 2, 'Move', 'position', '2', '1', 'Gui', '2024-03-02 09:00:00')
+                    , ('State', 3, 'Move', 'position', '1', '2', 'Gui', '2024-03-02 09:00:00')
+                    , ('Task', 1, 'Move', 'state_id', '1', '2', 'Gui', '2024-03-02 10:00:00')
+                    , ('Task', 2, 'Move', 'state_id', '4', '1', 'Gui', '2024-03-03 10:00:00')
+                    , ('State', 4, 'Delete', 'name', '"Blocked"', 'null', 'Gui', '2024-03-03 11:00:00')
+                    , ('State', 4, 'Delete', 'position', '3', 'null', 'Gui', '2024-03-03 11:00:00')
+                    , ('State', 4, 'Delete', 'board_id', '1', 'null', 'Gui', '2024-03-03 11:00:00');"#,
+        )
+        .unwrap();
+
+        let flow = cumulative_flow(
+            &conn,
+            &FlowRequest {
+                board_id: 1,
+                from: String::from("2024-03-01"),
+                to: Some(String::from("2024-03-03")),
+            },
+        )
+        .unwrap();
+        let states: Vec<(&str, bool)> = flow
+            .states
+            .iter()
+            .map(|state| (state.name.as_str(), state.deleted))
+            .collect();
+        assert_eq!(
+            vec![
+                ("Todo", false),
+                ("Review", false),
+                ("Done", false),
+                ("Blocked", true)
+            ],
+            states
+        );
+        assert_eq!(vec![String::from("QA")], flow.states[1].former_names);
+        let counts: Vec<&Vec<u32>> = flow.days.iter().map(|day| &day.counts).collect();
+        assert_eq!(
+            vec![&vec![1, 0, 1, 1], &vec![1, 1, 1, 1], &vec![2, 1, 1, 0]],
+            counts
+        );
+    }
+}
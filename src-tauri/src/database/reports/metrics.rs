@@ -0,0 +1,292 @@
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Result};
+use serde::Serialize;
+
+use super::{percentile, timeline, ReportFilter};
+
+/// Percentiles given with every distribution.
+pub const PERCENTILES: [u8; 3] = [50, 85, 95];
+
+/// Days a task took, or is taking, with the day it is plotted at.
+#[derive(Debug, Serialize)]
+pub struct TaskDays {
+    task_id: i64,
+    name: String,
+    state_name: String,
+    /// day of completion, or of work start for items in progress.
+    date: String,
+    days: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Percentile {
+    percentile: u8,
+    days: f64,
+}
+
+/// Days taken by tasks, shortest first, ready to be plotted as a scatter
+/// chart with its percentile lines.
+#[derive(Debug, Serialize)]
+pub struct Distribution {
+    count: usize,
+    average: Option<f64>,
+    percentiles: Vec<Percentile>,
+    tasks: Vec<TaskDays>,
+}
+
+/// Tasks completed in a week, starting on monday.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct WeekCount {
+    week: String,
+    count: u32,
+}
+
+/// Condition keeping completed tasks inside the period of a report.
+const COMPLETED_IN_PERIOD: &str = r#"completed_at IS NOT NULL
+    AND date(completed_at) >= COALESCE(?, date(completed_at))
+    AND date(completed_at) <= COALESCE(?, date(completed_at))"#;
+
+/// Days from creation to completion of tasks completed in the period.
+pub fn lead_time(conn: &Connection, report: &ReportFilter) -> Result<Distribution> {
+    distribution(
+        conn,
+        report,
+        "completed_at",
+        "julianday(completed_at) - julianday(created_at)",
+        &format!("{} AND created_at IS NOT NULL", COMPLETED_IN_PERIOD),
+        true,
+    )
+}
+
+/// Days from work start to completion of tasks completed in the period.
+pub fn cycle_time(conn: &Connection, report: &ReportFilter) -> Result<Distribution> {
+    distribution(
+        conn,
+        report,
+        "completed_at",
+        "julianday(completed_at) - julianday(work_started_at)",
+        &format!(
+            "{} AND work_started_at IS NOT NULL AND work_started_at <= completed_at",
+            COMPLETED_IN_PERIOD
+        ),
+        true,
+    )
+}
+
+/// Days since work started of tasks in progress: the ones in active
+/// states, or started and not ended. Report period is not used.
+pub fn wip_age(conn: &Connection, report: &ReportFilter) -> Result<Distribution> {
+    distribution(
+        conn,
+        report,
+        "COALESCE(work_started_at, created_at)",
+        "julianday('now') - julianday(COALESCE(work_started_at, created_at))",
+        r#"NOT state_done AND (state_active OR (started_at IS NOT NULL AND ended_at IS NULL))"#,
+        false,
+    )
+}
+
+/// Tasks completed per week in the period, weeks without tasks included.
+pub fn throughput(conn: &Connection, report: &ReportFilter) -> Result<Vec<WeekCount>> {
+    let (query, mut values) = timeline(report);
+    values.extend(period(report));
+    values.extend(period(report));
+    let mut stmt = conn.prepare(&format!(
+        r#"WITH RECURSIVE completed AS (
+            SELECT date(completed_at, 'weekday 0', '-6 days') AS week
+            FROM ({}) WHERE {}
+        ), weeks(week, last) AS (
+            SELECT COALESCE(date(?, 'weekday 0', '-6 days'), MIN(week))
+                , COALESCE(date(?, 'weekday 0', '-6 days'), MAX(week))
+            FROM completed
+            UNION ALL
+            SELECT date(week, '+7 days'), last FROM weeks WHERE week < last
+        )
+        SELECT weeks.week, (SELECT COUNT(*) FROM completed WHERE completed.week = weeks.week)
+        FROM weeks WHERE weeks.week IS NOT NULL ORDER BY weeks.week;"#,
+        query, COMPLETED_IN_PERIOD
+    ))?;
+    let weeks = stmt
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok(WeekCount {
+                week: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<WeekCount>>>()?;
+
+    Ok(weeks)
+}
+
+fn period(report: &ReportFilter) -> [Value; 2] {
+    let day = |day: &Option<String>| match day {
+        Some(day) => Value::Text(day.clone()),
+        None => Value::Null,
+    };
+
+    [day(&report.from), day(&report.to)]
+}
+
+fn distribution(
+    conn: &Connection,
+    report: &ReportFilter,
+    date: &str,
+    days: &str,
+    condition: &str,
+    in_period: bool,
+) -> Result<Distribution> {
+    let (query, mut values) = timeline(report);
+    if in_period {
+        values.extend(period(report));
+    }
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT id, name, state_name, date({}) AS day, MAX(0.0, {}) AS days
+        FROM ({}) WHERE {} ORDER BY days, id;"#,
+        date, days, query, condition
+    ))?;
+    let tasks = stmt
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok(TaskDays {
+                task_id: row.get("id")?,
+                name: row.get("name")?,
+                state_name: row.get("state_name")?,
+                date: row.get("day")?,
+                days: row.get("days")?,
+            })
+        })?
+        .collect::<Result<Vec<TaskDays>>>()?;
+
+    let days: Vec<f64> = tasks.iter().map(|task| task.days).collect();
+    let percentiles = PERCENTILES
+        .iter()
+        .filter_map(|&rank| {
+            percentile(&days, rank).map(|days| Percentile {
+                percentile: rank,
+                days,
+            })
+        })
+        .collect();
+
+    Ok(Distribution {
+        count: tasks.len(),
+        average: match days.is_empty() {
+            true => None,
+            false => Some(days.iter().sum::<f64>() / days.len() as f64),
+        },
+        percentiles,
+        tasks,
+    })
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use rusqlite::Connection;
+
+    use super::{cycle_time, lead_time, throughput, wip_age, Percentile, WeekCount};
+    use crate::database::filter::Scope;
+    use crate::database::fixtures::create_tables;
+    use crate::database::reports::ReportFilter;
+
+    #[test]
+    fn test_flow_metrics() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1), (2, 'web', 1, 1);
+            INSERT INTO states(id, name, position, board_id, active, done)
+                VALUES (1, 'Todo', 0, 1, 0, 0), (2, 'Doing', 1, 1, 1, 0), (3, 'Done', 2, 1, 0, 1)
+                    , (4, 'Todo', 0, 2, 0, 0);
+            INSERT INTO tasks(id, name, priority, state_id, board_id, position, labels, created_at, started_at, ended_at)
+                VALUES (1, 'login', 3, 3, 1, 0, '["bug"]', '2024-01-01 09:00:00', NULL, NULL)
+                    , (2, 'signup', 2, 3, 1, 1, '[]', '2024-01-02 09:00:00', '2024-01-04 09:00:00', '2024-01-05 09:00:00')
+                    , (3, 'logout', 2, 3, 1, 2, '[]', '2024-01-01 09:00:00', NULL, '2024-01-19 09:00:00')
+                    , (4, 'profile', 2, 2, 1, 0, '[]', datetime('now', '-10 days'), NULL, NULL)
+                    , (5, 'footer', 2, 4, 2, 0, '[]', datetime('now', '-5 days'), datetime('now', '-2 days'), NULL);
+            INSERT INTO audit_log(entity, entity_id, action, field, old_value, new_value, origin, changed_at)
+                VALUES ('Task', 1, 'Create', 'state_id', 'null', '1', 'Gui', '2024-01-01 09:00:00')
+                    , ('Task', 1, 'Move', 'state_id', '1', '2', 'Gui', '2024-01-03 09:00:00')
+                    , ('Task', 1, 'Move', 'state_id', '2', '3', 'Gui', '2024-01-11 09:00:00')
+                    , ('Task', 4, 'Move', 'state_id', '1', '2', 'Gui', datetime('now', '-4 days'));"#,
+        )
+        .unwrap();
+        let report = ReportFilter {
+            scope: Scope::Board(1),
+            ..ReportFilter::default()
+        };
+
+        let lead = lead_time(&conn, &report).unwrap();
+        assert_eq!(3, lead.count);
+        assert_eq!(
+            vec![3.0, 10.0, 18.0],
+            lead.tasks
+                .iter()
+                .map(|task| task.days)
+                .collect::<Vec<f64>>()
+        );
+        assert_eq!(
+            Percentile {
+                percentile: 50,
+                days: 10.0
+            },
+            lead.percentiles[0]
+        );
+
+        let cycle = cycle_time(&conn, &report).unwrap();
+        assert_eq!(2, cycle.count);
+        assert_eq!(Some(4.5), cycle.average);
+        let bugs = ReportFilter {
+            label: Some(String::from("bug")),
+            ..report.clone()
+        };
+        assert_eq!(1, cycle_time(&conn, &bugs).unwrap().count);
+        let high = ReportFilter {
+            priority: Some(String::from("high")),
+            ..report.clone()
+        };
+        assert_eq!(1, lead_time(&conn, &high).unwrap().count);
+
+        let weeks = throughput(
+            &conn,
+            &ReportFilter {
+                to: Some(String::from("2024-01-22")),
+                ..report.clone()
+            },
+        )
+        .unwrap();
+        let week = |week: &str, count: u32| WeekCount {
+            week: String::from(week),
+            count,
+        };
+        assert_eq!(
+            vec![
+                week("2024-01-01", 1),
+                week("2024-01-08", 1),
+                week("2024-01-15", 1),
+                week("2024-01-22", 0)
+            ],
+            weeks
+        );
+        let january = ReportFilter {
+            from: Some(String::from("2024-01-08")),
+            to: Some(String::from("2024-01-14")),
+            ..report.clone()
+        };
+        assert_eq!(1, lead_time(&conn, &january).unwrap().count);
+
+        let age = wip_age(
+            &conn,
+            &ReportFilter {
+                scope: Scope::Group(1),
+                ..ReportFilter::default()
+            },
+        )
+        .unwrap();
+        let ages: Vec<(&str, i64)> = age
+            .tasks
+            .iter()
+            .map(|task| (task.name.as_str(), task.days.round() as i64))
+            .collect();
+        assert_eq!(vec![("footer", 2), ("profile", 4)], ages);
+    }
+}
@@ -0,0 +1,178 @@
+pub mod burndown;
+pub mod flow;
+pub mod metrics;
+
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, Result};
+use serde::Deserialize;
+
+use super::audit::{self, AuditEntry};
+use super::filter::{self, Comparison, Condition, Scope, Term};
+use super::history::{self, RowSnapshot};
+use super::models::{EntityKind, TaskSummary};
+
+/// Tasks taken by a report, all the not archived ones of workspace by default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ReportFilter {
+    pub scope: Scope,
+    /// priority name, in the scheme of each task board.
+    pub priority: Option<String>,
+    pub label: Option<String>,
+    /// first day, `YYYY-MM-DD`, of the reported period.
+    pub from: Option<String>,
+    /// last day, `YYYY-MM-DD`, of the reported period.
+    pub to: Option<String>,
+}
+
+/// Query listing tasks matching `report` with their work timestamps,
+/// read from tasks and from `state_id` changes on audit log:
+///
+/// - `work_started_at`: task `started_at`, or when it first entered an
+///   active state, or first left its initial state.
+/// - `completed_at`: for tasks in done states, task `ended_at` or when
+///   it last entered its current state.
+///
+/// Period of `report` is not applied.
+fn timeline(report: &ReportFilter) -> (String, Vec<Value>) {
+    let mut terms = vec![];
+    if let Some(priority) = &report.priority {
+        terms.push(Term {
+            negated: false,
+            condition: Condition::Priority(Comparison::Equal, priority.clone()),
+        });
+    }
+    if let Some(label) = &report.label {
+        terms.push(Term {
+            negated: false,
+            condition: Condition::Label(label.clone()),
+        });
+    }
+    let (condition, values) = filter::to_sql(&terms, report.scope);
+
+    let query = format!(
+        r#"SELECT summary.*
+            , COALESCE(summary.started_at, (
+                SELECT MIN(a.changed_at) FROM audit_log a JOIN states x ON a.new_value = CAST(x.id AS TEXT)
+                WHERE a.entity = 'Task' AND a.entity_id = summary.id AND a.field = 'state_id'
+                    AND x.active = 1
+            ), (
+                SELECT MIN(a.changed_at) FROM audit_log a
+                WHERE a.entity = 'Task' AND a.entity_id = summary.id AND a.field = 'state_id'
+                    AND a.old_value <> 'null'
+            )) AS work_started_at
+            , CASE WHEN summary.state_done THEN COALESCE(summary.ended_at, (
+                SELECT MAX(a.changed_at) FROM audit_log a
+                WHERE a.entity = 'Task' AND a.entity_id = summary.id AND a.field = 'state_id'
+                    AND a.new_value = CAST(summary.state_id AS TEXT)
+            ), summary.updated_at) END AS completed_at
+        FROM ({} WHERE {}) summary"#,
+        TaskSummary::SELECT,
+        condition
+    );
+
+    (query, values)
+}
+
+/// Value at `percentile` of already sorted `values`, by nearest rank.
+fn percentile(values: &[f64], percentile: u8) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (percentile.min(100) as f64 / 100.0 * values.len() as f64).ceil() as usize;
+
+    Some(values[rank.clamp(1, values.len()) - 1])
+}
+
+/// An entity with all its changes, used to find how it was at the end of
+/// any day, even after it was changed or deleted.
+struct History {
+    id: i64,
+    current: Option<RowSnapshot>,
+    changes: Vec<AuditEntry>,
+}
+
+impl History {
+    fn load(conn: &Connection, kind: EntityKind, id: i64) -> Result<History> {
+        Ok(History {
+            id,
+            current: history::snapshot(conn, (kind, id))?,
+            changes: audit::entity_history(conn, kind, id, None)?,
+        })
+    }
+
+    /// Load entities of `kind` that are, or were at some time, inside
+    /// board `board_id`.
+    fn load_in_board(conn: &Connection, kind: EntityKind, board_id: i64) -> Result<Vec<History>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM {} WHERE board_id = ?1;",
+            kind.table()
+        ))?;
+        let mut ids = stmt
+            .query_map(params![board_id], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<i64>>>()?;
+        ids.extend(audit::entities_with(
+            conn,
+            kind,
+            "board_id",
+            serde_json::Value::from(board_id),
+        )?);
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| History::load(conn, kind, id))
+            .collect()
+    }
+
+    /// Value of `field` at the end of `day`.
+    fn value_at(&self, field: &str, day: &str) -> serde_json::Value {
+        let mut changes = self
+            .changes
+            .iter()
+            .filter(|change| change.get_field() == field);
+        let first = changes.clone().next();
+
+        match changes.rfind(|change| change.get_changed_at().get(..10).unwrap_or_default() <= day) {
+            Some(change) => change.get_new_value().clone(),
+            None => match first {
+                Some(change) => change.get_old_value().clone(),
+                None => self
+                    .current
+                    .as_ref()
+                    .and_then(|row| row.get(field).cloned())
+                    .unwrap_or(serde_json::Value::Null),
+            },
+        }
+    }
+
+    /// Whether the entity was created and not deleted by the end of `day`.
+    fn exists_at(&self, day: &str) -> bool {
+        let created = match self.value_at("created_at", day) {
+            serde_json::Value::String(created_at) => {
+                created_at.get(..10).unwrap_or_default() <= day
+            }
+            _ => true,
+        };
+
+        created && !self.value_at("name", day).is_null()
+    }
+}
+
+/// Days from `from` to `to`, or today, both included. Today is the UTC
+/// date, like the audit log times days are compared with.
+fn days(conn: &Connection, from: &str, to: Option<&str>) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"WITH RECURSIVE days(day) AS (
+            SELECT date(?1)
+            UNION ALL
+            SELECT date(day, '+1 day') FROM days
+            WHERE day < date(COALESCE(?2, date('now')))
+        ) SELECT day FROM days;"#,
+    )?;
+    let days = stmt
+        .query_map(params![from, to], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    Ok(days)
+}
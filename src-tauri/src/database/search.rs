@@ -0,0 +1,244 @@
+use rusqlite::types::Value;
+use rusqlite::{Connection, Result};
+use serde::Serialize;
+
+use super::pagination::{query_page_by, Page, PageRequest};
+
+/// Default number of hits returned by search.
+pub const DEFAULT_LIMIT: u32 = 50;
+
+/// A task matching a search, with its location in the workspace.
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    task_id: i64,
+    /// task name with matches wrapped in `<mark>` tags.
+    name: String,
+    /// description fragment around the matches, or checklist fragment when
+    /// only checklist items match, if any.
+    snippet: Option<String>,
+    rank: f64,
+    state_id: i64,
+    state_name: String,
+    board_id: i64,
+    board_name: String,
+    group_id: i64,
+    group_name: String,
+}
+
+/// Search a page of not archived tasks by name, description and checklist
+/// items. Hits are always best matches first, the sort key of the request
+/// is not used.
+///
+/// Words must all match, `word*` matches by prefix and `"some words"`
+/// matches the exact phrase.
+pub fn search(conn: &Connection, query: &str, request: &PageRequest) -> Result<Page<SearchHit>> {
+    let query = to_fts_query(query);
+    if query.is_empty() {
+        return Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        });
+    }
+    let request = PageRequest {
+        limit: Some(request.limit.unwrap_or(DEFAULT_LIMIT)),
+        ..request.clone()
+    };
+
+    query_page_by(
+        conn,
+        r#"SELECT t.id AS task_id
+            , highlight(tasks_search, 0, '<mark>', '</mark>') AS name
+            , CASE WHEN instr(snippet(tasks_search, 1, '<mark>', '</mark>', '…', 16), '<mark>') = 0
+                AND instr(snippet(tasks_search, 2, '<mark>', '</mark>', '…', 16), '<mark>') > 0
+                THEN snippet(tasks_search, 2, '<mark>', '</mark>', '…', 16)
+                ELSE snippet(tasks_search, 1, '<mark>', '</mark>', '…', 16)
+            END AS snippet
+            , tasks_search.rank AS rank
+            , s.id AS state_id, s.name AS state_name, b.id AS board_id
+            , b.name AS board_name, g.id AS group_id, g.name AS group_name
+        FROM tasks_search
+            JOIN tasks t ON t.id = tasks_search.rowid
+            JOIN states s ON s.id = t.state_id
+            JOIN boards b ON b.id = t.board_id
+            JOIN groups g ON g.id = b.group_id
+        WHERE tasks_search MATCH ?
+            AND t.archived_at IS NULL AND s.archived_at IS NULL
+            AND b.archived_at IS NULL AND g.archived_at IS NULL"#,
+        vec![Value::Text(query)],
+        &["rank", "task_id"],
+        &request,
+        |row| {
+            let snippet: Option<String> = row.get("snippet")?;
+            Ok(SearchHit {
+                task_id: row.get("task_id")?,
+                name: row.get("name")?,
+                snippet: snippet.filter(|snippet| !snippet.is_empty()),
+                rank: row.get("rank")?,
+                state_id: row.get("state_id")?,
+                state_name: row.get("state_name")?,
+                board_id: row.get("board_id")?,
+                board_name: row.get("board_name")?,
+                group_id: row.get("group_id")?,
+                group_name: row.get("group_name")?,
+            })
+        },
+    )
+}
+
+/// Translate user input into a safe FTS5 query, keeping phrases and
+/// prefixes while quoting everything else.
+pub fn to_fts_query(input: &str) -> String {
+    let mut terms: Vec<String> = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut term = String::new();
+        if c == '"' {
+            // phrase, closing quote is optional.
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                term.push(c);
+            }
+        } else {
+            term.push(c);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                term.push(c);
+            }
+        }
+
+        let prefix = term.ends_with('*') || chars.next_if_eq(&'*').is_some();
+        let term = term.trim_end_matches('*').replace('"', "");
+        if term.trim().is_empty() {
+            continue;
+        }
+
+        terms.push(match prefix {
+            true => format!("\"{}\"*", term),
+            false => format!("\"{}\"", term),
+        });
+    }
+
+    terms.join(" ")
+}
+
+#[cfg(test)]
+mod search_tests {
+    use rusqlite::Connection;
+
+    use super::{search, to_fts_query, SearchHit};
+    use crate::database::fixtures::create_tables;
+    use crate::database::pagination::PageRequest;
+
+    fn hits(conn: &Connection, query: &str) -> Vec<SearchHit> {
+        search(conn, query, &PageRequest::default()).unwrap().items
+    }
+
+    #[test]
+    fn test_to_fts_query() {
+        assert_eq!("", to_fts_query("  "));
+        assert_eq!("\"login\" \"page\"", to_fts_query("login page"));
+        assert_eq!("\"log\"*", to_fts_query("log*"));
+        assert_eq!("\"login page\"", to_fts_query("\"login page\""));
+        assert_eq!("\"login pa\"*", to_fts_query("\"login pa\"*"));
+        assert_eq!("\"foo-bar\" \"a:b\"", to_fts_query("foo-bar a:b"));
+        assert_eq!("\"open\"", to_fts_query("\"open"));
+        assert_eq!("\"AND\"", to_fts_query("AND *"));
+    }
+
+    #[test]
+    fn test_search_is_kept_in_sync() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1);
+            INSERT INTO tasks(id, name, description, duration, state_id, board_id, position)
+                VALUES (1, 'Login page', 'users sign in with email', 30, 1, 1, 0)
+                    , (2, 'Logout', NULL, 10, 1, 1, 1);"#,
+        )
+        .unwrap();
+
+        assert_eq!(2, hits(&conn, "log*").len());
+        let mut request = PageRequest {
+            limit: Some(1),
+            ..PageRequest::default()
+        };
+        let first = search(&conn, "log*", &request).unwrap();
+        request.cursor = first.next_cursor;
+        let second = search(&conn, "log*", &request).unwrap();
+        assert_ne!(first.items[0].task_id, second.items[0].task_id);
+        assert!(second.next_cursor.is_none());
+        let found = hits(&conn, "email");
+        assert_eq!(1, found.len());
+        assert_eq!("app", found[0].board_name);
+        assert!(found[0]
+            .snippet
+            .as_ref()
+            .unwrap()
+            .contains("<mark>email</mark>"));
+
+        conn.execute(
+            "UPDATE tasks SET description = 'with password' WHERE id = 1;",
+            [],
+        )
+        .unwrap();
+        assert!(hits(&conn, "email").is_empty());
+        assert_eq!(1, hits(&conn, "\"with password\"").len());
+
+        conn.execute(
+            "UPDATE tasks SET archived_at = datetime('now') WHERE id = 2;",
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = 1;", []).unwrap();
+        assert!(hits(&conn, "log*").is_empty());
+    }
+
+    #[test]
+    fn test_search_checklist_items() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1);
+            INSERT INTO tasks(id, name, description, state_id, board_id, position)
+                VALUES (1, 'Login page', 'users sign in', 1, 1, 0)
+                    , (2, 'Logout', NULL, 1, 1, 1);
+            INSERT INTO checklist_items(id, name, position, task_id)
+                VALUES (1, 'check password', 0, 1), (2, 'remember me', 1, 1);"#,
+        )
+        .unwrap();
+
+        let found = hits(&conn, "password");
+        assert_eq!(1, found.len());
+        assert_eq!(
+            Some("check <mark>password</mark>\nremember me"),
+            found[0].snippet.as_deref()
+        );
+
+        conn.execute_batch(
+            r#"UPDATE checklist_items SET task_id = 2 WHERE id = 1;
+            UPDATE tasks SET name = 'Sign in' WHERE id = 2;
+            DELETE FROM checklist_items WHERE id = 2;"#,
+        )
+        .unwrap();
+        assert_eq!(2, hits(&conn, "password")[0].task_id);
+        assert!(hits(&conn, "remember").is_empty());
+        conn.execute("DELETE FROM tasks WHERE id = 2;", []).unwrap();
+        assert!(hits(&conn, "password").is_empty());
+        // the index matches its content after every change.
+        conn.execute(
+            "INSERT INTO tasks_search(tasks_search, rank) VALUES ('integrity-check', 1);",
+            [],
+        )
+        .unwrap();
+    }
+}
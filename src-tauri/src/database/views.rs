@@ -0,0 +1,142 @@
+use rusqlite::Connection;
+use serde::Serialize;
+
+use super::filter::{self, FilterError};
+use super::models::{Grouping, SavedView, TaskSummary};
+use super::pagination::PageRequest;
+
+/// Tasks of a view sharing the same group key. Tasks without a value for
+/// the grouping, like tasks with no labels, are kept under a `None` key.
+#[derive(Debug, Serialize)]
+pub struct TaskGroup {
+    key: Option<String>,
+    tasks: Vec<TaskSummary>,
+}
+
+/// Tasks matching a [`SavedView`], already grouped.
+#[derive(Debug, Serialize)]
+pub struct ViewResult {
+    view_id: Option<i64>,
+    total: i64,
+    groups: Vec<TaskGroup>,
+    next_cursor: Option<String>,
+}
+
+/// Run `view` filter and group the page of tasks found.
+pub fn evaluate(
+    conn: &Connection,
+    view: &SavedView,
+    limit: Option<u32>,
+    cursor: Option<String>,
+) -> Result<ViewResult, FilterError> {
+    let terms = filter::parse(view.get_filter())?;
+    let request = PageRequest {
+        sort: view.get_sort(),
+        descending: false,
+        limit,
+        cursor,
+    };
+    let page = filter::filter_tasks(conn, &terms, view.get_scope(), &request)?;
+
+    Ok(ViewResult {
+        view_id: view.get_id(),
+        total: page.total,
+        groups: group_tasks(page.tasks.items, view.get_grouping()),
+        next_cursor: page.tasks.next_cursor,
+    })
+}
+
+/// Split `tasks` by `grouping`, keeping tasks order inside each group.
+/// Groups follow the order their first task appears, except priorities
+/// that are listed from the most urgent. A task with many labels is
+/// listed on each of its labels.
+pub fn group_tasks(tasks: Vec<TaskSummary>, grouping: Option<Grouping>) -> Vec<TaskGroup> {
+    let grouping = match grouping {
+        Some(grouping) => grouping,
+        None => return vec![TaskGroup { key: None, tasks }],
+    };
+    let mut groups: Vec<TaskGroup> = vec![];
+
+    for task in tasks {
+        let keys = match grouping {
+            Grouping::State => vec![Some(task.state_name.clone())],
+            Grouping::Board => vec![Some(task.board_name.clone())],
+            Grouping::Priority => vec![task.priority_name.clone()],
+            Grouping::Label if task.labels.is_empty() => vec![None],
+            Grouping::Label => task.labels.iter().cloned().map(Some).collect(),
+        };
+
+        for key in keys {
+            match groups.iter_mut().find(|group| group.key == key) {
+                Some(group) => group.tasks.push(task.clone()),
+                None => groups.push(TaskGroup {
+                    key,
+                    tasks: vec![task.clone()],
+                }),
+            }
+        }
+    }
+
+    if grouping == Grouping::Priority {
+        groups.sort_by_key(|group| std::cmp::Reverse(group.tasks[0].priority_rank));
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod views_tests {
+    use rusqlite::Connection;
+
+    use super::evaluate;
+    use crate::database::fixtures::create_tables;
+    use crate::database::models::{Grouping, ModelQueryBuilder, SavedView};
+    use crate::database::pagination::SortKey;
+
+    #[test]
+    fn test_evaluate_groups_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO groups(id, name, position) VALUES (1, 'work', 0);
+            INSERT INTO boards(id, name, position, group_id) VALUES (1, 'app', 0, 1);
+            INSERT INTO states(id, name, position, board_id) VALUES (1, 'Todo', 0, 1);
+            INSERT INTO tasks(name, duration, priority, state_id, board_id, position, labels)
+                VALUES ('login', 30, 3, 1, 1, 0, '["bug"]')
+                    , ('signup', 30, 4, 1, 1, 1, '["bug", "ui"]')
+                    , ('footer', 10, 1, 1, 1, 2, '[]')
+                    , ('header', 10, 4, 1, 1, 3, '["ui"]');"#,
+        )
+        .unwrap();
+
+        let mut view = SavedView::new(String::from("urgent"), String::from("p:>=high"), 0);
+        view.set_grouping(Some(Grouping::Priority))
+            .set_sort(SortKey::Name)
+            .set_pinned(true);
+        view.insert(&conn).unwrap();
+        let view = SavedView::load_all(&conn, true).unwrap().pop().unwrap();
+        assert_eq!(Some(Grouping::Priority), view.get_grouping());
+
+        let result = evaluate(&conn, &view, None, None).unwrap();
+        assert_eq!(3, result.total);
+        assert_eq!(Some(String::from("Now")), result.groups[0].key);
+        assert_eq!(
+            vec!["header", "signup"],
+            result.groups[0]
+                .tasks
+                .iter()
+                .map(|task| task.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(Some(String::from("High")), result.groups[1].key);
+
+        let mut view = SavedView::new(String::from("labels"), String::new(), 1);
+        view.set_grouping(Some(Grouping::Label));
+        let groups = evaluate(&conn, &view, None, None).unwrap().groups;
+        let sizes: Vec<(Option<&str>, usize)> = groups
+            .iter()
+            .map(|group| (group.key.as_deref(), group.tasks.len()))
+            .collect();
+        assert_eq!(vec![(Some("bug"), 2), (Some("ui"), 2), (None, 1)], sizes);
+    }
+}
@@ -4,11 +4,42 @@
 mod database;
 
 use std::borrow::{Borrow, BorrowMut};
-use std::sync::Mutex;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
 
-use database::fixtures::create_tables;
+use tauri::Manager as _;
+
+use database::archive::ArchivedItem;
+use database::audit::{AuditEntry, Origin};
+use database::backup::{BackupConfig, BackupError, BackupInfo, BackupReason};
+use database::dashboard::{DashboardEntry, UrgencyWeights};
+use database::duplicate::DuplicateOptions;
+use database::exchange::csv::{
+    self as tasks_csv, CsvExport, CsvImport, CsvImportReport, CsvPreview, RowError,
+};
+use database::exchange::github::{GitHubImport, GitHubSummary};
+use database::exchange::ical::{CalendarFeed, IcsOptions};
+use database::exchange::jira::{JiraImport, JiraMapping, JiraReport};
+use database::exchange::markdown::MarkdownOptions;
+use database::exchange::taskwarrior::{TaskwarriorExport, TaskwarriorImport, TaskwarriorSummary};
+use database::exchange::todo_txt::{TodoTxtImport, TodoTxtReport};
+use database::exchange::trello::TrelloSummary;
+use database::exchange::workspace::{ImportError, ImportMode, ImportSummary};
+use database::filter::{self, FilterError, FilterPage, Scope};
+use database::history::HistoryEntry;
 use database::manager::Manager;
-use database::models::{Board, Group, ModelQueryBuilder, State, Task};
+use database::models::{
+    Board, ChecklistItem, DurationInput, EntityKind, Group, Milestone, ModelQueryBuilder, Priority,
+    SavedView, State, Task, TaskSummary, TimeLog,
+};
+use database::pagination::{Page, PageRequest, StateTasks};
+use database::progress::Rollup;
+use database::reports::burndown::{Burndown, BurndownRequest};
+use database::reports::flow::{CumulativeFlow, FlowRequest};
+use database::reports::metrics::{Distribution, WeekCount};
+use database::reports::ReportFilter;
+use database::search::SearchHit;
+use database::views::ViewResult;
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
@@ -96,24 +127,1026 @@ fn update_task(task: Task, app_state: tauri::State<'_, AppState>) -> Result<Task
     update_entity(task, app_state)
 }
 
-struct AppState {
-    database_manager: Mutex<Manager>,
+/// Parse an estimate like `2d 3h` into minutes, using board working time.
+#[tauri::command]
+fn parse_duration(
+    input: String,
+    board_id: Option<i64>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    let time = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .working_time(board_id)
+        .map_err(|_| String::from("Transaction error!"))?;
+
+    DurationInput::parse(&input, &time)
+        .and_then(|duration| duration.value_in(&time))
+        .map_err(|err| err.to_string())
 }
 
-fn main() {
-    let connection =
-        Manager::new(String::from("./.my-board-db.db")).expect("Could not open database");
-    let state = AppState {
-        database_manager: Mutex::new(connection),
-    };
+/// Format an estimate in minutes like `2d 3h`, using board working time.
+#[tauri::command]
+fn format_duration(
+    minutes: u32,
+    board_id: Option<i64>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let time = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .working_time(board_id)
+        .map_err(|_| String::from("Transaction error!"))?;
 
-    tauri::Builder::default()
-        .setup(|_app| {
-            create_tables().expect("Error trying create database!\n:-(");
+    Ok(DurationInput::Minutes(minutes).format(&time))
+}
 
-            return Ok(());
-        })
-        .manage(state)
+#[tauri::command]
+fn create_priority(
+    new_priority: Priority,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Priority, String> {
+    create_entity(new_priority, app_state)
+}
+
+#[tauri::command]
+fn update_priority(
+    priority: Priority,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Priority, String> {
+    update_entity(priority, app_state)
+}
+
+/// Give a board its own priority levels, its tasks keeping their rank.
+#[tauri::command]
+fn create_priority_scheme(
+    board_id: i64,
+    mut levels: Vec<Priority>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<Priority>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .create_priority_scheme(board_id, &mut levels)
+        .map_err(|_| String::from("Transaction error!"))?;
+
+    Ok(levels)
+}
+
+#[tauri::command]
+fn delete_priority(
+    priority_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .delete_priority(priority_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_priorities(
+    board_id: Option<i64>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<Priority>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_priorities(board_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn create_checklist_item(
+    new_item: ChecklistItem,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<ChecklistItem, String> {
+    create_entity(new_item, app_state)
+}
+
+#[tauri::command]
+fn update_checklist_item(
+    item: ChecklistItem,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<ChecklistItem, String> {
+    update_entity(item, app_state)
+}
+
+#[tauri::command]
+fn delete_checklist_item(
+    item_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .delete_checklist_item(item_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_checklist(
+    task_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<ChecklistItem>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_checklist(task_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn create_time_log(
+    new_log: TimeLog,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<TimeLog, String> {
+    create_entity(new_log, app_state)
+}
+
+#[tauri::command]
+fn update_time_log(log: TimeLog, app_state: tauri::State<'_, AppState>) -> Result<TimeLog, String> {
+    update_entity(log, app_state)
+}
+
+#[tauri::command]
+fn delete_time_log(log_id: i64, app_state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .delete_time_log(log_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_time_logs(
+    task_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<TimeLog>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_time_logs(task_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn create_milestone(
+    new_milestone: Milestone,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Milestone, String> {
+    create_entity(new_milestone, app_state)
+}
+
+#[tauri::command]
+fn update_milestone(
+    milestone: Milestone,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Milestone, String> {
+    update_entity(milestone, app_state)
+}
+
+#[tauri::command]
+fn delete_milestone(
+    milestone_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .delete_milestone(milestone_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_milestones(
+    board_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<Milestone>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_milestones(board_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Progress of a board, milestone or group.
+#[tauri::command]
+fn progress_rollup(
+    kind: EntityKind,
+    id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Rollup, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .progress_rollup(kind, id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn duplicate_board(
+    board_id: i64,
+    options: DuplicateOptions,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<i64, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .duplicate_board(board_id, &options)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn duplicate_group(
+    group_id: i64,
+    options: DuplicateOptions,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<i64, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .duplicate_group(group_id, &options)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_groups(app_state: tauri::State<'_, AppState>) -> Result<Vec<Group>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_groups()
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_board(board_id: i64, app_state: tauri::State<'_, AppState>) -> Result<Board, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_board(board_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_tasks(
+    board_id: i64,
+    page: Option<PageRequest>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<StateTasks>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_tasks(board_id, &page.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_state_tasks(
+    state_id: i64,
+    page: Option<PageRequest>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Page<TaskSummary>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_state_tasks(state_id, &page.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Tell why an archive request was refused, like for kinds that can't
+/// be archived, or report a transaction error.
+fn archive_error(err: rusqlite::Error) -> String {
+    match err {
+        rusqlite::Error::InvalidParameterName(message) => message,
+        _ => String::from("Transaction error!"),
+    }
+}
+
+#[tauri::command]
+fn archive_entity(
+    kind: EntityKind,
+    id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .archive(kind, id)
+        .map_err(archive_error)
+}
+
+#[tauri::command]
+fn list_archived(
+    kind: Option<EntityKind>,
+    page: Option<PageRequest>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Page<ArchivedItem>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .list_archived(kind, &page.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn restore_entity(
+    kind: EntityKind,
+    id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .restore(kind, id)
+        .map_err(archive_error)
+}
+
+#[tauri::command]
+fn purge_entity(
+    kind: EntityKind,
+    id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .purge(kind, id)
+        .map_err(archive_error)
+}
+
+#[tauri::command]
+fn move_task(
+    task_id: i64,
+    state_id: i64,
+    position: u32,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .move_task(task_id, state_id, position)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Sets where next changes come from, logged in the audit log. Imports
+/// are always logged as automation.
+#[tauri::command]
+fn set_origin(origin: Origin, app_state: tauri::State<'_, AppState>) {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .set_origin(origin);
+}
+
+#[tauri::command]
+fn undo(app_state: tauri::State<'_, AppState>) -> Result<Option<HistoryEntry>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .undo()
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn redo(app_state: tauri::State<'_, AppState>) -> Result<Option<HistoryEntry>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .redo()
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn entity_history(
+    kind: EntityKind,
+    id: i64,
+    field: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .entity_history(kind, id, field.as_deref())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn field_changed_to(
+    kind: EntityKind,
+    id: i64,
+    field: String,
+    value: serde_json::Value,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Option<AuditEntry>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .changed_to(kind, id, &field, value)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn search(
+    query: String,
+    page: Option<PageRequest>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Page<SearchHit>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .search(&query, &page.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn filter_tasks(
+    filter: String,
+    scope: Option<Scope>,
+    page: Option<PageRequest>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<FilterPage, FilterError> {
+    app_state.database_manager.lock().unwrap().filter_tasks(
+        &filter,
+        scope.unwrap_or_default(),
+        &page.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+fn today(
+    weights: Option<UrgencyWeights>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<DashboardEntry>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .today(&weights.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Lead time of completed tasks, from creation to completion.
+#[tauri::command]
+fn lead_time(
+    report: Option<ReportFilter>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Distribution, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .lead_time(&report.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Cycle time of completed tasks, from work start to completion.
+#[tauri::command]
+fn cycle_time(
+    report: Option<ReportFilter>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Distribution, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .cycle_time(&report.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Tasks completed per week.
+#[tauri::command]
+fn throughput(
+    report: Option<ReportFilter>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<WeekCount>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .throughput(&report.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Age of tasks in progress.
+#[tauri::command]
+fn wip_age(
+    report: Option<ReportFilter>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Distribution, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .wip_age(&report.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Burndown series of a sprint or milestone, ready to chart.
+#[tauri::command]
+fn burndown(
+    request: BurndownRequest,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Burndown, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .burndown(&request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Burndown series of a sprint or milestone as CSV.
+#[tauri::command]
+fn burndown_csv(
+    request: BurndownRequest,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .burndown(&request)
+        .map(|series| series.to_csv())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Whole workspace as a versioned JSON file.
+#[tauri::command]
+fn export_workspace(app_state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let workspace = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .export_workspace()
+        .map_err(|_| String::from("Transaction error!"))?;
+
+    serde_json::to_string_pretty(&workspace).map_err(|err| err.to_string())
+}
+
+/// Import a workspace file made by [`export_workspace`].
+#[tauri::command]
+fn import_workspace(
+    contents: String,
+    mode: Option<ImportMode>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<ImportSummary, ImportError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_workspace(&contents, mode.unwrap_or_default())
+}
+
+/// Tasks as CSV, with the chosen columns.
+#[tauri::command]
+fn export_tasks_csv(
+    request: Option<CsvExport>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, FilterError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .export_tasks_csv(&request.unwrap_or_default())
+}
+
+/// Headers and first rows of a CSV file, with a suggested column mapping.
+#[tauri::command]
+fn preview_tasks_csv(contents: String) -> Result<CsvPreview, RowError> {
+    tasks_csv::preview(&contents)
+}
+
+#[tauri::command]
+fn import_tasks_csv(
+    contents: String,
+    request: CsvImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<CsvImportReport, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_tasks_csv(&contents, &request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Board or group as Markdown, also written to `path` when given.
+#[tauri::command]
+fn export_markdown(
+    kind: EntityKind,
+    id: i64,
+    options: Option<MarkdownOptions>,
+    path: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let markdown = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .render_markdown(kind, id, &options.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))?;
+    if let Some(path) = path {
+        std::fs::write(path, &markdown).map_err(|err| err.to_string())?;
+    }
+
+    Ok(markdown)
+}
+
+/// Import a Trello board JSON export into `group_id`.
+#[tauri::command]
+fn import_trello(
+    contents: String,
+    group_id: i64,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<TrelloSummary, ImportError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_trello(&contents, group_id)
+}
+
+/// Import GitHub issues from a `gh issue list --json` dump.
+#[tauri::command]
+fn import_github(
+    contents: String,
+    request: GitHubImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<GitHubSummary, ImportError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_github(&contents, &request)
+}
+
+/// Jira statuses and priorities of a CSV export, mapped to the board
+/// ones when they match, for the user to edit before importing.
+#[tauri::command]
+fn preview_jira(
+    contents: String,
+    request: JiraImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<JiraMapping, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .preview_jira(&contents, &request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn import_jira(
+    contents: String,
+    request: JiraImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<JiraReport, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_jira(&contents, &request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Board as todo.txt lines, also written to `path` when given.
+#[tauri::command]
+fn export_todo_txt(
+    board_id: i64,
+    path: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let todo = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .export_todo_txt(board_id)
+        .map_err(|_| String::from("Transaction error!"))?;
+    if let Some(path) = path {
+        std::fs::write(path, &todo).map_err(|err| err.to_string())?;
+    }
+
+    Ok(todo)
+}
+
+#[tauri::command]
+fn import_todo_txt(
+    contents: String,
+    request: TodoTxtImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<TodoTxtReport, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_todo_txt(&contents, &request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+/// Tasks as a JSON file for `task import`, also written to `path` when
+/// given.
+#[tauri::command]
+fn export_taskwarrior(
+    options: Option<TaskwarriorExport>,
+    path: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let json = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .export_taskwarrior(&options.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))?;
+    if let Some(path) = path {
+        std::fs::write(path, &json).map_err(|err| err.to_string())?;
+    }
+
+    Ok(json)
+}
+
+/// Import a `task export` dump, one board per project.
+#[tauri::command]
+fn import_taskwarrior(
+    contents: String,
+    request: TaskwarriorImport,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<TaskwarriorSummary, ImportError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .import_taskwarrior(&contents, &request)
+}
+
+/// Due dates, milestones and time logs as an iCalendar file, also
+/// written to `path` when given.
+#[tauri::command]
+fn export_ics(
+    options: Option<IcsOptions>,
+    path: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let ics = app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .export_ics(&options.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))?;
+    if let Some(path) = path {
+        std::fs::write(path, &ics).map_err(|err| err.to_string())?;
+    }
+
+    Ok(ics)
+}
+
+/// Keep an iCalendar file on disk up to date, or stop with no feed. The
+/// feed is saved, and kept on next starts.
+#[tauri::command]
+fn set_calendar_feed(
+    feed: Option<CalendarFeed>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    CalendarFeed::save(feed.as_ref(), CALENDAR_FEED).map_err(|err| err.to_string())?;
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .set_calendar_feed(feed)
+}
+
+/// Why the calendar feed could not be written on last change, `None`
+/// when it was.
+#[tauri::command]
+fn calendar_feed_error(app_state: tauri::State<'_, AppState>) -> Option<String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .get_calendar_feed_error()
+        .clone()
+}
+
+#[tauri::command]
+fn get_backup_config(app_state: tauri::State<'_, AppState>) -> BackupConfig {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .get_backup_config()
+        .clone()
+}
+
+/// Save where backups go and how many are kept, used from next backup on.
+#[tauri::command]
+fn set_backup_config(
+    config: BackupConfig,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    config.save(BACKUP_CONFIG).map_err(|err| err.to_string())?;
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .set_backup_config(config);
+
+    Ok(())
+}
+
+#[tauri::command]
+fn create_backup(app_state: tauri::State<'_, AppState>) -> Result<Option<BackupInfo>, BackupError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .backup(BackupReason::Manual)
+}
+
+#[tauri::command]
+fn list_backups(app_state: tauri::State<'_, AppState>) -> Result<Vec<BackupInfo>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .list_backups()
+        .map_err(|err| err.to_string())
+}
+
+/// Why the last backup failed, startup and scheduled ones included, or
+/// `None` when it succeeded.
+#[tauri::command]
+fn last_backup_error(app_state: tauri::State<'_, AppState>) -> Option<BackupError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .get_last_backup_error()
+        .clone()
+}
+
+/// Replace the database with a backup, once validated.
+#[tauri::command]
+fn restore_backup(file: String, app_state: tauri::State<'_, AppState>) -> Result<(), BackupError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .restore_backup(&file)
+}
+
+/// Cumulative flow diagram of a board, ready to chart.
+#[tauri::command]
+fn cumulative_flow(
+    request: FlowRequest,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<CumulativeFlow, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .cumulative_flow(&request)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn create_view(
+    new_view: SavedView,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<SavedView, FilterError> {
+    filter::parse(new_view.get_filter())?;
+    create_entity(new_view, app_state).map_err(|message| FilterError::Database { message })
+}
+
+#[tauri::command]
+fn update_view(
+    view: SavedView,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<SavedView, FilterError> {
+    filter::parse(view.get_filter())?;
+    update_entity(view, app_state).map_err(|message| FilterError::Database { message })
+}
+
+#[tauri::command]
+fn delete_view(view_id: i64, app_state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .delete_view(view_id)
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn load_views(
+    pinned_only: Option<bool>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<SavedView>, String> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .load_views(pinned_only.unwrap_or_default())
+        .map_err(|_| String::from("Transaction error!"))
+}
+
+#[tauri::command]
+fn evaluate_view(
+    view_id: i64,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<ViewResult, FilterError> {
+    app_state
+        .database_manager
+        .lock()
+        .unwrap()
+        .evaluate_view(view_id, limit, cursor)
+}
+
+struct AppState {
+    database_manager: Mutex<Manager>,
+}
+
+/// Where the backup config is saved, readable before the database opens.
+const BACKUP_CONFIG: &str = "./.my-board-backup.json";
+
+/// Where the calendar feed is saved, to keep it across starts.
+const CALENDAR_FEED: &str = "./.my-board-calendar.json";
+
+/// Time between checks for a due scheduled backup.
+const BACKUP_CHECK: Duration = Duration::from_secs(10 * 60);
+
+fn main() {
+    let mut connection =
+        Manager::new(String::from("./.my-board-db.db")).expect("Could not open database");
+    connection.set_backup_config(BackupConfig::load(BACKUP_CONFIG));
+    let migration = connection
+        .backup_before_migrations()
+        .expect("Could not back up database before migrating it");
+    if migration.is_none() {
+        // a failure is kept for views, read through `last_backup_error`.
+        connection.backup(BackupReason::Startup).ok();
+    }
+    connection
+        .create_tables()
+        .expect("Error trying create database!\n:-(");
+    // a failure is kept for views, read through `calendar_feed_error`.
+    connection
+        .set_calendar_feed(CalendarFeed::load(CALENDAR_FEED))
+        .ok();
+    let state = AppState {
+        database_manager: Mutex::new(connection),
+    };
+
+    tauri::Builder::default()
+        .manage(state)
+        .setup(|app| {
+            let handle = app.handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(BACKUP_CHECK);
+                let app_state = handle.state::<AppState>();
+                // a panic in a command must not stop backups, which only read.
+                let mut manager = app_state
+                    .database_manager
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                // a failure is kept for views, read through `last_backup_error`.
+                manager.backup_if_due().ok();
+            });
+
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             create_group,
@@ -123,7 +1156,81 @@ fn main() {
             create_state,
             update_state,
             create_task,
-            update_task
+            update_task,
+            parse_duration,
+            format_duration,
+            create_priority,
+            update_priority,
+            create_priority_scheme,
+            delete_priority,
+            load_priorities,
+            create_checklist_item,
+            update_checklist_item,
+            delete_checklist_item,
+            load_checklist,
+            create_time_log,
+            update_time_log,
+            delete_time_log,
+            load_time_logs,
+            create_milestone,
+            update_milestone,
+            delete_milestone,
+            load_milestones,
+            progress_rollup,
+            duplicate_board,
+            duplicate_group,
+            load_groups,
+            load_board,
+            load_tasks,
+            load_state_tasks,
+            archive_entity,
+            list_archived,
+            restore_entity,
+            purge_entity,
+            move_task,
+            set_origin,
+            undo,
+            redo,
+            entity_history,
+            field_changed_to,
+            search,
+            filter_tasks,
+            today,
+            lead_time,
+            cycle_time,
+            throughput,
+            wip_age,
+            burndown,
+            burndown_csv,
+            cumulative_flow,
+            export_workspace,
+            import_workspace,
+            export_tasks_csv,
+            preview_tasks_csv,
+            import_tasks_csv,
+            export_markdown,
+            import_trello,
+            import_github,
+            preview_jira,
+            import_jira,
+            export_todo_txt,
+            import_todo_txt,
+            export_taskwarrior,
+            import_taskwarrior,
+            export_ics,
+            set_calendar_feed,
+            calendar_feed_error,
+            get_backup_config,
+            set_backup_config,
+            create_backup,
+            list_backups,
+            last_backup_error,
+            restore_backup,
+            create_view,
+            update_view,
+            delete_view,
+            load_views,
+            evaluate_view
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");